@@ -4,7 +4,8 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use astroport_lbp::factory::{
-    ConfigResponse, ExecuteMsg, FactoryPairInfo, InstantiateMsg, PairsResponse, QueryMsg,
+    ConfigResponse, ExecuteMsg, FactoryPairInfo, GraduatedPairResponse, InstantiateMsg,
+    PairsResponse, QueryMsg,
 };
 
 fn main() {
@@ -19,4 +20,5 @@ fn main() {
     export_schema(&schema_for!(FactoryPairInfo), &out_dir);
     export_schema(&schema_for!(PairsResponse), &out_dir);
     export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(GraduatedPairResponse), &out_dir);
 }
@@ -1,14 +1,16 @@
 use cosmwasm_std::{
-    attr, entry_point, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Reply, ReplyOn,
-    Response, StdError, StdResult, SubMsg, WasmMsg,
+    attr, entry_point, to_binary, Addr, Binary, Decimal, Deps, DepsMut, Env, Event, MessageInfo,
+    Reply, ReplyOn, Response, StdResult, SubMsg, WasmMsg,
 };
 use cw2::set_contract_version;
 use protobuf::Message;
 
-use astroport_lbp::asset::{AssetInfo, PairInfo, WeightedAssetInfo};
+use astroport_lbp::asset::{
+    AssetInfo, LiquidityPolicy, PairInfo, SwapDirection, WeightedAssetInfo,
+};
 use astroport_lbp::factory::{
-    ConfigResponse, ExecuteMsg, FactoryPairInfo, InstantiateMsg, MigrateMsg, PairsResponse,
-    QueryMsg,
+    ConfigResponse, ExecuteMsg, FactoryPairInfo, GraduatedPairResponse, InstantiateMsg, MigrateMsg,
+    PairsResponse, QueryMsg,
 };
 use astroport_lbp::pair::InstantiateMsg as PairInstantiateMsg;
 
@@ -16,7 +18,8 @@ use crate::error::ContractError;
 use crate::querier::query_pair_info;
 use crate::response::MsgInstantiateContractResponse;
 use crate::state::{
-    pair_key, read_pair, read_pairs, Config, TmpPairInfo, CONFIG, PAIRS, TMP_PAIR_INFO,
+    pair_key, read_pair, read_pairs, Config, TmpPairInfo, CONFIG, GRADUATED_PAIRS, PAIRS,
+    TMP_PAIR_INFO,
 };
 
 // version info for migration info
@@ -32,12 +35,23 @@ pub fn instantiate(
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     let owner = deps.api.addr_validate(&msg.owner)?;
+    let fee_address = msg
+        .fee_address
+        .map(|fee_address| deps.api.addr_validate(&fee_address))
+        .transpose()?;
 
     let config = Config {
         owner,
         token_code_id: msg.token_code_id,
         pair_code_id: msg.pair_code_id,
+        min_swap_fee: msg.min_swap_fee,
+        max_swap_fee: msg.max_swap_fee,
+        fee_address,
+        protocol_fee_share: msg.protocol_fee_share,
+        xyk_pair_code_id: msg.xyk_pair_code_id,
     };
+    assert_swap_fee_bounds(&config)?;
+    assert_protocol_fee_share(&config)?;
     CONFIG.save(deps.storage, &config)?;
 
     Ok(Response::new())
@@ -55,12 +69,33 @@ pub fn execute(
             owner,
             token_code_id,
             pair_code_id,
-        } => try_update_config(deps, info, owner, token_code_id, pair_code_id),
+            min_swap_fee,
+            max_swap_fee,
+            fee_address,
+            protocol_fee_share,
+            xyk_pair_code_id,
+        } => try_update_config(
+            deps,
+            info,
+            owner,
+            token_code_id,
+            pair_code_id,
+            min_swap_fee,
+            max_swap_fee,
+            fee_address,
+            protocol_fee_share,
+            xyk_pair_code_id,
+        ),
         ExecuteMsg::CreatePair {
             asset_infos,
             start_time,
             end_time,
             description,
+            liquidity_policy,
+            swap_direction,
+            swap_fee,
+            max_in_ratio,
+            max_out_ratio,
         } => try_create_pair(
             deps,
             env,
@@ -69,11 +104,21 @@ pub fn execute(
             start_time,
             end_time,
             description,
+            liquidity_policy,
+            swap_direction,
+            swap_fee,
+            max_in_ratio,
+            max_out_ratio,
         ),
         ExecuteMsg::Unregister { asset_infos } => try_unregister(deps, env, info, asset_infos),
+        ExecuteMsg::RegisterGraduatedPair {
+            asset_infos,
+            graduated_pair,
+        } => try_register_graduated_pair(deps, info, asset_infos, graduated_pair),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 // Only owner can execute it
 pub fn try_update_config(
     deps: DepsMut,
@@ -81,6 +126,11 @@ pub fn try_update_config(
     owner: Option<Addr>,
     token_code_id: Option<u64>,
     pair_code_id: Option<u64>,
+    min_swap_fee: Option<Decimal>,
+    max_swap_fee: Option<Decimal>,
+    fee_address: Option<String>,
+    protocol_fee_share: Option<Decimal>,
+    xyk_pair_code_id: Option<u64>,
 ) -> Result<Response, ContractError> {
     let mut config: Config = CONFIG.load(deps.storage)?;
 
@@ -97,6 +147,23 @@ pub fn try_update_config(
     if let Some(pair_code_id) = pair_code_id {
         config.pair_code_id = pair_code_id;
     }
+    if let Some(min_swap_fee) = min_swap_fee {
+        config.min_swap_fee = min_swap_fee;
+    }
+    if let Some(max_swap_fee) = max_swap_fee {
+        config.max_swap_fee = max_swap_fee;
+    }
+    if let Some(fee_address) = fee_address {
+        config.fee_address = Some(deps.api.addr_validate(&fee_address)?);
+    }
+    if let Some(protocol_fee_share) = protocol_fee_share {
+        config.protocol_fee_share = protocol_fee_share;
+    }
+    if let Some(xyk_pair_code_id) = xyk_pair_code_id {
+        config.xyk_pair_code_id = Some(xyk_pair_code_id);
+    }
+    assert_swap_fee_bounds(&config)?;
+    assert_protocol_fee_share(&config)?;
     CONFIG.save(deps.storage, &config)?;
     Ok(Response::new().add_attribute("action", "update_config"))
 }
@@ -107,28 +174,42 @@ pub fn try_create_pair(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    weighted_asset_infos: [WeightedAssetInfo; 2],
+    weighted_asset_infos: Vec<WeightedAssetInfo>,
     start_time: u64,
     end_time: u64,
     description: Option<String>,
+    liquidity_policy: Option<LiquidityPolicy>,
+    swap_direction: Option<SwapDirection>,
+    swap_fee: Decimal,
+    max_in_ratio: Option<Decimal>,
+    max_out_ratio: Option<Decimal>,
 ) -> Result<Response, ContractError> {
     let config: Config = CONFIG.load(deps.storage)?;
 
-    let asset_infos = [
-        weighted_asset_infos[0].info.clone(),
-        weighted_asset_infos[1].info.clone(),
-    ];
+    if swap_fee < config.min_swap_fee || swap_fee > config.max_swap_fee {
+        return Err(ContractError::SwapFeeOutOfBounds {
+            min_swap_fee: config.min_swap_fee,
+            max_swap_fee: config.max_swap_fee,
+        });
+    }
+
+    let asset_infos: Vec<AssetInfo> = weighted_asset_infos
+        .iter()
+        .map(|asset_info| asset_info.info.clone())
+        .collect();
     if read_pair(deps.as_ref(), &asset_infos).is_ok() {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Pair already exists",
-        )));
+        return Err(ContractError::PairExists {});
     }
     let pair_key = pair_key(&asset_infos);
     TMP_PAIR_INFO.save(
         deps.storage,
         &TmpPairInfo {
             pair_key,
-            owner: info.sender,
+            owner: info.sender.clone(),
+            asset_infos: weighted_asset_infos.clone(),
+            start_time,
+            end_time,
+            swap_fee,
         },
     )?;
 
@@ -144,6 +225,12 @@ pub fn try_create_pair(
                     start_time,
                     end_time,
                     description,
+                    owner: info.sender.to_string(),
+                    liquidity_policy,
+                    swap_direction,
+                    swap_fee,
+                    max_in_ratio,
+                    max_out_ratio,
                 })?,
                 funds: vec![],
                 label: "Astroport pair".to_string(),
@@ -154,7 +241,7 @@ pub fn try_create_pair(
         })
         .add_attributes(vec![
             attr("action", "create_pair"),
-            attr("pair", format!("{}-{}", asset_infos[0], asset_infos[1])),
+            attr("pair", pair_name(&asset_infos)),
         ]))
 }
 
@@ -165,11 +252,14 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
         return Err(ContractError::PairWasRegistered {});
     }
 
-    let data = msg.result.unwrap().data.unwrap();
+    let data = msg
+        .result
+        .into_result()
+        .ok()
+        .and_then(|res| res.data)
+        .ok_or(ContractError::InvalidReply {})?;
     let res: MsgInstantiateContractResponse =
-        Message::parse_from_bytes(data.as_slice()).map_err(|_| {
-            StdError::parse_err("MsgInstantiateContractResponse", "failed to parse data")
-        })?;
+        Message::parse_from_bytes(data.as_slice()).map_err(|_| ContractError::InvalidReply {})?;
 
     let pair_contract = deps.api.addr_validate(res.get_contract_address())?;
 
@@ -177,12 +267,32 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
         deps.storage,
         &tmp.pair_key,
         &FactoryPairInfo {
-            owner: tmp.owner,
+            owner: tmp.owner.clone(),
             contract_addr: pair_contract.clone(),
         },
     )?;
 
-    Ok(Response::new().add_attributes(vec![("pair_contract_addr", pair_contract)]))
+    let event = tmp.asset_infos.iter().enumerate().fold(
+        Event::new("lbp_sale_created")
+            .add_attribute("pair_contract_addr", pair_contract.to_string())
+            .add_attribute("owner", tmp.owner.to_string())
+            .add_attribute("start_time", tmp.start_time.to_string())
+            .add_attribute("end_time", tmp.end_time.to_string())
+            .add_attribute("swap_fee", tmp.swap_fee.to_string()),
+        |event, (i, asset)| {
+            event
+                .add_attribute(format!("asset_{}", i), asset.info.to_string())
+                .add_attribute(
+                    format!("start_weight_{}", i),
+                    asset.start_weight.to_string(),
+                )
+                .add_attribute(format!("end_weight_{}", i), asset.end_weight.to_string())
+        },
+    );
+
+    Ok(Response::new()
+        .add_attributes(vec![("pair_contract_addr", pair_contract)])
+        .add_event(event))
 }
 
 /// remove from list of pairs
@@ -190,20 +300,45 @@ pub fn try_unregister(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    asset_infos: [AssetInfo; 2],
+    asset_infos: Vec<AssetInfo>,
 ) -> Result<Response, ContractError> {
     let pair_info: FactoryPairInfo = read_pair(deps.as_ref(), &asset_infos)?;
 
-    // Permission check
-    if pair_info.owner != info.sender {
+    // Permission check, a cancelled sale unregisters its pair itself
+    if pair_info.owner != info.sender && pair_info.contract_addr != info.sender {
         return Err(ContractError::Unauthorized {});
     }
 
     PAIRS.remove(deps.storage, &pair_key(&asset_infos));
+    GRADUATED_PAIRS.remove(deps.storage, &pair_key(&asset_infos));
 
     Ok(Response::new().add_attributes(vec![
         attr("action", "unregister"),
-        attr("pair", format!("{}-{}", asset_infos[0], asset_infos[1])),
+        attr("pair", pair_name(&asset_infos)),
+    ]))
+}
+
+/// record the constant product pair a finished sale graduated to
+pub fn try_register_graduated_pair(
+    deps: DepsMut,
+    info: MessageInfo,
+    asset_infos: Vec<AssetInfo>,
+    graduated_pair: String,
+) -> Result<Response, ContractError> {
+    let pair_info: FactoryPairInfo = read_pair(deps.as_ref(), &asset_infos)?;
+
+    // Permission check
+    if pair_info.contract_addr != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let graduated_pair = deps.api.addr_validate(&graduated_pair)?;
+    GRADUATED_PAIRS.save(deps.storage, &pair_key(&asset_infos), &graduated_pair)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "register_graduated_pair"),
+        attr("pair", pair_name(&asset_infos)),
+        attr("graduated_pair", graduated_pair),
     ]))
 }
 
@@ -215,6 +350,9 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Pairs { start_after, limit } => {
             to_binary(&query_pairs(deps, start_after, limit)?)
         }
+        QueryMsg::GraduatedPair { asset_infos } => {
+            to_binary(&query_graduated_pair(deps, asset_infos)?)
+        }
     }
 }
 
@@ -224,12 +362,17 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         owner: state.owner.clone(),
         token_code_id: state.token_code_id,
         pair_code_id: state.pair_code_id,
+        min_swap_fee: state.min_swap_fee,
+        max_swap_fee: state.max_swap_fee,
+        fee_address: state.fee_address,
+        protocol_fee_share: state.protocol_fee_share,
+        xyk_pair_code_id: state.xyk_pair_code_id,
     };
 
     Ok(resp)
 }
 
-pub fn query_pair(deps: Deps, asset_infos: [AssetInfo; 2]) -> StdResult<PairInfo> {
+pub fn query_pair(deps: Deps, asset_infos: Vec<AssetInfo>) -> StdResult<PairInfo> {
     let pair_addr = PAIRS
         .load(deps.storage, &pair_key(&asset_infos))?
         .contract_addr;
@@ -239,20 +382,56 @@ pub fn query_pair(deps: Deps, asset_infos: [AssetInfo; 2]) -> StdResult<PairInfo
 
 pub fn query_pairs(
     deps: Deps,
-    start_after: Option<[AssetInfo; 2]>,
+    start_after: Option<Vec<AssetInfo>>,
     limit: Option<u32>,
 ) -> StdResult<PairsResponse> {
-    let start_after =
-        start_after.map(|start_after| [start_after[0].clone(), start_after[1].clone()]);
-
-    let pairs: Vec<PairInfo> = read_pairs(deps, start_after, limit)
+    let pairs: Vec<PairInfo> = read_pairs(deps, start_after, limit)?
         .iter()
-        .map(|pair| query_pair_info(deps, &pair.contract_addr).unwrap())
-        .collect();
+        .map(|pair| query_pair_info(deps, &pair.contract_addr))
+        .collect::<StdResult<Vec<PairInfo>>>()?;
 
     Ok(PairsResponse { pairs })
 }
 
+pub fn query_graduated_pair(
+    deps: Deps,
+    asset_infos: Vec<AssetInfo>,
+) -> StdResult<GraduatedPairResponse> {
+    let pair_key = pair_key(&asset_infos);
+    let lbp_pair = PAIRS.load(deps.storage, &pair_key)?.contract_addr;
+    let graduated_pair = GRADUATED_PAIRS.load(deps.storage, &pair_key)?;
+
+    Ok(GraduatedPairResponse {
+        lbp_pair,
+        graduated_pair,
+    })
+}
+
+/// Joins the asset infos with dashes, e.g. `asset0000-uusd`
+fn pair_name(asset_infos: &[AssetInfo]) -> String {
+    asset_infos
+        .iter()
+        .map(|asset_info| asset_info.to_string())
+        .collect::<Vec<String>>()
+        .join("-")
+}
+
+fn assert_swap_fee_bounds(config: &Config) -> Result<(), ContractError> {
+    if config.min_swap_fee > config.max_swap_fee || config.max_swap_fee >= Decimal::one() {
+        return Err(ContractError::InvalidSwapFeeBounds {});
+    }
+
+    Ok(())
+}
+
+fn assert_protocol_fee_share(config: &Config) -> Result<(), ContractError> {
+    if config.protocol_fee_share > Decimal::one() {
+        return Err(ContractError::InvalidProtocolFeeShare {});
+    }
+
+    Ok(())
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
     Ok(Response::default())
@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Decimal, StdError};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -11,4 +11,25 @@ pub enum ContractError {
 
     #[error("Pair was already registered")]
     PairWasRegistered {},
+
+    #[error("Pair already exists")]
+    PairExists {},
+
+    #[error("Pair not found")]
+    PairNotFound {},
+
+    #[error("swap_fee must be between {min_swap_fee} and {max_swap_fee}")]
+    SwapFeeOutOfBounds {
+        min_swap_fee: Decimal,
+        max_swap_fee: Decimal,
+    },
+
+    #[error("min_swap_fee must not exceed max_swap_fee and max_swap_fee must be less than 1")]
+    InvalidSwapFeeBounds {},
+
+    #[error("protocol_fee_share must not exceed 1")]
+    InvalidProtocolFeeShare {},
+
+    #[error("Failed to parse the reply")]
+    InvalidReply {},
 }
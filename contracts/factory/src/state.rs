@@ -1,10 +1,10 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Deps, Order, StdError};
+use cosmwasm_std::{Addr, Decimal, Deps, Order, StdResult};
 
 use crate::error::ContractError;
-use astroport_lbp::asset::AssetInfo;
+use astroport_lbp::asset::{AssetInfo, WeightedAssetInfo};
 use astroport_lbp::factory::FactoryPairInfo;
 use cw_storage_plus::{Bound, Item, Map};
 
@@ -13,32 +13,44 @@ pub struct Config {
     pub owner: Addr,
     pub pair_code_id: u64,
     pub token_code_id: u64,
+    pub min_swap_fee: Decimal,
+    pub max_swap_fee: Decimal,
+    pub fee_address: Option<Addr>,
+    pub protocol_fee_share: Decimal,
+    pub xyk_pair_code_id: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct TmpPairInfo {
     pub pair_key: Vec<u8>,
     pub owner: Addr,
+    pub asset_infos: Vec<WeightedAssetInfo>,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub swap_fee: Decimal,
 }
 
 pub const TMP_PAIR_INFO: Item<TmpPairInfo> = Item::new("tmp_pair_info");
 
 pub const CONFIG: Item<Config> = Item::new("config");
 pub const PAIRS: Map<&[u8], FactoryPairInfo> = Map::new("pair_info");
+/// Constant product pairs the finished sales graduated to, keyed by the sale pair key
+pub const GRADUATED_PAIRS: Map<&[u8], Addr> = Map::new("graduated_pairs");
 
-pub fn pair_key(asset_infos: &[AssetInfo; 2]) -> Vec<u8> {
+pub fn pair_key(asset_infos: &[AssetInfo]) -> Vec<u8> {
     let mut asset_infos = asset_infos.to_vec();
     asset_infos.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
-    [asset_infos[0].as_bytes(), asset_infos[1].as_bytes()].concat()
+    asset_infos
+        .iter()
+        .map(|asset_info| asset_info.as_bytes())
+        .collect::<Vec<&[u8]>>()
+        .concat()
 }
 
-pub fn read_pair(
-    deps: Deps,
-    asset_infos: &[AssetInfo; 2],
-) -> Result<FactoryPairInfo, ContractError> {
-    match PAIRS.load(deps.storage, &pair_key(&asset_infos.clone())) {
+pub fn read_pair(deps: Deps, asset_infos: &[AssetInfo]) -> Result<FactoryPairInfo, ContractError> {
+    match PAIRS.load(deps.storage, &pair_key(asset_infos)) {
         Ok(v) => Ok(v),
-        Err(_e) => Err(StdError::generic_err("no pair data stored").into()),
+        Err(_e) => Err(ContractError::PairNotFound {}),
     }
 }
 
@@ -47,31 +59,25 @@ const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
 pub fn read_pairs(
     deps: Deps,
-    start_after: Option<[AssetInfo; 2]>,
+    start_after: Option<Vec<AssetInfo>>,
     limit: Option<u32>,
-) -> Vec<FactoryPairInfo> {
+) -> StdResult<Vec<FactoryPairInfo>> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = calc_range_start(start_after).map(Bound::exclusive);
     PAIRS
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|item| {
-            let (_, pair_info) = item.unwrap();
-            pair_info
+            let (_, pair_info) = item?;
+            Ok(pair_info)
         })
         .collect()
 }
 
 // this will set the first key after the provided key, by appending a 1 byte
-fn calc_range_start(start_after: Option<[AssetInfo; 2]>) -> Option<Vec<u8>> {
+fn calc_range_start(start_after: Option<Vec<AssetInfo>>) -> Option<Vec<u8>> {
     start_after.map(|asset_infos| {
-        let mut asset_infos = asset_infos.to_vec();
-        asset_infos.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
-
-        let mut v = [asset_infos[0].as_bytes(), asset_infos[1].as_bytes()]
-            .concat()
-            .as_slice()
-            .to_vec();
+        let mut v = pair_key(&asset_infos);
         v.push(1);
         v
     })
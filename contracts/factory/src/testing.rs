@@ -1,5 +1,5 @@
 use cosmwasm_std::{
-    attr, from_binary, to_binary, Addr, ContractResult, CosmosMsg, Reply, SubMsg,
+    attr, from_binary, to_binary, Addr, ContractResult, CosmosMsg, Decimal, Event, Reply, SubMsg,
     SubMsgExecutionResponse, Uint128, WasmMsg,
 };
 
@@ -9,8 +9,12 @@ use crate::mock_querier::mock_dependencies;
 
 use crate::state::{read_tmp_pair, CONFIG};
 
-use astroport_lbp::asset::{AssetInfo, PairInfo, WeightedAssetInfo};
-use astroport_lbp::factory::{ConfigResponse, ExecuteMsg, InstantiateMsg, PairsResponse, QueryMsg};
+use astroport_lbp::asset::{
+    AssetInfo, LiquidityPolicy, PairInfo, SwapDirection, WeightedAssetInfo,
+};
+use astroport_lbp::factory::{
+    ConfigResponse, ExecuteMsg, GraduatedPairResponse, InstantiateMsg, PairsResponse, QueryMsg,
+};
 use cosmwasm_std::testing::{mock_env, mock_info};
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -26,6 +30,11 @@ fn proper_initialization() {
         pair_code_id: 321u64,
         token_code_id: 123u64,
         owner: "owner0000".to_string(),
+        min_swap_fee: Decimal::zero(),
+        max_swap_fee: Decimal::percent(1),
+        fee_address: None,
+        protocol_fee_share: Decimal::zero(),
+        xyk_pair_code_id: None,
     };
 
     let env = mock_env();
@@ -49,6 +58,11 @@ fn update_config() {
         pair_code_id: 321u64,
         token_code_id: 123u64,
         owner: "owner0000".to_string(),
+        min_swap_fee: Decimal::zero(),
+        max_swap_fee: Decimal::percent(1),
+        fee_address: None,
+        protocol_fee_share: Decimal::zero(),
+        xyk_pair_code_id: None,
     };
 
     let env = mock_env();
@@ -64,6 +78,11 @@ fn update_config() {
         owner: Some(Addr::unchecked("addr0001")),
         pair_code_id: None,
         token_code_id: None,
+        min_swap_fee: None,
+        max_swap_fee: None,
+        fee_address: None,
+        protocol_fee_share: None,
+        xyk_pair_code_id: None,
     };
 
     let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
@@ -83,6 +102,11 @@ fn update_config() {
         owner: None,
         pair_code_id: Some(100u64),
         token_code_id: Some(200u64),
+        min_swap_fee: None,
+        max_swap_fee: None,
+        fee_address: None,
+        protocol_fee_share: None,
+        xyk_pair_code_id: Some(300u64),
     };
 
     let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
@@ -93,8 +117,64 @@ fn update_config() {
     let config_res: ConfigResponse = from_binary(&query_res).unwrap();
     assert_eq!(200u64, config_res.token_code_id);
     assert_eq!(100u64, config_res.pair_code_id);
+    assert_eq!(Some(300u64), config_res.xyk_pair_code_id);
     assert_eq!(Addr::unchecked("addr0001"), config_res.owner);
 
+    // update swap fee bounds and protocol fee
+    let env = mock_env();
+    let info = mock_info("addr0001", &[]);
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        pair_code_id: None,
+        token_code_id: None,
+        min_swap_fee: Some(Decimal::permille(1)),
+        max_swap_fee: Some(Decimal::percent(2)),
+        fee_address: Some(String::from("collector0000")),
+        protocol_fee_share: Some(Decimal::percent(50)),
+        xyk_pair_code_id: None,
+    };
+
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert_eq!(0, res.messages.len());
+
+    let query_res = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&query_res).unwrap();
+    assert_eq!(Decimal::permille(1), config_res.min_swap_fee);
+    assert_eq!(Decimal::percent(2), config_res.max_swap_fee);
+    assert_eq!(
+        Some(Addr::unchecked("collector0000")),
+        config_res.fee_address
+    );
+    assert_eq!(Decimal::percent(50), config_res.protocol_fee_share);
+
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        pair_code_id: None,
+        token_code_id: None,
+        min_swap_fee: None,
+        max_swap_fee: None,
+        fee_address: None,
+        protocol_fee_share: Some(Decimal::percent(101)),
+        xyk_pair_code_id: None,
+    };
+
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::InvalidProtocolFeeShare {});
+
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        pair_code_id: None,
+        token_code_id: None,
+        min_swap_fee: Some(Decimal::percent(3)),
+        max_swap_fee: None,
+        fee_address: None,
+        protocol_fee_share: None,
+        xyk_pair_code_id: None,
+    };
+
+    let res = execute(deps.as_mut(), env, info, msg);
+    assert_eq!(res.unwrap_err(), ContractError::InvalidSwapFeeBounds {});
+
     // Unauthorzied err
     let env = mock_env();
     let info = mock_info("addr0000", &[]);
@@ -102,6 +182,11 @@ fn update_config() {
         owner: None,
         pair_code_id: None,
         token_code_id: None,
+        min_swap_fee: None,
+        max_swap_fee: None,
+        fee_address: None,
+        protocol_fee_share: None,
+        xyk_pair_code_id: None,
     };
 
     let res = execute(deps.as_mut(), env, info, msg);
@@ -125,6 +210,11 @@ fn create_pair() {
         pair_code_id: 321u64,
         token_code_id: 123u64,
         owner: "owner0000".to_string(),
+        min_swap_fee: Decimal::zero(),
+        max_swap_fee: Decimal::percent(1),
+        fee_address: None,
+        protocol_fee_share: Decimal::zero(),
+        xyk_pair_code_id: None,
     };
 
     let env = mock_env();
@@ -133,13 +223,14 @@ fn create_pair() {
     // we can just call .unwrap() to assert this was a success
     let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
 
-    let asset_infos = [
+    let asset_infos = vec![
         WeightedAssetInfo {
             info: AssetInfo::Token {
                 contract_addr: Addr::unchecked("asset0000"),
             },
             start_weight: Uint128::new(30),
             end_weight: Uint128::new(20),
+            curve: None,
         },
         WeightedAssetInfo {
             info: AssetInfo::Token {
@@ -147,17 +238,46 @@ fn create_pair() {
             },
             start_weight: Uint128::new(30),
             end_weight: Uint128::new(20),
+            curve: None,
         },
     ];
 
+    // the swap fee must be within the factory bounds
     let msg = ExecuteMsg::CreatePair {
         asset_infos: asset_infos.clone(),
         start_time,
         end_time,
         description: Some(String::from("description")),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::percent(2),
+        max_in_ratio: None,
+        max_out_ratio: None,
     };
 
     let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::SwapFeeOutOfBounds {
+            min_swap_fee: Decimal::zero(),
+            max_swap_fee: Decimal::percent(1),
+        }
+    );
+
+    let msg = ExecuteMsg::CreatePair {
+        asset_infos: asset_infos.clone(),
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(15u128, 10000u128),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
     let info = mock_info("addr0000", &[]);
     let config = CONFIG.load(&deps.storage);
     let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
@@ -179,6 +299,12 @@ fn create_pair() {
                     start_time,
                     end_time,
                     description: Some(String::from("description")),
+                    owner: String::from("addr0000"),
+                    liquidity_policy: None,
+                    swap_direction: None,
+                    swap_fee: Decimal::from_ratio(15u128, 10000u128),
+                    max_in_ratio: None,
+                    max_out_ratio: None,
                 })
                 .unwrap(),
                 code_id: 321u64,
@@ -209,19 +335,25 @@ fn register() {
         pair_code_id: 321u64,
         token_code_id: 123u64,
         owner: "owner0000".to_string(),
+        min_swap_fee: Decimal::zero(),
+        max_swap_fee: Decimal::percent(1),
+        fee_address: None,
+        protocol_fee_share: Decimal::zero(),
+        xyk_pair_code_id: None,
     };
 
     let env = mock_env();
     let info = mock_info("addr0000", &[]);
     let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
 
-    let asset_infos = [
+    let asset_infos = vec![
         WeightedAssetInfo {
             info: AssetInfo::Token {
                 contract_addr: Addr::unchecked("asset0000"),
             },
             start_weight: Uint128::new(30),
             end_weight: Uint128::new(20),
+            curve: None,
         },
         WeightedAssetInfo {
             info: AssetInfo::Token {
@@ -229,6 +361,7 @@ fn register() {
             },
             start_weight: Uint128::new(30),
             end_weight: Uint128::new(20),
+            curve: None,
         },
     ];
 
@@ -237,6 +370,11 @@ fn register() {
         start_time,
         end_time,
         description: Some(String::from("description")),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(15u128, 10000u128),
+        max_in_ratio: None,
+        max_out_ratio: None,
     };
 
     let env = mock_env();
@@ -248,9 +386,18 @@ fn register() {
         asset_infos: asset_infos.clone(),
         contract_addr: Addr::unchecked("pair0000"),
         liquidity_token: Addr::unchecked("liquidity0000"),
+        owner: Addr::unchecked("addr0000"),
         start_time,
         end_time,
         description: Some(String::from("description")),
+        liquidity_policy: LiquidityPolicy::SaleOwner,
+        swap_direction: SwapDirection::Both,
+        paused: false,
+        swap_fee: Decimal::from_ratio(15u128, 10000u128),
+        max_in_ratio: Decimal::percent(30),
+        max_out_ratio: Decimal::percent(30),
+        closed_at: None,
+        cancelled: false,
     };
 
     let mut deployed_pairs = vec![(&pair0_addr, &pair0_info)];
@@ -275,13 +422,29 @@ fn register() {
         }),
     };
 
-    let _res = reply(deps.as_mut(), mock_env(), reply_msg.clone()).unwrap();
+    let res = reply(deps.as_mut(), mock_env(), reply_msg.clone()).unwrap();
+    assert_eq!(
+        res.events,
+        vec![Event::new("lbp_sale_created").add_attributes(vec![
+            attr("pair_contract_addr", "pair0000"),
+            attr("owner", "addr0000"),
+            attr("start_time", start_time.to_string()),
+            attr("end_time", end_time.to_string()),
+            attr("swap_fee", "0.0015"),
+            attr("asset_0", "asset0000"),
+            attr("start_weight_0", "30"),
+            attr("end_weight_0", "20"),
+            attr("asset_1", "asset0001"),
+            attr("start_weight_1", "30"),
+            attr("end_weight_1", "20"),
+        ])]
+    );
 
     let query_res = query(
         deps.as_ref(),
         env.clone(),
         QueryMsg::Pair {
-            asset_infos: [asset_infos[0].info.clone(), asset_infos[1].info.clone()],
+            asset_infos: vec![asset_infos[0].info.clone(), asset_infos[1].info.clone()],
         },
     )
     .unwrap();
@@ -291,11 +454,20 @@ fn register() {
         pair_res,
         PairInfo {
             liquidity_token: Addr::unchecked("liquidity0000"),
+            owner: Addr::unchecked("addr0000"),
             contract_addr: Addr::unchecked("pair0000"),
             asset_infos: asset_infos.clone(),
             start_time,
             end_time,
             description: Some(String::from("description")),
+            liquidity_policy: LiquidityPolicy::SaleOwner,
+            swap_direction: SwapDirection::Both,
+            paused: false,
+            swap_fee: Decimal::from_ratio(15u128, 10000u128),
+            max_in_ratio: Decimal::percent(30),
+            max_out_ratio: Decimal::percent(30),
+            closed_at: None,
+            cancelled: false,
         }
     );
 
@@ -303,13 +475,14 @@ fn register() {
     let res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap_err();
     assert_eq!(res, ContractError::PairWasRegistered {});
 
-    let asset_infos_2 = [
+    let asset_infos_2 = vec![
         WeightedAssetInfo {
             info: AssetInfo::Token {
                 contract_addr: Addr::unchecked("asset0000"),
             },
             start_weight: Uint128::new(30),
             end_weight: Uint128::new(20),
+            curve: None,
         },
         WeightedAssetInfo {
             info: AssetInfo::Token {
@@ -317,6 +490,7 @@ fn register() {
             },
             start_weight: Uint128::new(30),
             end_weight: Uint128::new(20),
+            curve: None,
         },
     ];
 
@@ -325,6 +499,11 @@ fn register() {
         start_time,
         end_time,
         description: Some(String::from("description")),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(15u128, 10000u128),
+        max_in_ratio: None,
+        max_out_ratio: None,
     };
 
     let env = mock_env();
@@ -337,9 +516,18 @@ fn register() {
         asset_infos: asset_infos_2.clone(),
         contract_addr: Addr::unchecked("pair0001"),
         liquidity_token: Addr::unchecked("liquidity0001"),
+        owner: Addr::unchecked("addr0000"),
         start_time,
         end_time,
         description: Some(String::from("description")),
+        liquidity_policy: LiquidityPolicy::SaleOwner,
+        swap_direction: SwapDirection::Both,
+        paused: false,
+        swap_fee: Decimal::from_ratio(15u128, 10000u128),
+        max_in_ratio: Decimal::percent(30),
+        max_out_ratio: Decimal::percent(30),
+        closed_at: None,
+        cancelled: false,
     };
 
     deployed_pairs.push((&pair1_addr, &pair1_info));
@@ -387,7 +575,10 @@ fn register() {
     assert_eq!(pairs_res.pairs, vec![pair0_info]);
 
     let query_msg = QueryMsg::Pairs {
-        start_after: Some([asset_infos[0].info.clone(), asset_infos[1].info.clone()]),
+        start_after: Some(vec![
+            asset_infos[0].info.clone(),
+            asset_infos[1].info.clone(),
+        ]),
         limit: None,
     };
 
@@ -397,7 +588,7 @@ fn register() {
 
     // try unregister
     let msg = ExecuteMsg::Unregister {
-        asset_infos: [
+        asset_infos: vec![
             AssetInfo::Token {
                 contract_addr: Addr::unchecked("asset0000"),
             },
@@ -435,8 +626,169 @@ fn register() {
         limit: None,
     };
 
-    let res = query(deps.as_ref(), env, query_msg).unwrap();
+    let res = query(deps.as_ref(), env, query_msg.clone()).unwrap();
     let pairs_res: PairsResponse = from_binary(&res).unwrap();
 
     assert_eq!(pairs_res.pairs, vec![pair1_info]);
+
+    // the pair itself can unregister when its sale is cancelled
+    let msg = ExecuteMsg::Unregister {
+        asset_infos: vec![
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0002"),
+            },
+        ],
+    };
+
+    let env = mock_env();
+    let info = mock_info("pair0001", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "unregister"),
+            attr("pair", "asset0000-asset0002")
+        ]
+    );
+
+    let res = query(deps.as_ref(), env, query_msg).unwrap();
+    let pairs_res: PairsResponse = from_binary(&res).unwrap();
+
+    assert_eq!(pairs_res.pairs, vec![]);
+}
+
+#[test]
+fn register_graduated_pair() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let end_time = start_time + 1000;
+
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        pair_code_id: 321u64,
+        token_code_id: 123u64,
+        owner: "owner0000".to_string(),
+        min_swap_fee: Decimal::zero(),
+        max_swap_fee: Decimal::percent(1),
+        fee_address: None,
+        protocol_fee_share: Decimal::zero(),
+        xyk_pair_code_id: Some(456u64),
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    let asset_infos = vec![
+        WeightedAssetInfo {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            start_weight: Uint128::new(30),
+            end_weight: Uint128::new(20),
+            curve: None,
+        },
+        WeightedAssetInfo {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0001"),
+            },
+            start_weight: Uint128::new(30),
+            end_weight: Uint128::new(20),
+            curve: None,
+        },
+    ];
+
+    let msg = ExecuteMsg::CreatePair {
+        asset_infos: asset_infos.clone(),
+        start_time,
+        end_time,
+        description: None,
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(15u128, 10000u128),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let data = MsgInstantiateContractResponse {
+        contract_address: String::from("pair0000"),
+        data: vec![],
+        unknown_fields: Default::default(),
+        cached_size: Default::default(),
+    }
+    .write_to_bytes()
+    .unwrap();
+
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(data.into()),
+        }),
+    };
+
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    let asset_infos = vec![asset_infos[0].info.clone(), asset_infos[1].info.clone()];
+    let msg = ExecuteMsg::RegisterGraduatedPair {
+        asset_infos: asset_infos.clone(),
+        graduated_pair: String::from("xyk0000"),
+    };
+
+    // only the registered pair can record its graduation
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg.clone());
+    match res {
+        Err(ContractError::Unauthorized { .. }) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let info = mock_info("pair0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "register_graduated_pair"),
+            attr("pair", "asset0000-asset0001"),
+            attr("graduated_pair", "xyk0000"),
+        ]
+    );
+
+    let query_res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::GraduatedPair {
+            asset_infos: asset_infos.clone(),
+        },
+    )
+    .unwrap();
+    let graduated_pair_res: GraduatedPairResponse = from_binary(&query_res).unwrap();
+    assert_eq!(
+        graduated_pair_res,
+        GraduatedPairResponse {
+            lbp_pair: Addr::unchecked("pair0000"),
+            graduated_pair: Addr::unchecked("xyk0000"),
+        }
+    );
+
+    // the graduation record is removed with the pair
+    let info = mock_info("addr0000", &[]);
+    let msg = ExecuteMsg::Unregister {
+        asset_infos: asset_infos.clone(),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let res = query(deps.as_ref(), env, QueryMsg::GraduatedPair { asset_infos });
+    assert!(res.is_err());
 }
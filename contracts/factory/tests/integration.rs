@@ -7,7 +7,7 @@ use astroport_lbp::asset::{AssetInfo, PairInfo, WeightedAssetInfo};
 use astroport_lbp::factory::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Decimal, Uint128};
 use terra_multi_test::{App, BankKeeper, ContractWrapper, Executor, TerraMockQuerier};
 
 fn mock_app() -> App {
@@ -75,6 +75,11 @@ fn create_and_register_pair_with_reply() {
         pair_code_id,
         token_code_id,
         owner: owner.to_string(),
+        min_swap_fee: Decimal::zero(),
+        max_swap_fee: Decimal::percent(1),
+        fee_address: None,
+        protocol_fee_share: Decimal::zero(),
+        xyk_pair_code_id: None,
     };
 
     // we can just call .unwrap() to assert this was a success
@@ -89,13 +94,14 @@ fn create_and_register_pair_with_reply() {
         )
         .unwrap();
 
-    let asset_infos = [
+    let asset_infos = vec![
         WeightedAssetInfo {
             info: AssetInfo::Token {
                 contract_addr: Addr::unchecked("asset0000"),
             },
             start_weight: Uint128::new(1),
             end_weight: Uint128::new(1),
+            curve: None,
         },
         WeightedAssetInfo {
             info: AssetInfo::Token {
@@ -103,6 +109,7 @@ fn create_and_register_pair_with_reply() {
             },
             start_weight: Uint128::new(1),
             end_weight: Uint128::new(1),
+            curve: None,
         },
     ];
 
@@ -111,6 +118,11 @@ fn create_and_register_pair_with_reply() {
         start_time,
         end_time,
         description: Some(String::from("description")),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(15u128, 10000u128),
+        max_in_ratio: None,
+        max_out_ratio: None,
     };
 
     app.execute_contract(
@@ -126,7 +138,7 @@ fn create_and_register_pair_with_reply() {
         .query_wasm_smart(
             factory_instance.clone(),
             &QueryMsg::Pair {
-                asset_infos: [asset_infos[0].info.clone(), asset_infos[1].info.clone()],
+                asset_infos: vec![asset_infos[0].info.clone(), asset_infos[1].info.clone()],
             },
         )
         .unwrap();
@@ -137,6 +149,7 @@ fn create_and_register_pair_with_reply() {
     assert_eq!(start_time, res.start_time);
     assert_eq!(end_time, res.end_time);
     assert_eq!(asset_infos, res.asset_infos);
+    assert_eq!("addr0000", res.owner.to_string());
 }
 
 #[test]
@@ -154,6 +167,11 @@ fn update_config() {
         pair_code_id,
         token_code_id,
         owner: owner.to_string(),
+        min_swap_fee: Decimal::zero(),
+        max_swap_fee: Decimal::percent(1),
+        fee_address: None,
+        protocol_fee_share: Decimal::zero(),
+        xyk_pair_code_id: None,
     };
 
     // we can just call .unwrap() to assert this was a success
@@ -6,8 +6,12 @@ use cosmwasm_std::Response;
 
 use astroport_lbp::asset::PairInfo;
 use astroport_lbp::pair::{
-    Cw20HookMsg, ExecuteMsg, InstantiateMsg, PoolResponse, QueryMsg, ReverseSimulationResponse,
-    SimulationResponse,
+    AllowanceResponse, ContributionResponse, CumulativePricesResponse, Cw20HookMsg,
+    EligibilityResponse, ExecuteMsg, GraduationResponse, HardCapResponse, InstantiateMsg,
+    PoolResponse, PriceCurveResponse, ProvideSingleSimulationResponse, QueryMsg,
+    ReverseSimulationResponse, SimulationResponse, SoftCapResponse, SpotPriceResponse,
+    StatsResponse, TwapResponse, VestingInfoResponse, WeightSchedulesResponse,
+    WithdrawSingleSimulationResponse,
 };
 
 fn main() {
@@ -25,4 +29,19 @@ fn main() {
     export_schema(&schema_for!(PoolResponse), &out_dir);
     export_schema(&schema_for!(ReverseSimulationResponse), &out_dir);
     export_schema(&schema_for!(SimulationResponse), &out_dir);
+    export_schema(&schema_for!(WeightSchedulesResponse), &out_dir);
+    export_schema(&schema_for!(StatsResponse), &out_dir);
+    export_schema(&schema_for!(EligibilityResponse), &out_dir);
+    export_schema(&schema_for!(AllowanceResponse), &out_dir);
+    export_schema(&schema_for!(CumulativePricesResponse), &out_dir);
+    export_schema(&schema_for!(TwapResponse), &out_dir);
+    export_schema(&schema_for!(SpotPriceResponse), &out_dir);
+    export_schema(&schema_for!(PriceCurveResponse), &out_dir);
+    export_schema(&schema_for!(GraduationResponse), &out_dir);
+    export_schema(&schema_for!(VestingInfoResponse), &out_dir);
+    export_schema(&schema_for!(SoftCapResponse), &out_dir);
+    export_schema(&schema_for!(ContributionResponse), &out_dir);
+    export_schema(&schema_for!(HardCapResponse), &out_dir);
+    export_schema(&schema_for!(ProvideSingleSimulationResponse), &out_dir);
+    export_schema(&schema_for!(WithdrawSingleSimulationResponse), &out_dir);
 }
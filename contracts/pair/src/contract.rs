@@ -1,85 +1,191 @@
-use crate::math::{calc_in_given_out, calc_out_given_in, uint2dec};
+use crate::math::{
+    calc_in_given_out, calc_out_given_in, calc_share_given_single_in, calc_single_out_given_share,
+    calc_spot_price, geometric_mean, half_pow, uint2dec,
+};
 use crate::response::MsgInstantiateContractResponse;
 
-use crate::state::PAIR_INFO;
+use crate::state::{
+    Contribution, OwnershipProposal, PriceCumulative, CONTRIBUTIONS, FACTORY_ADDR, GRADUATED_PAIR,
+    HARD_CAP, MERKLE_ROOT, OWNERSHIP_PROPOSAL, PAIR_INFO, PRICE_CUMULATIVES, PURCHASES,
+    PURCHASE_CAP, REFUNDABLE, SOFT_CAP, STATS, TMP_GRADUATION_ASSETS, VESTED_PURCHASES, VESTING,
+    VESTING_RESERVE, WEIGHT_SCHEDULES,
+};
 
-use astroport_lbp::U256;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
     attr, entry_point, from_binary, to_binary, Addr, Binary, Coin, CosmosMsg, Decimal, Deps,
-    DepsMut, Env, MessageInfo, Reply, ReplyOn, Response, StdError, StdResult, SubMsg, Uint128,
-    WasmMsg,
+    DepsMut, Env, Event, MessageInfo, Order, Reply, ReplyOn, Response, StdResult, Storage, SubMsg,
+    Uint128, WasmMsg,
 };
+use cw_storage_plus::{Bound, U64Key};
 
 use crate::error::ContractError;
 use cw2::set_contract_version;
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
 use protobuf::Message;
+use sha2::Digest;
+use std::cmp::Ordering;
 
-use astroport_lbp::asset::{Asset, AssetInfo, PairInfo, WeightedAsset};
+use astroport_lbp::asset::{
+    Asset, AssetInfo, LiquidityPolicy, PairInfo, SwapDirection, WeightCurve, WeightedAsset,
+};
+use astroport_lbp::factory::ExecuteMsg as FactoryExecuteMsg;
 use astroport_lbp::pair::{
-    Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, PoolResponse, QueryMsg,
-    ReverseSimulationResponse, SimulationResponse,
+    AllowanceResponse, AssetStats, ContributionResponse, CumulativePricesResponse, Cw20HookMsg,
+    EligibilityResponse, ExecuteMsg, GraduationResponse, HardCapResponse, InstantiateMsg,
+    MigrateMsg, PoolResponse, PriceCurveResponse, PricePoint, ProvideSingleSimulationResponse,
+    PurchaseCap, QueryMsg, ReverseSimulationResponse, SimulationResponse, SoftCapResponse,
+    SpotPriceResponse, StatsResponse, TwapResponse, VestingConfig, VestingInfoResponse,
+    WeightSchedule, WeightSchedulesResponse, WithdrawSingleSimulationResponse, XykPairExecuteMsg,
+    XykPairInstantiateMsg,
+};
+use astroport_lbp::querier::{
+    query_factory_config, query_factory_config_if_exists, query_supply, query_token_balance,
 };
-use astroport_lbp::querier::query_supply;
 use astroport_lbp::token::InstantiateMsg as TokenInstantiateMsg;
 use std::ops::{Add, Div, Mul, Sub};
 use std::str::FromStr;
 
-/// Commission rate == 0.15%
-pub const COMMISSION_RATE: &str = "0.0015";
-
 // version info for migration info
 const CONTRACT_NAME: &str = "astroport-lbp-pair";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 const INSTANTIATE_REPLY_ID: u64 = 1;
+const GRADUATION_REPLY_ID: u64 = 2;
+
+/// An ownership proposal can stay valid for 14 days at most
+const MAX_PROPOSAL_TTL: u64 = 1209600;
+
+/// Weights of an updated schedule are scaled up to keep the current price precise
+const WEIGHT_PRECISION: u128 = 1_000_000;
+
+/// The price curve query can sample up to 100 points
+const MAX_PRICE_CURVE_POINTS: u32 = 100;
+
+/// Price accumulators hold the sum of `price * seconds` with 18 decimals
+const PRICE_PRECISION: u128 = 1_000_000_000_000_000_000;
+
+/// Price records are kept at least 10 minutes apart, updates in between move the last record
+const PRICE_RECORD_INTERVAL: u64 = 600;
+
+/// Price records older than 7 days are pruned
+const PRICE_HISTORY_PERIOD: u64 = 604800;
+
+/// A sale below its soft cap can be refunded for 7 days after it ends, the liquidity is
+/// released afterwards even if some buyers did not return their tokens
+const REFUND_PERIOD: u64 = 604800;
+
+/// A pair holds from 2 to 8 assets
+const MIN_ASSETS: usize = 2;
+const MAX_ASSETS: usize = 8;
+
+/// By default a swap can take in or out 30% of the pool at most
+const DEFAULT_MAX_SWAP_RATIO: u64 = 30;
+
+/// A weight schedule update can move the price by 0.01% (1 basis point) at most
+const MAX_PRICE_DEVIATION_BPS: u64 = 1;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     // Check LBP parameters
     if msg.start_time < env.block.time.seconds() {
-        return Err(ContractError::Std(StdError::generic_err(
-            "start_time is less then current time",
-        )));
+        return Err(ContractError::InvalidStartTime {});
     }
 
     if msg.end_time <= msg.start_time {
-        return Err(ContractError::Std(StdError::generic_err(
-            "end_time is less then or same as start_time",
-        )));
+        return Err(ContractError::InvalidEndTime {});
     }
 
-    for asset in msg.asset_infos.iter() {
+    assert_swap_fee(msg.swap_fee)?;
+
+    let max_in_ratio = msg
+        .max_in_ratio
+        .unwrap_or_else(|| Decimal::percent(DEFAULT_MAX_SWAP_RATIO));
+    let max_out_ratio = msg
+        .max_out_ratio
+        .unwrap_or_else(|| Decimal::percent(DEFAULT_MAX_SWAP_RATIO));
+    assert_max_ratio(max_in_ratio)?;
+    assert_max_ratio(max_out_ratio)?;
+
+    if !(MIN_ASSETS..=MAX_ASSETS).contains(&msg.asset_infos.len()) {
+        return Err(ContractError::InvalidAssetCount {
+            min: MIN_ASSETS,
+            max: MAX_ASSETS,
+        });
+    }
+
+    for (i, asset) in msg.asset_infos.iter().enumerate() {
+        if msg.asset_infos[..i]
+            .iter()
+            .any(|other| other.info.equal(&asset.info))
+        {
+            return Err(ContractError::DuplicateAssets {});
+        }
+
         if asset.start_weight.is_zero() {
-            return Err(ContractError::Std(StdError::generic_err(
-                "start_weight can not be 0",
-            )));
+            return Err(ContractError::ZeroWeight {});
         }
 
         if asset.end_weight.is_zero() {
-            return Err(ContractError::Std(StdError::generic_err(
-                "end_weight can not be 0",
-            )));
+            return Err(ContractError::ZeroWeight {});
+        }
+
+        if let Some(curve) = &asset.curve {
+            assert_weight_curve(curve, msg.start_time, msg.end_time)?;
         }
     }
 
+    let swap_direction = msg.swap_direction.unwrap_or(SwapDirection::Both);
+    if swap_direction != SwapDirection::Both && msg.asset_infos.len() > 2 {
+        return Err(ContractError::TwoAssetPairsOnly {});
+    }
+
     let pair_info: &PairInfo = &PairInfo {
         contract_addr: env.contract.address.clone(),
         liquidity_token: Addr::unchecked(""),
-        asset_infos: [msg.asset_infos[0].clone(), msg.asset_infos[1].clone()],
+        owner: deps.api.addr_validate(&msg.owner)?,
+        asset_infos: msg.asset_infos,
         start_time: msg.start_time,
         end_time: msg.end_time,
         description: msg.description,
+        liquidity_policy: msg.liquidity_policy.unwrap_or(LiquidityPolicy::SaleOwner),
+        swap_direction,
+        paused: false,
+        swap_fee: msg.swap_fee,
+        max_in_ratio,
+        max_out_ratio,
+        closed_at: None,
+        cancelled: false,
     };
 
     PAIR_INFO.save(deps.storage, pair_info)?;
+    FACTORY_ADDR.save(deps.storage, &info.sender)?;
+    WEIGHT_SCHEDULES.save(
+        deps.storage,
+        &vec![WeightSchedule {
+            asset_infos: pair_info.asset_infos.clone(),
+            start_time: pair_info.start_time,
+            end_time: pair_info.end_time,
+        }],
+    )?;
+    STATS.save(deps.storage, &empty_stats(pair_info))?;
+    if pair_info.asset_infos.len() == 2 {
+        PRICE_CUMULATIVES.save(
+            deps.storage,
+            U64Key::new(env.block.time.seconds()),
+            &PriceCumulative {
+                block_time: env.block.time.seconds(),
+                price0_cumulative: Uint128::zero(),
+                price1_cumulative: Uint128::zero(),
+            },
+        )?;
+    }
 
     Ok(Response::new().add_submessage(SubMsg {
         // Create LP token
@@ -108,18 +214,83 @@ pub fn instantiate(
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
-    let mut config: PairInfo = PAIR_INFO.load(deps.storage)?;
-
-    let data = msg.result.unwrap().data.unwrap();
+    let data = msg
+        .result
+        .into_result()
+        .ok()
+        .and_then(|res| res.data)
+        .ok_or(ContractError::InvalidReply {})?;
     let res: MsgInstantiateContractResponse =
-        Message::parse_from_bytes(data.as_slice()).map_err(|_| {
-            StdError::parse_err("MsgInstantiateContractResponse", "failed to parse data")
-        })?;
+        Message::parse_from_bytes(data.as_slice()).map_err(|_| ContractError::InvalidReply {})?;
+    let contract_addr = deps.api.addr_validate(res.get_contract_address())?;
+
+    match msg.id {
+        INSTANTIATE_REPLY_ID => {
+            let mut config: PairInfo = PAIR_INFO.load(deps.storage)?;
+            config.liquidity_token = contract_addr;
+
+            PAIR_INFO.save(deps.storage, &config)?;
+            Ok(Response::new().add_attribute("liquidity_token_addr", config.liquidity_token))
+        }
+        GRADUATION_REPLY_ID => provide_graduation_liquidity(deps, contract_addr),
+        _ => Err(ContractError::UnknownReplyId { id: msg.id }),
+    }
+}
+
+/// Seeds the graduated pair with the withdrawn sale owner liquidity and records it
+fn provide_graduation_liquidity(
+    deps: DepsMut,
+    graduated_pair: Addr,
+) -> Result<Response, ContractError> {
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    let assets = TMP_GRADUATION_ASSETS.load(deps.storage)?;
+    TMP_GRADUATION_ASSETS.remove(deps.storage);
+    GRADUATED_PAIR.save(deps.storage, &graduated_pair)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut funds: Vec<Coin> = vec![];
+    for asset in assets.iter() {
+        match &asset.info {
+            AssetInfo::Token { contract_addr } => {
+                messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: contract_addr.to_string(),
+                    msg: to_binary(&Cw20ExecuteMsg::IncreaseAllowance {
+                        spender: graduated_pair.to_string(),
+                        amount: asset.amount,
+                        expires: None,
+                    })?,
+                    funds: vec![],
+                }))
+            }
+            AssetInfo::NativeToken { denom } => funds.push(Coin {
+                denom: denom.to_string(),
+                amount: asset.amount,
+            }),
+        }
+    }
+    funds.sort_by(|a, b| a.denom.cmp(&b.denom));
 
-    config.liquidity_token = deps.api.addr_validate(res.get_contract_address())?;
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: graduated_pair.to_string(),
+        msg: to_binary(&XykPairExecuteMsg::ProvideLiquidity {
+            assets: assets.clone(),
+            slippage_tolerance: None,
+            receiver: Some(pair_info.owner.to_string()),
+        })?,
+        funds,
+    }));
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: FACTORY_ADDR.load(deps.storage)?.to_string(),
+        msg: to_binary(&FactoryExecuteMsg::RegisterGraduatedPair {
+            asset_infos: pair_info.infos(),
+            graduated_pair: graduated_pair.to_string(),
+        })?,
+        funds: vec![],
+    }));
 
-    PAIR_INFO.save(deps.storage, &config)?;
-    Ok(Response::new().add_attribute("liquidity_token_addr", config.liquidity_token))
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("graduated_pair_addr", graduated_pair))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -135,11 +306,16 @@ pub fn execute(
             assets,
             slippage_tolerance,
         } => try_provide_liquidity(deps, env, info, assets, slippage_tolerance),
+        ExecuteMsg::ProvideSingleLiquidity { asset, min_share } => {
+            try_provide_single_liquidity(deps, env, info, asset, min_share)
+        }
         ExecuteMsg::Swap {
             offer_asset,
+            ask_asset_info,
             belief_price,
             max_spread,
             to,
+            merkle_proof,
         } => {
             if !offer_asset.is_native_token() {
                 return Err(ContractError::Unauthorized {});
@@ -151,11 +327,78 @@ pub fn execute(
                 info.clone(),
                 info.sender,
                 offer_asset,
+                ask_asset_info,
+                None,
                 belief_price,
                 max_spread,
                 to,
+                merkle_proof,
+            )
+        }
+        ExecuteMsg::SwapExactOut {
+            ask_asset,
+            offer_asset_info,
+            max_offer_amount,
+            to,
+            merkle_proof,
+        } => {
+            let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+            let pools: Vec<WeightedAsset> =
+                pair_info.query_pools(deps.as_ref(), &env.contract.address)?;
+            let (_, offer_index) =
+                get_swap_indexes(&pools, &ask_asset.info, offer_asset_info.as_ref())?;
+
+            // tokens are offered with the cw20 hook
+            let offer_asset = Asset {
+                info: pools[offer_index].info.clone(),
+                amount: max_offer_amount,
+            };
+            if !offer_asset.is_native_token() {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            try_swap(
+                deps,
+                env,
+                info.clone(),
+                info.sender,
+                offer_asset,
+                Some(ask_asset.info),
+                Some(ask_asset.amount),
+                None,
+                None,
+                to,
+                merkle_proof,
             )
         }
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            try_propose_new_owner(deps, env, info, owner, expires_in)
+        }
+        ExecuteMsg::DropOwnershipProposal {} => try_drop_ownership_proposal(deps, info),
+        ExecuteMsg::ClaimOwnership {} => try_claim_ownership(deps, env, info),
+        ExecuteMsg::UpdateWeightSchedule {
+            end_weights,
+            end_time,
+        } => try_update_weight_schedule(deps, env, info, end_weights, end_time),
+        ExecuteMsg::Pause {} => try_set_paused(deps, info, true),
+        ExecuteMsg::Unpause {} => try_set_paused(deps, info, false),
+        ExecuteMsg::UpdateSwapFee { swap_fee } => try_update_swap_fee(deps, env, info, swap_fee),
+        ExecuteMsg::UpdateMerkleRoot { merkle_root } => {
+            try_update_merkle_root(deps, env, info, merkle_root)
+        }
+        ExecuteMsg::UpdatePurchaseCap { purchase_cap } => {
+            try_update_purchase_cap(deps, env, info, purchase_cap)
+        }
+        ExecuteMsg::GraduateToPool {} => try_graduate_to_pool(deps, env, info),
+        ExecuteMsg::UpdateVesting { vesting } => try_update_vesting(deps, env, info, vesting),
+        ExecuteMsg::Claim {} => try_claim(deps, env, info),
+        ExecuteMsg::UpdateSoftCap { soft_cap } => try_update_soft_cap(deps, env, info, soft_cap),
+        ExecuteMsg::UpdateHardCap { hard_cap } => try_update_hard_cap(deps, env, info, hard_cap),
+        ExecuteMsg::CancelSale {} => try_cancel_sale(deps, env, info),
+        ExecuteMsg::Refund {} => {
+            let sender = info.sender.clone();
+            try_refund(deps, env, info, sender, None)
+        }
     }
 }
 
@@ -168,14 +411,16 @@ pub fn receive_cw20(
     let contract_addr = info.sender.clone();
     match from_binary(&cw20_msg.msg) {
         Ok(Cw20HookMsg::Swap {
+            ask_asset_info,
             belief_price,
             max_spread,
             to,
+            merkle_proof,
         }) => {
             // only asset contract can execute this message
             let mut authorized: bool = false;
             let config: PairInfo = PAIR_INFO.load(deps.storage)?;
-            let pools: [WeightedAsset; 2] =
+            let pools: Vec<WeightedAsset> =
                 config.query_pools(deps.as_ref(), &env.contract.address)?;
             for pool in pools.iter() {
                 if let AssetInfo::Token { contract_addr, .. } = &pool.info {
@@ -198,9 +443,49 @@ pub fn receive_cw20(
                     info: AssetInfo::Token { contract_addr },
                     amount: cw20_msg.amount,
                 },
+                ask_asset_info,
+                None,
                 belief_price,
                 max_spread,
                 to,
+                merkle_proof,
+            )
+        }
+        Ok(Cw20HookMsg::SwapExactOut {
+            ask_asset,
+            to,
+            merkle_proof,
+        }) => {
+            // only asset contract can execute this message
+            let mut authorized: bool = false;
+            let config: PairInfo = PAIR_INFO.load(deps.storage)?;
+            for asset_info in config.infos().iter() {
+                if let AssetInfo::Token { contract_addr, .. } = asset_info {
+                    if contract_addr == &info.sender {
+                        authorized = true;
+                    }
+                }
+            }
+
+            if !authorized {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            try_swap(
+                deps,
+                env,
+                info,
+                Addr::unchecked(cw20_msg.sender),
+                Asset {
+                    info: AssetInfo::Token { contract_addr },
+                    amount: cw20_msg.amount,
+                },
+                Some(ask_asset.info),
+                Some(ask_asset.amount),
+                None,
+                None,
+                to,
+                merkle_proof,
             )
         }
         Ok(Cw20HookMsg::WithdrawLiquidity {}) => try_withdraw_liquidity(
@@ -210,6 +495,28 @@ pub fn receive_cw20(
             Addr::unchecked(cw20_msg.sender),
             cw20_msg.amount,
         ),
+        Ok(Cw20HookMsg::WithdrawSingleLiquidity {
+            asset_info,
+            min_return,
+        }) => try_withdraw_single_liquidity(
+            deps,
+            env,
+            info,
+            Addr::unchecked(cw20_msg.sender),
+            cw20_msg.amount,
+            asset_info,
+            min_return,
+        ),
+        Ok(Cw20HookMsg::Refund {}) => try_refund(
+            deps,
+            env,
+            info,
+            Addr::unchecked(cw20_msg.sender),
+            Some(Asset {
+                info: AssetInfo::Token { contract_addr },
+                amount: cw20_msg.amount,
+            }),
+        ),
         Err(err) => Err(ContractError::Std(err)),
     }
 }
@@ -219,30 +526,44 @@ pub fn try_provide_liquidity(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    assets: [Asset; 2],
+    assets: Vec<Asset>,
     slippage_tolerance: Option<Decimal>,
 ) -> Result<Response, ContractError> {
     for asset in assets.iter() {
-        asset.assert_sent_native_token_balance(&info)?;
+        assert_sent_native_token_balance(asset, &info)?;
     }
 
     let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
-    let mut pools: [WeightedAsset; 2] =
-        pair_info.query_pools(deps.as_ref(), &env.contract.address)?;
-    let deposits: [Uint128; 2] = [
-        assets
-            .iter()
-            .find(|a| a.info.equal(&pools[0].info))
-            .map(|a| a.amount)
-            .expect("Wrong asset info is given"),
-        assets
-            .iter()
-            .find(|a| a.info.equal(&pools[1].info))
-            .map(|a| a.amount)
-            .expect("Wrong asset info is given"),
-    ];
 
-    if deposits[0].is_zero() || deposits[1].is_zero() {
+    if pair_info.paused {
+        return Err(ContractError::Paused {});
+    }
+
+    assert_not_cancelled(&pair_info)?;
+
+    assert_liquidity_policy(&pair_info, &info.sender, env.block.time.seconds())?;
+
+    let mut pools: Vec<WeightedAsset> =
+        get_pools(deps.as_ref(), &pair_info, &env.contract.address)?;
+    if assets
+        .iter()
+        .any(|a| !pools.iter().any(|pool| pool.info.equal(&a.info)))
+    {
+        return Err(ContractError::AssetMismatch {});
+    }
+
+    let deposits: Vec<Uint128> = pools
+        .iter()
+        .map(|pool| {
+            assets
+                .iter()
+                .find(|a| a.info.equal(&pool.info))
+                .map(|a| a.amount)
+                .ok_or(ContractError::AssetMismatch {})
+        })
+        .collect::<Result<Vec<Uint128>, ContractError>>()?;
+
+    if deposits.iter().any(|deposit| deposit.is_zero()) {
         return Err(ContractError::ZeroAmount {});
     }
 
@@ -262,33 +583,29 @@ pub fn try_provide_liquidity(
         } else {
             // If the asset is native token, balance is already increased
             // To calculated properly we should subtract user deposit from the pool
-            pool.amount = pool.amount.checked_sub(deposits[i]).unwrap();
+            pool.amount = pool.amount.checked_sub(deposits[i])?;
         }
     }
 
     // assert slippage tolerance
     assert_slippage_tolerance(&slippage_tolerance, &deposits, &pools)?;
 
+    update_price_cumulatives(deps.storage, &env, &pair_info, &pools)?;
+
     let liquidity_token = pair_info.liquidity_token.clone();
     let total_share = query_supply(deps.as_ref(), &liquidity_token)?;
 
     let share = if total_share.is_zero() {
-        // Initial share = collateral amount
-        Uint128::new(
-            (U256::from(deposits[0].u128()) * U256::from(deposits[1].u128()))
-                .integer_sqrt()
-                .as_u128(),
-        )
+        // Initial share = geometric mean of the deposits, sqrt(deposit_0 * deposit_1) for two assets
+        geometric_mean(&deposits)
     } else {
-        // min(1, 2)
-        // 1. sqrt(deposit_0 * exchange_rate_0_to_1 * deposit_0) * (total_share / sqrt(pool_0 * pool_1))
-        // == deposit_0 * total_share / pool_0
-        // 2. sqrt(deposit_1 * exchange_rate_1_to_0 * deposit_1) * (total_share / sqrt(pool_1 * pool_1))
-        // == deposit_1 * total_share / pool_1
-        std::cmp::min(
-            deposits[0].multiply_ratio(total_share, pools[0].amount),
-            deposits[1].multiply_ratio(total_share, pools[1].amount),
-        )
+        // min(deposit_i * total_share / pool_i)
+        deposits
+            .iter()
+            .zip(pools.iter())
+            .map(|(deposit, pool)| deposit.multiply_ratio(total_share, pool.amount))
+            .min()
+            .unwrap_or_default()
     };
 
     // mint LP token to sender
@@ -301,11 +618,142 @@ pub fn try_provide_liquidity(
         funds: vec![],
     }));
 
-    Ok(Response::new().add_messages(messages).add_attributes(vec![
-        attr("action", "provide_liquidity"),
-        attr("assets", format!("{}, {}", assets[0], assets[1])),
-        attr("share", share.to_string()),
-    ]))
+    let deposited_assets: Vec<Asset> = pools
+        .iter()
+        .zip(deposits.iter())
+        .map(|(pool, deposit)| Asset {
+            info: pool.info.clone(),
+            amount: *deposit,
+        })
+        .collect();
+    let weights = get_normalized_weights(&pair_info, &pools, env.block.time.seconds())?;
+    let event = Event::new("lbp_provide")
+        .add_attribute("sender", info.sender.to_string())
+        .add_attribute("share", share.to_string())
+        .add_attribute("fee_amount", Uint128::zero().to_string());
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "provide_liquidity"),
+            attr("assets", join_assets(&assets)),
+            attr("share", share.to_string()),
+        ])
+        .add_event(with_weights(
+            with_assets(event, &deposited_assets),
+            &weights,
+        )))
+}
+
+/// CONTRACT - should approve contract to use the amount of token
+pub fn try_provide_single_liquidity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    asset: Asset,
+    min_share: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    assert_sent_native_token_balance(&asset, &info)?;
+
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+
+    if pair_info.paused {
+        return Err(ContractError::Paused {});
+    }
+
+    assert_not_cancelled(&pair_info)?;
+
+    let block_time = env.block.time.seconds();
+    assert_liquidity_policy(&pair_info, &info.sender, block_time)?;
+
+    if block_time >= pair_info.start_time && block_time <= pair_info.sale_end_time() {
+        return Err(ContractError::SaleRunning {});
+    }
+
+    if asset.amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+
+    let mut pools: Vec<WeightedAsset> =
+        get_pools(deps.as_ref(), &pair_info, &env.contract.address)?;
+    let (index, weight) = get_normalized_weight(&pair_info, &pools, &asset.info, block_time)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if let AssetInfo::Token { contract_addr, .. } = &asset.info {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                owner: info.sender.to_string(),
+                recipient: env.contract.address.to_string(),
+                amount: asset.amount,
+            })?,
+            funds: vec![],
+        }));
+    } else {
+        // the native balance is already increased by the deposit
+        pools[index].amount = pools[index].amount.checked_sub(asset.amount)?;
+    }
+
+    let total_share = query_supply(deps.as_ref(), &pair_info.liquidity_token)?;
+    if total_share.is_zero() || pools[index].amount.is_zero() {
+        return Err(ContractError::InsufficientLiquidity {});
+    }
+
+    update_price_cumulatives(deps.storage, &env, &pair_info, &pools)?;
+
+    let (share, fee_amount) = compute_single_asset_join(
+        pools[index].amount,
+        weight,
+        total_share,
+        asset.amount,
+        pair_info.swap_fee,
+        pair_info.max_in_ratio,
+    )?;
+
+    if let Some(min_share) = min_share {
+        if share < min_share {
+            return Err(ContractError::MinShareNotReached { share });
+        }
+    }
+
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: pair_info.liquidity_token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Mint {
+            recipient: info.sender.to_string(),
+            amount: share,
+        })?,
+        funds: vec![],
+    }));
+
+    let deposited_assets: Vec<Asset> = pools
+        .iter()
+        .map(|pool| Asset {
+            info: pool.info.clone(),
+            amount: if pool.info.equal(&asset.info) {
+                asset.amount
+            } else {
+                Uint128::zero()
+            },
+        })
+        .collect();
+    let weights = get_normalized_weights(&pair_info, &pools, block_time)?;
+    let event = Event::new("lbp_provide")
+        .add_attribute("sender", info.sender.to_string())
+        .add_attribute("share", share.to_string())
+        .add_attribute("fee_amount", fee_amount.to_string());
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "provide_single_liquidity"),
+            attr("asset", asset.to_string()),
+            attr("share", share.to_string()),
+            attr("fee_amount", fee_amount.to_string()),
+        ])
+        .add_event(with_weights(
+            with_assets(event, &deposited_assets),
+            &weights,
+        )))
 }
 
 pub fn try_withdraw_liquidity(
@@ -320,11 +768,29 @@ pub fn try_withdraw_liquidity(
     if info.sender != pair_info.liquidity_token {
         return Err(ContractError::Unauthorized {});
     }
+
+    let block_time = env.block.time.seconds();
+    // withdrawals are only available after the sale while the pair is paused
+    if pair_info.paused && block_time <= pair_info.sale_end_time() {
+        return Err(ContractError::Paused {});
+    }
+
+    if pair_info.liquidity_policy == LiquidityPolicy::SaleOwner
+        && block_time >= pair_info.start_time
+        && block_time <= pair_info.sale_end_time()
+    {
+        return Err(ContractError::SaleRunning {});
+    }
+
+    assert_refunds_settled(deps.storage, &pair_info, block_time)?;
+
     let liquidity_addr: Addr = pair_info.liquidity_token.clone();
 
-    let pools: [WeightedAsset; 2] = pair_info.query_pools(deps.as_ref(), &env.contract.address)?;
+    let pools: Vec<WeightedAsset> = get_pools(deps.as_ref(), &pair_info, &env.contract.address)?;
     let total_share: Uint128 = query_supply(deps.as_ref(), &liquidity_addr)?;
 
+    update_price_cumulatives(deps.storage, &env, &pair_info, &pools)?;
+
     let share_ratio: Decimal = Decimal::from_ratio(amount, total_share);
     let refund_assets: Vec<Asset> = pools
         .iter()
@@ -334,13 +800,107 @@ pub fn try_withdraw_liquidity(
         })
         .collect();
 
-    let messages: Vec<CosmosMsg> = vec![
-        refund_assets[0].clone().into_msg(
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for refund_asset in refund_assets.iter() {
+        messages.push(refund_asset.clone().into_msg(
             deps.as_ref(),
             env.contract.address.clone(),
             sender.clone(),
-        )?,
-        refund_assets[1]
+        )?);
+    }
+    let weights = get_normalized_weights(&pair_info, &pools, block_time)?;
+    let event = Event::new("lbp_withdraw")
+        .add_attribute("sender", sender.to_string())
+        .add_attribute("share", amount.to_string())
+        .add_attribute("fee_amount", Uint128::zero().to_string());
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: pair_info.liquidity_token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
+        funds: vec![],
+    }));
+
+    // update pool info
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "withdraw_liquidity"),
+            attr("withdrawn_share", &amount.to_string()),
+            attr("refund_assets", join_assets(&refund_assets)),
+        ])
+        .add_event(with_weights(with_assets(event, &refund_assets), &weights)))
+}
+
+pub fn try_withdraw_single_liquidity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    amount: Uint128,
+    asset_info: AssetInfo,
+    min_return: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+
+    if info.sender != pair_info.liquidity_token {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let block_time = env.block.time.seconds();
+    if pair_info.paused && block_time <= pair_info.sale_end_time() {
+        return Err(ContractError::Paused {});
+    }
+
+    if block_time >= pair_info.start_time && block_time <= pair_info.sale_end_time() {
+        return Err(ContractError::SaleRunning {});
+    }
+
+    assert_refunds_settled(deps.storage, &pair_info, block_time)?;
+
+    let pools: Vec<WeightedAsset> = get_pools(deps.as_ref(), &pair_info, &env.contract.address)?;
+    let (index, weight) = get_normalized_weight(&pair_info, &pools, &asset_info, block_time)?;
+    let total_share: Uint128 = query_supply(deps.as_ref(), &pair_info.liquidity_token)?;
+
+    update_price_cumulatives(deps.storage, &env, &pair_info, &pools)?;
+
+    let (return_amount, fee_amount) = compute_single_asset_exit(
+        pools[index].amount,
+        weight,
+        total_share,
+        amount,
+        pair_info.swap_fee,
+        pair_info.max_out_ratio,
+    )?;
+
+    if let Some(min_return) = min_return {
+        if return_amount < min_return {
+            return Err(ContractError::MinReturnNotReached { return_amount });
+        }
+    }
+
+    let refund_assets: Vec<Asset> = pools
+        .iter()
+        .map(|pool| Asset {
+            info: pool.info.clone(),
+            amount: if pool.info.equal(&asset_info) {
+                return_amount
+            } else {
+                Uint128::zero()
+            },
+        })
+        .collect();
+    let weights = get_normalized_weights(&pair_info, &pools, block_time)?;
+    let event = Event::new("lbp_withdraw")
+        .add_attribute("sender", sender.to_string())
+        .add_attribute("share", amount.to_string())
+        .add_attribute("fee_amount", fee_amount.to_string());
+
+    let refund_asset = Asset {
+        info: asset_info,
+        amount: return_amount,
+    };
+
+    let messages: Vec<CosmosMsg> = vec![
+        refund_asset
             .clone()
             .into_msg(deps.as_ref(), env.contract.address, sender)?,
         CosmosMsg::Wasm(WasmMsg::Execute {
@@ -350,15 +910,15 @@ pub fn try_withdraw_liquidity(
         }),
     ];
 
-    // update pool info
-    Ok(Response::new().add_messages(messages).add_attributes(vec![
-        attr("action", "withdraw_liquidity"),
-        attr("withdrawn_share", &amount.to_string()),
-        attr(
-            "refund_assets",
-            format!("{}, {}", refund_assets[0], refund_assets[1]),
-        ),
-    ]))
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "withdraw_single_liquidity"),
+            attr("withdrawn_share", amount.to_string()),
+            attr("refund_asset", refund_asset.to_string()),
+            attr("fee_amount", fee_amount.to_string()),
+        ])
+        .add_event(with_weights(with_assets(event, &refund_assets), &weights)))
 }
 
 // CONTRACT - a user must do token approval
@@ -369,127 +929,1935 @@ pub fn try_swap(
     info: MessageInfo,
     sender: Addr,
     offer_asset: Asset,
+    ask_asset_info: Option<AssetInfo>,
+    ask_amount: Option<Uint128>,
     belief_price: Option<Decimal>,
     max_spread: Option<Decimal>,
     to: Option<Addr>,
+    merkle_proof: Option<Vec<String>>,
 ) -> Result<Response, ContractError> {
-    offer_asset.assert_sent_native_token_balance(&info)?;
+    assert_sent_native_token_balance(&offer_asset, &info)?;
 
-    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    let mut pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+
+    if pair_info.paused {
+        return Err(ContractError::Paused {});
+    }
+
+    assert_not_cancelled(&pair_info)?;
 
-    let pools: [WeightedAsset; 2] = pair_info.query_pools(deps.as_ref(), &env.contract.address)?;
+    if let Some(merkle_root) = MERKLE_ROOT.may_load(deps.storage)? {
+        let merkle_proof = merkle_proof.unwrap_or_default();
+        if !verify_merkle_proof(&merkle_root, sender.as_str(), &merkle_proof)? {
+            return Err(ContractError::NotAllowed {});
+        }
+    }
 
-    let offer_pool: WeightedAsset;
-    let ask_pool: WeightedAsset;
+    let mut pools: Vec<WeightedAsset> =
+        get_pools(deps.as_ref(), &pair_info, &env.contract.address)?;
+    let (offer_index, ask_index) =
+        get_swap_indexes(&pools, &offer_asset.info, ask_asset_info.as_ref())?;
 
     // If the asset balance is already increased
     // To calculated properly we should subtract user deposit from the pool
-    if offer_asset.info.equal(&pools[0].info) {
-        offer_pool = WeightedAsset {
-            amount: pools[0].amount.checked_sub(offer_asset.amount).unwrap(),
-            info: pools[0].info.clone(),
-            start_weight: pools[0].start_weight,
-            end_weight: pools[0].end_weight,
-        };
-        ask_pool = pools[1].clone();
-    } else if offer_asset.info.equal(&pools[1].info) {
-        offer_pool = WeightedAsset {
-            amount: pools[1].amount.checked_sub(offer_asset.amount).unwrap(),
-            info: pools[1].info.clone(),
-            start_weight: pools[1].start_weight,
-            end_weight: pools[1].end_weight,
-        };
-        ask_pool = pools[0].clone();
-    } else {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Wrong asset info is given",
-        )));
-    }
+    pools[offer_index].amount = pools[offer_index].amount.checked_sub(offer_asset.amount)?;
+    let offer_pool: WeightedAsset = pools[offer_index].clone();
+    let ask_pool: WeightedAsset = pools[ask_index].clone();
+
+    assert_swap_direction(&pair_info, &offer_pool.info)?;
+
+    update_price_cumulatives(deps.storage, &env, &pair_info, &pools)?;
 
     let ask_weight = get_current_weight(
         ask_pool.start_weight,
         ask_pool.end_weight,
+        ask_pool.curve.as_ref(),
+        pair_info.start_time,
+        pair_info.end_time,
+        pair_info.closed_at,
+        env.block.time.seconds(),
+    )?;
+    let offer_weight = get_current_weight(
+        offer_pool.start_weight,
+        offer_pool.end_weight,
+        offer_pool.curve.as_ref(),
         pair_info.start_time,
         pair_info.end_time,
+        pair_info.closed_at,
         env.block.time.seconds(),
     )?;
-    let offer_weight = get_current_weight(
-        offer_pool.start_weight,
-        offer_pool.end_weight,
-        pair_info.start_time,
-        pair_info.end_time,
-        env.block.time.seconds(),
+
+    let mut offer_amount = offer_asset.amount;
+    let mut refund_amount = Uint128::zero();
+    let remaining_hard_cap = match HARD_CAP.may_load(deps.storage)? {
+        Some(hard_cap) if hard_cap.info.equal(&offer_asset.info) => {
+            let inflow = query_inflow(deps.storage, &pair_info, &hard_cap.info)?;
+            Some(hard_cap.amount.saturating_sub(inflow))
+        }
+        _ => None,
+    };
+
+    let (return_amount, spread_amount, commission_amount) = match ask_amount {
+        // the exact output swap takes only the required offer amount and refunds the excess
+        Some(ask_amount) => {
+            let (required_amount, spread_amount, commission_amount) = compute_offer_amount(
+                offer_pool.amount,
+                offer_weight,
+                ask_pool.amount,
+                ask_weight,
+                ask_amount,
+                pair_info.swap_fee,
+                pair_info.max_in_ratio,
+                pair_info.max_out_ratio,
+            )?;
+
+            if required_amount > offer_amount {
+                return Err(ContractError::MaxOfferAmountExceeded { required_amount });
+            }
+
+            if let Some(remaining) = remaining_hard_cap {
+                if required_amount > remaining {
+                    return Err(ContractError::HardCapExceeded { remaining });
+                }
+
+                if required_amount == remaining {
+                    pair_info.closed_at = Some(env.block.time.seconds());
+                    PAIR_INFO.save(deps.storage, &pair_info)?;
+                }
+            }
+
+            refund_amount = offer_amount - required_amount;
+            offer_amount = required_amount;
+            (ask_amount, spread_amount, commission_amount)
+        }
+        None => {
+            // the trade reaching the hard cap is filled partially and closes the sale
+            if let Some(remaining) = remaining_hard_cap {
+                if offer_amount >= remaining {
+                    refund_amount = offer_amount - remaining;
+                    offer_amount = remaining;
+                    pair_info.closed_at = Some(env.block.time.seconds());
+                    PAIR_INFO.save(deps.storage, &pair_info)?;
+                }
+            }
+
+            compute_swap(
+                offer_pool.amount,
+                offer_weight,
+                ask_pool.amount,
+                ask_weight,
+                offer_amount,
+                pair_info.swap_fee,
+                pair_info.max_in_ratio,
+                pair_info.max_out_ratio,
+            )?
+        }
+    };
+
+    // check max spread limit if exist
+    assert_max_spread(
+        belief_price,
+        max_spread,
+        offer_amount,
+        return_amount + commission_amount,
+        spread_amount,
+    )?;
+
+    if let Some(purchase_cap) = PURCHASE_CAP.may_load(deps.storage)? {
+        let purchased = PURCHASES
+            .may_load(deps.storage, &sender)?
+            .unwrap_or_default();
+        let remaining = remaining_allowance(&purchase_cap, purchased);
+        let purchase_amount = match &purchase_cap {
+            PurchaseCap::Received { info, .. } if info.equal(&ask_pool.info) => return_amount,
+            PurchaseCap::Spent { info, .. } if info.equal(&offer_asset.info) => offer_amount,
+            _ => Uint128::zero(),
+        };
+
+        if purchase_amount > remaining {
+            return Err(ContractError::PurchaseCapExceeded { remaining });
+        }
+
+        PURCHASES.save(deps.storage, &sender, &(purchased + purchase_amount))?;
+    }
+
+    // the soft cap is reached by the net inflow of its asset, contributions only track refunds
+    // of the receivers of the purchases
+    if let Some(soft_cap) = SOFT_CAP.may_load(deps.storage)? {
+        let beneficiary = to.clone().unwrap_or_else(|| sender.clone());
+        let mut refundable = REFUNDABLE.may_load(deps.storage)?.unwrap_or_default();
+        if soft_cap.info.equal(&offer_asset.info) {
+            let mut contribution = CONTRIBUTIONS
+                .may_load(deps.storage, &beneficiary)?
+                .unwrap_or_default();
+            contribution.paid += offer_amount;
+            contribution.received += return_amount;
+            refundable += offer_amount;
+            CONTRIBUTIONS.save(deps.storage, &beneficiary, &contribution)?;
+        } else {
+            // selling back reverses the sold share of the purchases of the seller
+            let mut contribution = CONTRIBUTIONS
+                .may_load(deps.storage, &sender)?
+                .unwrap_or_default();
+            if !contribution.received.is_zero() {
+                let sold = offer_amount.min(contribution.received);
+                let repaid = contribution
+                    .paid
+                    .multiply_ratio(sold, contribution.received);
+                contribution.paid -= repaid;
+                contribution.received -= sold;
+                refundable -= repaid;
+                CONTRIBUTIONS.save(deps.storage, &sender, &contribution)?;
+            }
+        }
+        REFUNDABLE.save(deps.storage, &refundable)?;
+    }
+
+    // compute tax
+    let return_asset = Asset {
+        info: ask_pool.info.clone(),
+        amount: return_amount,
+    };
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !refund_amount.is_zero() {
+        let refund_asset = Asset {
+            info: offer_asset.info.clone(),
+            amount: refund_amount,
+        };
+        messages.push(refund_asset.into_msg(
+            deps.as_ref(),
+            env.contract.address.clone(),
+            sender.clone(),
+        )?);
+    }
+
+    let receiver = to.unwrap_or_else(|| sender.clone());
+    let mut tax_amount = Uint128::zero();
+
+    // 1. send collateral token from the contract to a user or record it if it vests
+    // 2. send inactive commission to collector
+    match VESTING.may_load(deps.storage)? {
+        Some(vesting) if vesting.info.equal(&ask_pool.info) => {
+            let mut vested_purchase = VESTED_PURCHASES
+                .may_load(deps.storage, &receiver)?
+                .unwrap_or_default();
+            vested_purchase.purchased += return_amount;
+            VESTED_PURCHASES.save(deps.storage, &receiver, &vested_purchase)?;
+
+            let reserve = VESTING_RESERVE.may_load(deps.storage)?.unwrap_or_default();
+            VESTING_RESERVE.save(deps.storage, &(reserve + return_amount))?;
+        }
+        _ => {
+            tax_amount = return_asset.compute_tax(deps.as_ref())?;
+            messages.push(return_asset.into_msg(
+                deps.as_ref(),
+                env.contract.address.clone(),
+                receiver.clone(),
+            )?);
+        }
+    }
+
+    let mut protocol_fee_amount = Uint128::zero();
+    if let Some((fee_address, protocol_fee_share)) = query_protocol_fee(deps.as_ref())? {
+        protocol_fee_amount = commission_amount * protocol_fee_share;
+        if !protocol_fee_amount.is_zero() {
+            let protocol_fee = Asset {
+                info: ask_pool.info.clone(),
+                amount: protocol_fee_amount,
+            };
+            messages.push(protocol_fee.into_msg(
+                deps.as_ref(),
+                env.contract.address.clone(),
+                fee_address,
+            )?);
+        }
+    }
+
+    let mut stats = STATS
+        .may_load(deps.storage)?
+        .unwrap_or_else(|| empty_stats(&pair_info));
+    for asset_stats in stats.iter_mut() {
+        if asset_stats.info.equal(&offer_asset.info) {
+            asset_stats.volume_in += offer_amount;
+        } else if asset_stats.info.equal(&ask_pool.info) {
+            asset_stats.volume_out += return_amount;
+            asset_stats.commission_amount += commission_amount;
+            asset_stats.protocol_fee_amount += protocol_fee_amount;
+        }
+    }
+    STATS.save(deps.storage, &stats)?;
+
+    let mut attributes = vec![
+        attr("action", "swap"),
+        attr("offer_asset", offer_asset.info.to_string()),
+        attr("ask_asset", ask_pool.info.to_string()),
+        attr("offer_amount", offer_amount.to_string()),
+        attr("return_amount", return_amount.to_string()),
+        attr("tax_amount", tax_amount.to_string()),
+        attr("spread_amount", spread_amount.to_string()),
+        attr("commission_amount", commission_amount.to_string()),
+        attr("protocol_fee_amount", protocol_fee_amount.to_string()),
+    ];
+    if pair_info.closed_at.is_some() || ask_amount.is_some() {
+        attributes.push(attr("refund_amount", refund_amount.to_string()));
+    }
+
+    // the pools and the price after the trade, the spot price is the price of the ask asset
+    pools[offer_index].amount += offer_amount;
+    pools[ask_index].amount = pools[ask_index]
+        .amount
+        .checked_sub(return_amount + protocol_fee_amount)?;
+    let weights = get_normalized_weights(&pair_info, &pools, env.block.time.seconds())?;
+    let spot_price = calc_spot_price(
+        pools[ask_index].amount,
+        weights[ask_index],
+        pools[offer_index].amount,
+        weights[offer_index],
+    );
+    let event = Event::new("lbp_swap")
+        .add_attribute("sender", sender.to_string())
+        .add_attribute("receiver", receiver.to_string())
+        .add_attribute("offer_asset", offer_asset.info.to_string())
+        .add_attribute("ask_asset", ask_pool.info.to_string())
+        .add_attribute("offer_amount", offer_amount.to_string())
+        .add_attribute("return_amount", return_amount.to_string())
+        .add_attribute("refund_amount", refund_amount.to_string())
+        .add_attribute("tax_amount", tax_amount.to_string())
+        .add_attribute("spread_amount", spread_amount.to_string())
+        .add_attribute("commission_amount", commission_amount.to_string())
+        .add_attribute("protocol_fee_amount", protocol_fee_amount.to_string())
+        .add_attribute("offer_pool", pools[offer_index].amount.to_string())
+        .add_attribute("ask_pool", pools[ask_index].amount.to_string())
+        .add_attribute("offer_weight", weights[offer_index].to_string())
+        .add_attribute("ask_weight", weights[ask_index].to_string())
+        .add_attribute("spot_price", spot_price.to_string());
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(attributes)
+        .add_event(event))
+}
+
+// Only owner can execute it
+pub fn try_propose_new_owner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    expires_in: u64,
+) -> Result<Response, ContractError> {
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    assert_owner(&pair_info, &info.sender)?;
+
+    let new_owner = deps.api.addr_validate(&owner)?;
+    if new_owner == pair_info.owner {
+        return Err(ContractError::SameOwner {});
+    }
+
+    if expires_in > MAX_PROPOSAL_TTL {
+        return Err(ContractError::ExpirationTooLong {
+            max: MAX_PROPOSAL_TTL,
+        });
+    }
+
+    OWNERSHIP_PROPOSAL.save(
+        deps.storage,
+        &OwnershipProposal {
+            owner: new_owner.clone(),
+            ttl: env.block.time.seconds() + expires_in,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "propose_new_owner"),
+        attr("new_owner", new_owner),
+    ]))
+}
+
+// Only owner can execute it
+pub fn try_drop_ownership_proposal(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    assert_owner(&pair_info, &info.sender)?;
+
+    OWNERSHIP_PROPOSAL.remove(deps.storage);
+
+    Ok(Response::new().add_attribute("action", "drop_ownership_proposal"))
+}
+
+// Only the proposed owner can execute it
+pub fn try_claim_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let proposal: OwnershipProposal = OWNERSHIP_PROPOSAL
+        .may_load(deps.storage)?
+        .ok_or(ContractError::OwnershipProposalNotFound {})?;
+
+    if info.sender != proposal.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if env.block.time.seconds() > proposal.ttl {
+        return Err(ContractError::OwnershipProposalExpired {});
+    }
+
+    OWNERSHIP_PROPOSAL.remove(deps.storage);
+
+    let mut pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    pair_info.owner = proposal.owner;
+    PAIR_INFO.save(deps.storage, &pair_info)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "claim_ownership"),
+        attr("new_owner", pair_info.owner),
+    ]))
+}
+
+// Only owner can execute it
+pub fn try_update_weight_schedule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    end_weights: Vec<Uint128>,
+    end_time: u64,
+) -> Result<Response, ContractError> {
+    let mut pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    assert_owner(&pair_info, &info.sender)?;
+
+    if end_weights.len() != pair_info.asset_infos.len() {
+        return Err(ContractError::WeightCountMismatch {});
+    }
+
+    if end_weights.iter().any(|weight| weight.is_zero()) {
+        return Err(ContractError::ZeroWeight {});
+    }
+
+    // The new schedule starts from the current weights, or from the start weights
+    // if the sale has not started yet
+    let start_time = std::cmp::max(env.block.time.seconds(), pair_info.start_time);
+    if end_time <= start_time {
+        return Err(ContractError::InvalidEndTime {});
+    }
+
+    let mut current_weights: Vec<Decimal256> = vec![];
+    for asset in pair_info.asset_infos.iter() {
+        current_weights.push(get_current_weight(
+            asset.start_weight,
+            asset.end_weight,
+            asset.curve.as_ref(),
+            pair_info.start_time,
+            pair_info.end_time,
+            pair_info.closed_at,
+            start_time,
+        )?);
+    }
+
+    // The new weights are scaled up by WEIGHT_PRECISION, so they and their sum must still fit
+    let end_weights: Vec<Uint128> = end_weights
+        .iter()
+        .map(|weight| {
+            weight
+                .checked_mul(Uint128::from(WEIGHT_PRECISION))
+                .map_err(|_| ContractError::WeightsTooLarge {})
+        })
+        .collect::<Result<Vec<Uint128>, ContractError>>()?;
+    let total_end_weight = end_weights
+        .iter()
+        .try_fold(Uint128::zero(), |sum, weight| sum.checked_add(*weight))
+        .map_err(|_| ContractError::WeightsTooLarge {})?;
+
+    // Weights are relative, so the current weights are scaled to the range of the new targets
+    let scale = uint2dec(total_end_weight).div(
+        current_weights
+            .iter()
+            .fold(Decimal256::zero(), |sum, weight| sum.add(*weight)),
+    );
+    let start_weights: Vec<Uint128> = current_weights
+        .iter()
+        .map(|weight| Uint128::from(Uint256::one().mul(weight.mul(scale))))
+        .collect();
+
+    if start_weights.iter().any(|weight| weight.is_zero()) {
+        return Err(ContractError::PriceDeviationExceeded {});
+    }
+
+    // every price is compared against the first asset
+    let max_price_deviation = Decimal256::from_ratio(MAX_PRICE_DEVIATION_BPS, 10000u64);
+    for i in 1..current_weights.len() {
+        let current_price = current_weights[0].div(current_weights[i]);
+        let new_price = uint2dec(start_weights[0]).div(uint2dec(start_weights[i]));
+        let price_diff = if new_price > current_price {
+            new_price.sub(current_price)
+        } else {
+            current_price.sub(new_price)
+        };
+
+        if price_diff.div(current_price) > max_price_deviation {
+            return Err(ContractError::PriceDeviationExceeded {});
+        }
+    }
+
+    for (i, asset) in pair_info.asset_infos.iter_mut().enumerate() {
+        asset.start_weight = start_weights[i];
+        asset.end_weight = end_weights[i];
+        asset.curve = None;
+    }
+    pair_info.start_time = start_time;
+    pair_info.end_time = end_time;
+    PAIR_INFO.save(deps.storage, &pair_info)?;
+
+    let mut schedules = WEIGHT_SCHEDULES.may_load(deps.storage)?.unwrap_or_default();
+    schedules.push(WeightSchedule {
+        asset_infos: pair_info.asset_infos.clone(),
+        start_time,
+        end_time,
+    });
+    WEIGHT_SCHEDULES.save(deps.storage, &schedules)?;
+
+    let event = pair_info.asset_infos.iter().enumerate().fold(
+        Event::new("lbp_weights_updated")
+            .add_attribute("start_time", start_time.to_string())
+            .add_attribute("end_time", end_time.to_string()),
+        |event, (i, asset)| {
+            event
+                .add_attribute(format!("asset_{}", i), asset.info.to_string())
+                .add_attribute(
+                    format!("start_weight_{}", i),
+                    asset.start_weight.to_string(),
+                )
+                .add_attribute(format!("end_weight_{}", i), asset.end_weight.to_string())
+        },
+    );
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "update_weight_schedule"),
+            attr("start_time", start_time.to_string()),
+            attr("end_time", end_time.to_string()),
+            attr("start_weights", join_weights(start_weights.iter())),
+            attr(
+                "end_weights",
+                join_weights(pair_info.asset_infos.iter().map(|asset| &asset.end_weight)),
+            ),
+        ])
+        .add_event(event))
+}
+
+// Only owner or factory owner can execute it
+pub fn try_set_paused(
+    deps: DepsMut,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    let mut pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    if info.sender != pair_info.owner {
+        let factory_addr = FACTORY_ADDR.load(deps.storage)?;
+        // pairs instantiated without the factory can only be paused by the sale owner
+        match query_factory_config(deps.as_ref(), &factory_addr) {
+            Ok(config) if config.owner == info.sender => {}
+            _ => return Err(ContractError::Unauthorized {}),
+        }
+    }
+
+    pair_info.paused = paused;
+    PAIR_INFO.save(deps.storage, &pair_info)?;
+
+    Ok(Response::new().add_attribute("action", if paused { "pause" } else { "unpause" }))
+}
+
+// Only owner can execute it
+pub fn try_update_swap_fee(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    swap_fee: Decimal,
+) -> Result<Response, ContractError> {
+    let mut pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    assert_owner(&pair_info, &info.sender)?;
+
+    if env.block.time.seconds() >= pair_info.start_time {
+        return Err(ContractError::SaleStarted {});
+    }
+
+    assert_swap_fee(swap_fee)?;
+
+    // pairs created by the factory keep the swap fee within the factory bounds
+    let factory_addr = FACTORY_ADDR.load(deps.storage)?;
+    if let Ok(config) = query_factory_config(deps.as_ref(), &factory_addr) {
+        if swap_fee < config.min_swap_fee || swap_fee > config.max_swap_fee {
+            return Err(ContractError::SwapFeeOutOfBounds {
+                min_swap_fee: config.min_swap_fee,
+                max_swap_fee: config.max_swap_fee,
+            });
+        }
+    }
+
+    pair_info.swap_fee = swap_fee;
+    PAIR_INFO.save(deps.storage, &pair_info)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_swap_fee"),
+        attr("swap_fee", swap_fee.to_string()),
+    ]))
+}
+
+// Only owner can execute it
+pub fn try_update_merkle_root(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    merkle_root: Option<String>,
+) -> Result<Response, ContractError> {
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    assert_owner(&pair_info, &info.sender)?;
+
+    if env.block.time.seconds() >= pair_info.start_time {
+        return Err(ContractError::SaleStarted {});
+    }
+
+    match &merkle_root {
+        Some(merkle_root) => {
+            decode_hash(merkle_root)?;
+            MERKLE_ROOT.save(deps.storage, merkle_root)?;
+        }
+        None => MERKLE_ROOT.remove(deps.storage),
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_merkle_root"),
+        attr("merkle_root", merkle_root.unwrap_or_default()),
+    ]))
+}
+
+// Only owner can execute it
+pub fn try_update_purchase_cap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    purchase_cap: Option<PurchaseCap>,
+) -> Result<Response, ContractError> {
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    assert_owner(&pair_info, &info.sender)?;
+
+    if env.block.time.seconds() >= pair_info.start_time {
+        return Err(ContractError::SaleStarted {});
+    }
+
+    let purchase_cap_attr = match &purchase_cap {
+        Some(purchase_cap) => {
+            let (cap_type, cap_info, amount) = match purchase_cap {
+                PurchaseCap::Received { info, amount } => ("received", info, amount),
+                PurchaseCap::Spent { info, amount } => ("spent", info, amount),
+            };
+            if !pair_info
+                .asset_infos
+                .iter()
+                .any(|asset_info| asset_info.info.equal(cap_info))
+            {
+                return Err(ContractError::AssetMismatch {});
+            }
+
+            PURCHASE_CAP.save(deps.storage, purchase_cap)?;
+            format!("{} {}{}", cap_type, amount, cap_info)
+        }
+        None => {
+            PURCHASE_CAP.remove(deps.storage);
+            String::new()
+        }
+    };
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_purchase_cap"),
+        attr("purchase_cap", purchase_cap_attr),
+    ]))
+}
+
+// Only owner can execute it
+pub fn try_graduate_to_pool(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    assert_owner(&pair_info, &info.sender)?;
+
+    if pair_info.asset_infos.len() != 2 {
+        return Err(ContractError::TwoAssetPairsOnly {});
+    }
+
+    if env.block.time.seconds() <= pair_info.sale_end_time() {
+        return Err(ContractError::SaleNotEnded {});
+    }
+
+    assert_refunds_settled(deps.storage, &pair_info, env.block.time.seconds())?;
+
+    if GRADUATED_PAIR.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::SaleGraduated {});
+    }
+
+    let factory_config = query_factory_config(deps.as_ref(), &FACTORY_ADDR.load(deps.storage)?)?;
+    let xyk_pair_code_id = factory_config
+        .xyk_pair_code_id
+        .ok_or(ContractError::XykPairCodeIdNotSet {})?;
+
+    let pools: Vec<WeightedAsset> = get_pools(deps.as_ref(), &pair_info, &env.contract.address)?;
+    let total_share: Uint128 = query_supply(deps.as_ref(), &pair_info.liquidity_token)?;
+    let owner_share: Uint128 =
+        query_token_balance(deps.as_ref(), &pair_info.liquidity_token, &pair_info.owner)?;
+    if owner_share.is_zero() {
+        return Err(ContractError::InsufficientLiquidity {});
+    }
+
+    update_price_cumulatives(deps.storage, &env, &pair_info, &pools)?;
+
+    let share_ratio: Decimal = Decimal::from_ratio(owner_share, total_share);
+    let withdrawn: [Uint128; 2] = [pools[0].amount * share_ratio, pools[1].amount * share_ratio];
+
+    // the final sale price is (pool1 / weight1) / (pool0 / weight0), so the asset with the
+    // bigger weight is scaled down by the weight ratio and its remainder goes to the owner
+    let weights = get_weights_at(&pair_info, &pools, pair_info.sale_end_time())?;
+    let mut amounts = withdrawn;
+    if weights[0] <= weights[1] {
+        amounts[1] = Uint128::from(Uint256::from(withdrawn[1].u128()) * (weights[0] / weights[1]));
+    } else {
+        amounts[0] = Uint128::from(Uint256::from(withdrawn[0].u128()) * (weights[1] / weights[0]));
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: pair_info.liquidity_token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::BurnFrom {
+            owner: pair_info.owner.to_string(),
+            amount: owner_share,
+        })?,
+        funds: vec![],
+    })];
+    let mut graduation_assets: Vec<Asset> = vec![];
+    for (i, pool) in pools.iter().enumerate() {
+        let remainder = withdrawn[i] - amounts[i];
+        if !remainder.is_zero() {
+            messages.push(
+                Asset {
+                    info: pool.info.clone(),
+                    amount: remainder,
+                }
+                .into_msg(
+                    deps.as_ref(),
+                    env.contract.address.clone(),
+                    pair_info.owner.clone(),
+                )?,
+            );
+        }
+
+        let mut asset = Asset {
+            info: pool.info.clone(),
+            amount: amounts[i],
+        };
+        // native tokens are sent with the tax on top
+        if asset.is_native_token() {
+            asset.amount = asset.deduct_tax(deps.as_ref())?.amount;
+        }
+        graduation_assets.push(asset);
+    }
+
+    let graduation_assets = [graduation_assets[0].clone(), graduation_assets[1].clone()];
+    TMP_GRADUATION_ASSETS.save(deps.storage, &graduation_assets)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessage(SubMsg {
+            id: GRADUATION_REPLY_ID,
+            msg: WasmMsg::Instantiate {
+                admin: Some(factory_config.owner.to_string()),
+                code_id: xyk_pair_code_id,
+                msg: to_binary(&XykPairInstantiateMsg {
+                    asset_infos: [pools[0].info.clone(), pools[1].info.clone()],
+                    token_code_id: factory_config.token_code_id,
+                })?,
+                funds: vec![],
+                label: String::from("astroport-lbp graduated pair"),
+            }
+            .into(),
+            gas_limit: None,
+            reply_on: ReplyOn::Success,
+        })
+        .add_attributes(vec![
+            attr("action", "graduate_to_pool"),
+            attr("withdrawn_share", owner_share.to_string()),
+            attr(
+                "graduation_assets",
+                format!("{}, {}", graduation_assets[0], graduation_assets[1]),
+            ),
+        ]))
+}
+
+// Only owner can execute it
+pub fn try_cancel_sale(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    assert_owner(&pair_info, &info.sender)?;
+    assert_not_cancelled(&pair_info)?;
+
+    if env.block.time.seconds() >= pair_info.start_time {
+        return Err(ContractError::SaleStarted {});
+    }
+
+    let pools: Vec<WeightedAsset> = get_pools(deps.as_ref(), &pair_info, &env.contract.address)?;
+    let total_share: Uint128 = query_supply(deps.as_ref(), &pair_info.liquidity_token)?;
+    let owner_share: Uint128 =
+        query_token_balance(deps.as_ref(), &pair_info.liquidity_token, &pair_info.owner)?;
+    if owner_share != total_share {
+        return Err(ContractError::OtherProvidersLiquidity {});
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !owner_share.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: pair_info.liquidity_token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::BurnFrom {
+                owner: pair_info.owner.to_string(),
+                amount: owner_share,
+            })?,
+            funds: vec![],
+        }));
+    }
+
+    let refund_assets: Vec<Asset> = pools
+        .iter()
+        .map(|pool| Asset {
+            info: pool.info.clone(),
+            amount: pool.amount,
+        })
+        .collect();
+    for asset in refund_assets.iter().filter(|asset| !asset.amount.is_zero()) {
+        messages.push(asset.clone().into_msg(
+            deps.as_ref(),
+            env.contract.address.clone(),
+            pair_info.owner.clone(),
+        )?);
+    }
+
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: FACTORY_ADDR.load(deps.storage)?.to_string(),
+        msg: to_binary(&FactoryExecuteMsg::Unregister {
+            asset_infos: pair_info.infos(),
+        })?,
+        funds: vec![],
+    }));
+
+    pair_info.cancelled = true;
+    PAIR_INFO.save(deps.storage, &pair_info)?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "cancel_sale"),
+        attr("withdrawn_share", owner_share.to_string()),
+        attr("refund_assets", join_assets(&refund_assets)),
+    ]))
+}
+
+// Only owner can execute it
+pub fn try_update_vesting(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    vesting: Option<VestingConfig>,
+) -> Result<Response, ContractError> {
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    assert_owner(&pair_info, &info.sender)?;
+
+    if env.block.time.seconds() >= pair_info.start_time {
+        return Err(ContractError::SaleStarted {});
+    }
+
+    let vesting_attr = match &vesting {
+        Some(vesting) => {
+            if !pair_info
+                .asset_infos
+                .iter()
+                .any(|asset_info| asset_info.info.equal(&vesting.info))
+            {
+                return Err(ContractError::AssetMismatch {});
+            }
+
+            if vesting.cliff > vesting.duration {
+                return Err(ContractError::InvalidVesting {});
+            }
+
+            VESTING.save(deps.storage, vesting)?;
+            format!(
+                "{} cliff {} duration {}",
+                vesting.info, vesting.cliff, vesting.duration
+            )
+        }
+        None => {
+            VESTING.remove(deps.storage);
+            String::new()
+        }
+    };
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_vesting"),
+        attr("vesting", vesting_attr),
+    ]))
+}
+
+pub fn try_claim(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    let vesting = VESTING
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoVesting {})?;
+
+    if is_refunding(deps.storage, &pair_info, env.block.time.seconds())? {
+        return Err(ContractError::SoftCapNotReached {});
+    }
+
+    let mut vested_purchase = VESTED_PURCHASES
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    let vested = vested_amount(
+        &vesting,
+        pair_info.sale_end_time(),
+        vested_purchase.purchased,
+        env.block.time.seconds(),
+    );
+    let claimable = vested - vested_purchase.claimed;
+    if claimable.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    vested_purchase.claimed += claimable;
+    VESTED_PURCHASES.save(deps.storage, &info.sender, &vested_purchase)?;
+
+    let reserve = VESTING_RESERVE.load(deps.storage)?;
+    VESTING_RESERVE.save(deps.storage, &(reserve - claimable))?;
+
+    let claimed_asset = Asset {
+        info: vesting.info,
+        amount: claimable,
+    };
+
+    Ok(Response::new()
+        .add_message(claimed_asset.clone().into_msg(
+            deps.as_ref(),
+            env.contract.address,
+            info.sender,
+        )?)
+        .add_attributes(vec![
+            attr("action", "claim"),
+            attr("claimed_asset", claimed_asset.to_string()),
+        ]))
+}
+
+// Only owner can execute it
+pub fn try_update_soft_cap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    soft_cap: Option<Asset>,
+) -> Result<Response, ContractError> {
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    assert_owner(&pair_info, &info.sender)?;
+
+    if env.block.time.seconds() >= pair_info.start_time {
+        return Err(ContractError::SaleStarted {});
+    }
+
+    // refunds return the other asset of the pair
+    if soft_cap.is_some() && pair_info.asset_infos.len() != 2 {
+        return Err(ContractError::TwoAssetPairsOnly {});
+    }
+
+    let soft_cap_attr = match &soft_cap {
+        Some(soft_cap) => {
+            if !pair_info
+                .asset_infos
+                .iter()
+                .any(|asset_info| asset_info.info.equal(&soft_cap.info))
+            {
+                return Err(ContractError::AssetMismatch {});
+            }
+
+            if let Some(hard_cap) = HARD_CAP.may_load(deps.storage)? {
+                if hard_cap.info.equal(&soft_cap.info) && hard_cap.amount < soft_cap.amount {
+                    return Err(ContractError::SoftCapAboveHardCap {});
+                }
+            }
+
+            SOFT_CAP.save(deps.storage, soft_cap)?;
+            soft_cap.to_string()
+        }
+        None => {
+            SOFT_CAP.remove(deps.storage);
+            String::new()
+        }
+    };
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_soft_cap"),
+        attr("soft_cap", soft_cap_attr),
+    ]))
+}
+
+// Only owner can execute it
+pub fn try_update_hard_cap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    hard_cap: Option<Asset>,
+) -> Result<Response, ContractError> {
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    assert_owner(&pair_info, &info.sender)?;
+
+    if env.block.time.seconds() >= pair_info.start_time {
+        return Err(ContractError::SaleStarted {});
+    }
+
+    let hard_cap_attr = match &hard_cap {
+        Some(hard_cap) => {
+            if !pair_info
+                .asset_infos
+                .iter()
+                .any(|asset_info| asset_info.info.equal(&hard_cap.info))
+            {
+                return Err(ContractError::AssetMismatch {});
+            }
+
+            if hard_cap.amount.is_zero() {
+                return Err(ContractError::ZeroHardCap {});
+            }
+
+            if let Some(soft_cap) = SOFT_CAP.may_load(deps.storage)? {
+                if soft_cap.info.equal(&hard_cap.info) && hard_cap.amount < soft_cap.amount {
+                    return Err(ContractError::SoftCapAboveHardCap {});
+                }
+            }
+
+            HARD_CAP.save(deps.storage, hard_cap)?;
+            hard_cap.to_string()
+        }
+        None => {
+            HARD_CAP.remove(deps.storage);
+            String::new()
+        }
+    };
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_hard_cap"),
+        attr("hard_cap", hard_cap_attr),
+    ]))
+}
+
+/// Takes back the bought tokens, including the vested ones held by the pair,
+/// and refunds the same share of the paid amount
+pub fn try_refund(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    returned_asset: Option<Asset>,
+) -> Result<Response, ContractError> {
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+
+    if !is_refunding(deps.storage, &pair_info, env.block.time.seconds())? {
+        return Err(ContractError::RefundsUnavailable {});
+    }
+
+    let soft_cap = SOFT_CAP.load(deps.storage)?;
+    let sale_info = if pair_info.asset_infos[0].info.equal(&soft_cap.info) {
+        pair_info.asset_infos[1].info.clone()
+    } else {
+        pair_info.asset_infos[0].info.clone()
+    };
+
+    let mut returned_amount = match (returned_asset, &sale_info) {
+        (Some(returned_asset), _) => {
+            if !returned_asset.info.equal(&sale_info) {
+                return Err(ContractError::Unauthorized {});
+            }
+            returned_asset.amount
+        }
+        (None, AssetInfo::NativeToken { denom }) => amount_of(&info.funds, denom.to_string()),
+        (None, AssetInfo::Token { .. }) => Uint128::zero(),
+    };
+
+    if let Some(vesting) = VESTING.may_load(deps.storage)? {
+        if vesting.info.equal(&sale_info) {
+            if let Some(mut vested_purchase) = VESTED_PURCHASES.may_load(deps.storage, &sender)? {
+                let held = vested_purchase.purchased - vested_purchase.claimed;
+                vested_purchase.purchased = vested_purchase.claimed;
+                VESTED_PURCHASES.save(deps.storage, &sender, &vested_purchase)?;
+
+                let reserve = VESTING_RESERVE.load(deps.storage)?;
+                VESTING_RESERVE.save(deps.storage, &(reserve - held))?;
+                returned_amount += held;
+            }
+        }
+    }
+
+    let mut contribution: Contribution = CONTRIBUTIONS
+        .may_load(deps.storage, &sender)?
+        .unwrap_or_default();
+    let refund_amount = if contribution.received.is_zero() {
+        // the paid amount left without purchases is refunded without returning anything
+        if !returned_amount.is_zero() {
+            return Err(ContractError::RefundExceedsPurchase {});
+        }
+
+        if contribution.paid.is_zero() {
+            return Err(ContractError::NothingToRefund {});
+        }
+
+        contribution.paid
+    } else {
+        if returned_amount.is_zero() {
+            return Err(ContractError::NothingToRefund {});
+        }
+
+        if returned_amount > contribution.received {
+            return Err(ContractError::RefundExceedsPurchase {});
+        }
+
+        contribution
+            .paid
+            .multiply_ratio(returned_amount, contribution.received)
+    };
+    contribution.paid -= refund_amount;
+    contribution.received -= returned_amount;
+    CONTRIBUTIONS.save(deps.storage, &sender, &contribution)?;
+
+    let refundable = REFUNDABLE.load(deps.storage)?;
+    REFUNDABLE.save(deps.storage, &(refundable - refund_amount))?;
+
+    let refund_asset = Asset {
+        info: soft_cap.info,
+        amount: refund_amount,
+    };
+
+    Ok(Response::new()
+        .add_message(
+            refund_asset
+                .clone()
+                .into_msg(deps.as_ref(), env.contract.address, sender)?,
+        )
+        .add_attributes(vec![
+            attr("action", "refund"),
+            attr(
+                "returned_asset",
+                format!("{}{}", returned_amount, sale_info),
+            ),
+            attr("refund_asset", refund_asset.to_string()),
+        ]))
+}
+
+fn assert_owner(pair_info: &PairInfo, sender: &Addr) -> Result<(), ContractError> {
+    if *sender != pair_info.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(())
+}
+
+/// The native tokens sent with the message must match the amount of the asset
+fn assert_sent_native_token_balance(
+    asset: &Asset,
+    info: &MessageInfo,
+) -> Result<(), ContractError> {
+    asset
+        .assert_sent_native_token_balance(info)
+        .map_err(|_| ContractError::NativeBalanceMismatch {})
+}
+
+/// Only the sale owner seeds the pool of a SaleOwner pair and only before the sale starts
+fn assert_liquidity_policy(
+    pair_info: &PairInfo,
+    sender: &Addr,
+    block_time: u64,
+) -> Result<(), ContractError> {
+    if pair_info.liquidity_policy == LiquidityPolicy::SaleOwner {
+        assert_owner(pair_info, sender)?;
+
+        if block_time >= pair_info.start_time {
+            return Err(ContractError::SaleStarted {});
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Pair {} => to_binary(&query_pair_info(deps)?),
+        QueryMsg::Pool {} => to_binary(&query_pool(deps, env)?),
+        QueryMsg::Simulation {
+            offer_asset,
+            ask_asset_info,
+            block_time,
+        } => to_binary(&query_simulation(
+            deps,
+            env,
+            offer_asset,
+            ask_asset_info,
+            block_time,
+        )?),
+        QueryMsg::ReverseSimulation {
+            ask_asset,
+            offer_asset_info,
+            block_time,
+        } => to_binary(&query_reverse_simulation(
+            deps,
+            env,
+            ask_asset,
+            offer_asset_info,
+            block_time,
+        )?),
+        QueryMsg::ProvideSingleSimulation { asset, block_time } => to_binary(
+            &query_provide_single_simulation(deps, env, asset, block_time)?,
+        ),
+        QueryMsg::WithdrawSingleSimulation {
+            share,
+            asset_info,
+            block_time,
+        } => to_binary(&query_withdraw_single_simulation(
+            deps, env, share, asset_info, block_time,
+        )?),
+        QueryMsg::WeightSchedules {} => to_binary(&query_weight_schedules(deps)?),
+        QueryMsg::Stats {} => to_binary(&query_stats(deps)?),
+        QueryMsg::Eligibility {
+            address,
+            merkle_proof,
+        } => to_binary(&query_eligibility(deps, address, merkle_proof)?),
+        QueryMsg::Allowance { address } => to_binary(&query_allowance(deps, address)?),
+        QueryMsg::CumulativePrices {} => to_binary(&query_cumulative_prices(deps, env)?),
+        QueryMsg::Twap { start, end } => to_binary(&query_twap(deps, env, start, end)?),
+        QueryMsg::SpotPrice {
+            base_asset,
+            quote_asset,
+            at_time,
+        } => to_binary(&query_spot_price(
+            deps,
+            env,
+            base_asset,
+            quote_asset,
+            at_time,
+        )?),
+        QueryMsg::PriceCurve {
+            base_asset,
+            quote_asset,
+            points,
+        } => to_binary(&query_price_curve(
+            deps,
+            env,
+            base_asset,
+            quote_asset,
+            points,
+        )?),
+        QueryMsg::Graduation {} => to_binary(&query_graduation(deps)?),
+        QueryMsg::VestingInfo { address } => to_binary(&query_vesting_info(deps, env, address)?),
+        QueryMsg::SoftCap {} => to_binary(&query_soft_cap(deps, env)?),
+        QueryMsg::HardCap {} => to_binary(&query_hard_cap(deps)?),
+        QueryMsg::Contribution { address } => to_binary(&query_contribution(deps, address)?),
+    }
+}
+
+pub fn query_pair_info(deps: Deps) -> StdResult<PairInfo> {
+    PAIR_INFO.load(deps.storage)
+}
+
+pub fn query_weight_schedules(deps: Deps) -> StdResult<WeightSchedulesResponse> {
+    let schedules = WEIGHT_SCHEDULES.may_load(deps.storage)?.unwrap_or_default();
+
+    Ok(WeightSchedulesResponse { schedules })
+}
+
+pub fn query_graduation(deps: Deps) -> StdResult<GraduationResponse> {
+    Ok(GraduationResponse {
+        graduated_pair: GRADUATED_PAIR.may_load(deps.storage)?,
+    })
+}
+
+pub fn query_vesting_info(deps: Deps, env: Env, address: String) -> StdResult<VestingInfoResponse> {
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    let address = deps.api.addr_validate(&address)?;
+    let vesting = VESTING.may_load(deps.storage)?;
+    let vested_purchase = VESTED_PURCHASES
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+
+    let vested = match &vesting {
+        Some(vesting) => vested_amount(
+            vesting,
+            pair_info.sale_end_time(),
+            vested_purchase.purchased,
+            env.block.time.seconds(),
+        ),
+        None => Uint128::zero(),
+    };
+
+    Ok(VestingInfoResponse {
+        vesting,
+        purchased: vested_purchase.purchased,
+        claimed: vested_purchase.claimed,
+        vested,
+        claimable: vested - vested_purchase.claimed,
+    })
+}
+
+pub fn query_soft_cap(deps: Deps, env: Env) -> StdResult<SoftCapResponse> {
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    let soft_cap = SOFT_CAP.may_load(deps.storage)?;
+    let raised = match &soft_cap {
+        Some(soft_cap) => query_net_inflow(deps.storage, &pair_info, &soft_cap.info)?,
+        None => Uint128::zero(),
+    };
+
+    Ok(SoftCapResponse {
+        soft_cap,
+        raised,
+        refundable: REFUNDABLE.may_load(deps.storage)?.unwrap_or_default(),
+        refunding: is_refunding(deps.storage, &pair_info, env.block.time.seconds())?,
+    })
+}
+
+pub fn query_hard_cap(deps: Deps) -> StdResult<HardCapResponse> {
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    let hard_cap = HARD_CAP.may_load(deps.storage)?;
+    let inflow = match &hard_cap {
+        Some(hard_cap) => query_inflow(deps.storage, &pair_info, &hard_cap.info)?,
+        None => Uint128::zero(),
+    };
+
+    Ok(HardCapResponse {
+        hard_cap,
+        inflow,
+        closed_at: pair_info.closed_at,
+    })
+}
+
+pub fn query_contribution(deps: Deps, address: String) -> StdResult<ContributionResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let contribution = CONTRIBUTIONS
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+
+    Ok(ContributionResponse {
+        paid: contribution.paid,
+        received: contribution.received,
+    })
+}
+
+pub fn query_stats(deps: Deps) -> StdResult<StatsResponse> {
+    let assets = match STATS.may_load(deps.storage)? {
+        Some(stats) => stats,
+        None => empty_stats(&PAIR_INFO.load(deps.storage)?),
+    };
+
+    Ok(StatsResponse { assets })
+}
+
+pub fn query_eligibility(
+    deps: Deps,
+    address: String,
+    merkle_proof: Option<Vec<String>>,
+) -> StdResult<EligibilityResponse> {
+    let merkle_root = MERKLE_ROOT.may_load(deps.storage)?;
+    let eligible = match &merkle_root {
+        Some(merkle_root) => {
+            verify_merkle_proof(merkle_root, &address, &merkle_proof.unwrap_or_default())?
+        }
+        None => true,
+    };
+
+    Ok(EligibilityResponse {
+        merkle_root,
+        eligible,
+    })
+}
+
+pub fn query_allowance(deps: Deps, address: String) -> StdResult<AllowanceResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let purchase_cap = PURCHASE_CAP.may_load(deps.storage)?;
+    let purchased = PURCHASES
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    let remaining = purchase_cap
+        .as_ref()
+        .map(|purchase_cap| remaining_allowance(purchase_cap, purchased));
+
+    Ok(AllowanceResponse {
+        purchase_cap,
+        purchased,
+        remaining,
+    })
+}
+
+pub fn query_cumulative_prices(deps: Deps, env: Env) -> StdResult<CumulativePricesResponse> {
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    if pair_info.asset_infos.len() != 2 {
+        return Err(ContractError::TwoAssetPairsOnly {}.into());
+    }
+
+    let assets: Vec<WeightedAsset> = get_pools(deps, &pair_info, &env.contract.address)?;
+    let total_share: Uint128 = query_supply(deps, &pair_info.liquidity_token)?;
+
+    let price_cumulative =
+        price_cumulative_at(deps, &pair_info, &assets, env.block.time.seconds())?;
+
+    Ok(CumulativePricesResponse {
+        assets,
+        total_share,
+        price0_cumulative_last: price_cumulative.price0_cumulative,
+        price1_cumulative_last: price_cumulative.price1_cumulative,
+    })
+}
+
+pub fn query_twap(deps: Deps, env: Env, start: u64, end: u64) -> StdResult<TwapResponse> {
+    if start >= end || end > env.block.time.seconds() {
+        return Err(ContractError::InvalidTwapPeriod {}.into());
+    }
+
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    if pair_info.asset_infos.len() != 2 {
+        return Err(ContractError::TwoAssetPairsOnly {}.into());
+    }
+
+    let pools: Vec<WeightedAsset> = get_pools(deps, &pair_info, &env.contract.address)?;
+
+    let start_cumulative = price_cumulative_at(deps, &pair_info, &pools, start)?;
+    let end_cumulative = price_cumulative_at(deps, &pair_info, &pools, end)?;
+    let period = Uint128::from(u128::from(end - start) * PRICE_PRECISION);
+
+    Ok(TwapResponse {
+        start,
+        end,
+        price0_average: Decimal::from_ratio(
+            end_cumulative
+                .price0_cumulative
+                .wrapping_sub(start_cumulative.price0_cumulative),
+            period,
+        ),
+        price1_average: Decimal::from_ratio(
+            end_cumulative
+                .price1_cumulative
+                .wrapping_sub(start_cumulative.price1_cumulative),
+            period,
+        ),
+    })
+}
+
+pub fn query_spot_price(
+    deps: Deps,
+    env: Env,
+    base_asset: AssetInfo,
+    quote_asset: Option<AssetInfo>,
+    at_time: Option<u64>,
+) -> StdResult<SpotPriceResponse> {
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    let pools: Vec<WeightedAsset> = get_pools(deps, &pair_info, &env.contract.address)?;
+    let at_time = at_time.unwrap_or_else(|| env.block.time.seconds());
+    let (base, quote) = get_base_quote_indexes(&pools, &base_asset, quote_asset.as_ref())?;
+
+    let weights = get_weights_at(&pair_info, &pools, at_time)?;
+    let spot_price = calc_spot_price(
+        pools[base].amount,
+        weights[base],
+        pools[quote].amount,
+        weights[quote],
+    );
+    let spot_price_with_fee =
+        spot_price.div(Decimal256::one() - Decimal256::from(pair_info.swap_fee));
+
+    Ok(SpotPriceResponse {
+        base_asset,
+        quote_asset: pools[quote].info.clone(),
+        at_time,
+        spot_price: Decimal::from_str(&spot_price.to_string())?,
+        spot_price_with_fee: Decimal::from_str(&spot_price_with_fee.to_string())?,
+    })
+}
+
+pub fn query_price_curve(
+    deps: Deps,
+    env: Env,
+    base_asset: AssetInfo,
+    quote_asset: Option<AssetInfo>,
+    points: u32,
+) -> StdResult<PriceCurveResponse> {
+    if !(2..=MAX_PRICE_CURVE_POINTS).contains(&points) {
+        return Err(ContractError::InvalidPricePoints {
+            max: MAX_PRICE_CURVE_POINTS,
+        }
+        .into());
+    }
+
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+    let pools: Vec<WeightedAsset> = get_pools(deps, &pair_info, &env.contract.address)?;
+    let (base, quote) = get_base_quote_indexes(&pools, &base_asset, quote_asset.as_ref())?;
+
+    let duration = pair_info.end_time - pair_info.start_time;
+    let points = (0..points)
+        .map(|i| {
+            let time = pair_info.start_time
+                + Uint128::from(duration).multiply_ratio(i, points - 1).u128() as u64;
+            let weights = get_weights_at(&pair_info, &pools, time)?;
+            let spot_price = calc_spot_price(
+                pools[base].amount,
+                weights[base],
+                pools[quote].amount,
+                weights[quote],
+            );
+
+            Ok(PricePoint {
+                time,
+                base_weight: weights[base].to_string(),
+                quote_weight: weights[quote].to_string(),
+                spot_price: Decimal::from_str(&spot_price.to_string())?,
+            })
+        })
+        .collect::<StdResult<Vec<PricePoint>>>()?;
+
+    Ok(PriceCurveResponse {
+        base_asset,
+        quote_asset: pools[quote].info.clone(),
+        points,
+    })
+}
+
+/// Returns the indexes of the base and the quote asset in the pools,
+/// the spot price is only available if both pools have liquidity
+fn get_base_quote_indexes(
+    pools: &[WeightedAsset],
+    base_asset: &AssetInfo,
+    quote_asset: Option<&AssetInfo>,
+) -> Result<(usize, usize), ContractError> {
+    let (base, quote) = get_swap_indexes(pools, base_asset, quote_asset)?;
+
+    if pools[base].amount.is_zero() || pools[quote].amount.is_zero() {
+        return Err(ContractError::InsufficientLiquidity {});
+    }
+
+    Ok((base, quote))
+}
+
+/// Returns the indexes of the given asset and of the other asset of the swap in the pools,
+/// the other asset can only be omitted in two asset pairs
+fn get_swap_indexes(
+    pools: &[WeightedAsset],
+    asset_info: &AssetInfo,
+    other_asset_info: Option<&AssetInfo>,
+) -> Result<(usize, usize), ContractError> {
+    let index = pools
+        .iter()
+        .position(|pool| pool.info.equal(asset_info))
+        .ok_or(ContractError::AssetMismatch {})?;
+
+    let other_index = match other_asset_info {
+        Some(other_asset_info) => pools
+            .iter()
+            .position(|pool| pool.info.equal(other_asset_info))
+            .filter(|other_index| *other_index != index)
+            .ok_or(ContractError::AssetMismatch {})?,
+        None if pools.len() == 2 => 1 - index,
+        None => return Err(ContractError::OtherAssetRequired {}),
+    };
+
+    Ok((index, other_index))
+}
+
+/// Returns the asset weights at the given time, the weights stay at the start weights
+/// before the sale and at the weights it finished with after it
+fn get_weights_at(
+    pair_info: &PairInfo,
+    pools: &[WeightedAsset],
+    time: u64,
+) -> Result<Vec<Decimal256>, ContractError> {
+    let time = time
+        .max(pair_info.start_time)
+        .min(pair_info.sale_end_time());
+
+    pools
+        .iter()
+        .map(|pool| {
+            get_current_weight(
+                pool.start_weight,
+                pool.end_weight,
+                pool.curve.as_ref(),
+                pair_info.start_time,
+                pair_info.end_time,
+                None,
+                time,
+            )
+        })
+        .collect()
+}
+
+/// Returns the index of the asset pool and the asset weight at the given time
+/// normalized by the sum of the weights
+fn get_normalized_weight(
+    pair_info: &PairInfo,
+    pools: &[WeightedAsset],
+    asset_info: &AssetInfo,
+    time: u64,
+) -> Result<(usize, Decimal256), ContractError> {
+    let index = pools
+        .iter()
+        .position(|pool| pool.info.equal(asset_info))
+        .ok_or(ContractError::AssetMismatch {})?;
+
+    let weights = get_normalized_weights(pair_info, pools, time)?;
+
+    Ok((index, weights[index]))
+}
+
+/// Returns the weights of the pools at the given time normalized by the sum of the weights
+fn get_normalized_weights(
+    pair_info: &PairInfo,
+    pools: &[WeightedAsset],
+    time: u64,
+) -> Result<Vec<Decimal256>, ContractError> {
+    let weights = get_weights_at(pair_info, pools, time)?;
+    let total_weight = weights
+        .iter()
+        .fold(Decimal256::zero(), |total, weight| total + *weight);
+
+    Ok(weights
+        .iter()
+        .map(|weight| *weight / total_weight)
+        .collect())
+}
+
+/// Returns the price accumulators at the given time, between two updates they are interpolated
+/// and after the last update they are accumulated with the current pools
+fn price_cumulative_at(
+    deps: Deps,
+    pair_info: &PairInfo,
+    pools: &[WeightedAsset],
+    time: u64,
+) -> Result<PriceCumulative, ContractError> {
+    let before = PRICE_CUMULATIVES
+        .range(
+            deps.storage,
+            None,
+            Some(Bound::inclusive_int(time)),
+            Order::Descending,
+        )
+        .next()
+        .transpose()?
+        .map(|(_, price_cumulative)| price_cumulative)
+        .ok_or(ContractError::PriceNotRecorded {})?;
+
+    let after = PRICE_CUMULATIVES
+        .range(
+            deps.storage,
+            Some(Bound::exclusive_int(time)),
+            None,
+            Order::Ascending,
+        )
+        .next()
+        .transpose()?
+        .map(|(_, price_cumulative)| price_cumulative);
+
+    match after {
+        Some(after) => {
+            let elapsed = time - before.block_time;
+            let period = after.block_time - before.block_time;
+            let interpolate = |before: Uint128, after: Uint128| {
+                before.wrapping_add(after.wrapping_sub(before).multiply_ratio(elapsed, period))
+            };
+
+            Ok(PriceCumulative {
+                block_time: time,
+                price0_cumulative: interpolate(before.price0_cumulative, after.price0_cumulative),
+                price1_cumulative: interpolate(before.price1_cumulative, after.price1_cumulative),
+            })
+        }
+        None => Ok(accumulate_prices(pair_info, pools, &before, time)?),
+    }
+}
+
+/// Updates the price accumulators with the pools before their balances change,
+/// prices are only tracked in two asset pairs
+fn update_price_cumulatives(
+    storage: &mut dyn Storage,
+    env: &Env,
+    pair_info: &PairInfo,
+    pools: &[WeightedAsset],
+) -> Result<(), ContractError> {
+    if pools.len() != 2 {
+        return Ok(());
+    }
+
+    let block_time = env.block.time.seconds();
+    let records: Vec<PriceCumulative> = PRICE_CUMULATIVES
+        .range(storage, None, None, Order::Descending)
+        .take(2)
+        .map(|item| item.map(|(_, price_cumulative)| price_cumulative))
+        .collect::<StdResult<Vec<PriceCumulative>>>()?;
+
+    let price_cumulative = match records.first() {
+        Some(last) if last.block_time >= block_time => return Ok(()),
+        Some(last) => accumulate_prices(pair_info, pools, last, block_time)?,
+        None => PriceCumulative {
+            block_time,
+            price0_cumulative: Uint128::zero(),
+            price1_cumulative: Uint128::zero(),
+        },
+    };
+
+    // the last record is replaced until it is PRICE_RECORD_INTERVAL apart from the one before it
+    if let [last, prev] = records.as_slice() {
+        if last.block_time - prev.block_time < PRICE_RECORD_INTERVAL {
+            PRICE_CUMULATIVES.remove(storage, U64Key::new(last.block_time));
+        }
+    }
+    PRICE_CUMULATIVES.save(storage, U64Key::new(block_time), &price_cumulative)?;
+
+    // the last record before the history period is kept to interpolate the prices within it
+    let expired: Vec<u64> = PRICE_CUMULATIVES
+        .range(
+            storage,
+            None,
+            Some(Bound::inclusive_int(
+                block_time.saturating_sub(PRICE_HISTORY_PERIOD),
+            )),
+            Order::Ascending,
+        )
+        .take(3)
+        .map(|item| item.map(|(_, price_cumulative)| price_cumulative.block_time))
+        .collect::<StdResult<Vec<u64>>>()?;
+    for time in expired.iter().rev().skip(1) {
+        PRICE_CUMULATIVES.remove(storage, U64Key::new(*time));
+    }
+
+    Ok(())
+}
+
+/// Adds the spot prices of the two assets at block_time over the time elapsed
+/// since the last update
+fn accumulate_prices(
+    pair_info: &PairInfo,
+    pools: &[WeightedAsset],
+    last: &PriceCumulative,
+    block_time: u64,
+) -> Result<PriceCumulative, ContractError> {
+    let mut price_cumulative = PriceCumulative {
+        block_time,
+        ..last.clone()
+    };
+
+    // there is no price without liquidity
+    if pools[0].amount.is_zero() || pools[1].amount.is_zero() {
+        return Ok(price_cumulative);
+    }
+
+    let weights = get_weights_at(pair_info, pools, block_time)?;
+    let time_elapsed = Uint256::from(u128::from(block_time - last.block_time) * PRICE_PRECISION);
+
+    let price0 = to_price_increment(
+        calc_spot_price(pools[0].amount, weights[0], pools[1].amount, weights[1]) * time_elapsed,
+    )?;
+    let price1 = to_price_increment(
+        calc_spot_price(pools[1].amount, weights[1], pools[0].amount, weights[0]) * time_elapsed,
     )?;
 
-    let offer_amount = offer_asset.amount;
-    let (return_amount, spread_amount, commission_amount) = compute_swap(
-        offer_pool.amount,
-        offer_weight,
-        ask_pool.amount,
-        ask_weight,
-        offer_amount,
-    )?;
+    price_cumulative.price0_cumulative = last.price0_cumulative.wrapping_add(price0);
+    price_cumulative.price1_cumulative = last.price1_cumulative.wrapping_add(price1);
 
-    // check max spread limit if exist
-    assert_max_spread(
-        belief_price,
-        max_spread,
-        offer_amount,
-        return_amount + commission_amount,
-        spread_amount,
-    )?;
+    Ok(price_cumulative)
+}
 
-    // compute tax
-    let return_asset = Asset {
-        info: ask_pool.info.clone(),
-        amount: return_amount,
-    };
+/// Accumulators wrap around, but a single increment must fit into Uint128
+fn to_price_increment(increment: Uint256) -> Result<Uint128, ContractError> {
+    if increment > Uint256::from(u128::MAX) {
+        return Err(ContractError::PriceAccumulatorOverflow {});
+    }
+
+    Ok(increment.into())
+}
 
-    let tax_amount = return_asset.compute_tax(deps.as_ref())?;
+/// Returns the pools without the purchased amount which is not claimed yet
+fn get_pools(
+    deps: Deps,
+    pair_info: &PairInfo,
+    contract_addr: &Addr,
+) -> StdResult<Vec<WeightedAsset>> {
+    let mut pools = pair_info.query_pools(deps, contract_addr)?;
+    if let Some(vesting) = VESTING.may_load(deps.storage)? {
+        let reserve = VESTING_RESERVE.may_load(deps.storage)?.unwrap_or_default();
+        for pool in pools.iter_mut() {
+            if pool.info.equal(&vesting.info) {
+                pool.amount = pool.amount.checked_sub(reserve)?;
+            }
+        }
+    }
 
-    // 1. send collateral token from the contract to a user
-    // 2. send inactive commission to collector
-    Ok(Response::new()
-        .add_message(return_asset.into_msg(
-            deps.as_ref(),
-            env.contract.address,
-            to.unwrap_or(sender),
-        )?)
-        .add_attributes(vec![
-            attr("action", "swap"),
-            attr("offer_asset", offer_asset.info.to_string()),
-            attr("ask_asset", ask_pool.info.to_string()),
-            attr("offer_amount", offer_amount.to_string()),
-            attr("return_amount", return_amount.to_string()),
-            attr("tax_amount", tax_amount.to_string()),
-            attr("spread_amount", spread_amount.to_string()),
-            attr("commission_amount", commission_amount.to_string()),
-        ]))
+    Ok(pools)
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::Pair {} => to_binary(&query_pair_info(deps)?),
-        QueryMsg::Pool {} => to_binary(&query_pool(deps, env)?),
-        QueryMsg::Simulation {
-            offer_asset,
-            block_time,
-        } => to_binary(&query_simulation(deps, env, offer_asset, block_time)?),
-        QueryMsg::ReverseSimulation {
-            ask_asset,
-            block_time,
-        } => to_binary(&query_reverse_simulation(deps, env, ask_asset, block_time)?),
+/// Purchases vest linearly from the end of the sale, nothing is vested before the cliff
+pub fn vested_amount(
+    vesting: &VestingConfig,
+    end_time: u64,
+    purchased: Uint128,
+    time: u64,
+) -> Uint128 {
+    // a cliff past the end of time is never reached
+    match end_time.checked_add(vesting.cliff) {
+        Some(cliff_end) if time >= cliff_end => {}
+        _ => return Uint128::zero(),
+    }
+
+    let elapsed = time - end_time;
+    if elapsed >= vesting.duration {
+        purchased
+    } else {
+        purchased.multiply_ratio(elapsed, vesting.duration)
     }
 }
 
-pub fn query_pair_info(deps: Deps) -> StdResult<PairInfo> {
-    PAIR_INFO.load(deps.storage)
+/// Returns the amount of the asset offered in swaps so far
+fn query_inflow(
+    storage: &dyn Storage,
+    pair_info: &PairInfo,
+    info: &AssetInfo,
+) -> StdResult<Uint128> {
+    let stats = STATS
+        .may_load(storage)?
+        .unwrap_or_else(|| empty_stats(pair_info));
+
+    Ok(stats
+        .iter()
+        .find(|asset_stats| asset_stats.info.equal(info))
+        .map(|asset_stats| asset_stats.volume_in)
+        .unwrap_or_default())
+}
+
+/// Returns the amount of the asset offered in swaps minus the amount paid out of the pool
+fn query_net_inflow(
+    storage: &dyn Storage,
+    pair_info: &PairInfo,
+    info: &AssetInfo,
+) -> StdResult<Uint128> {
+    let stats = STATS
+        .may_load(storage)?
+        .unwrap_or_else(|| empty_stats(pair_info));
+
+    Ok(stats
+        .iter()
+        .find(|asset_stats| asset_stats.info.equal(info))
+        .map(|asset_stats| {
+            asset_stats
+                .volume_in
+                .saturating_sub(asset_stats.volume_out + asset_stats.protocol_fee_amount)
+        })
+        .unwrap_or_default())
+}
+
+/// Returns whether the sale has ended below its soft cap and its refund period is not over
+fn is_refunding(storage: &dyn Storage, pair_info: &PairInfo, time: u64) -> StdResult<bool> {
+    let sale_end_time = pair_info.sale_end_time();
+    if time <= sale_end_time || time > sale_end_time.saturating_add(REFUND_PERIOD) {
+        return Ok(false);
+    }
+
+    match SOFT_CAP.may_load(storage)? {
+        Some(soft_cap) => {
+            let raised = query_net_inflow(storage, pair_info, &soft_cap.info)?;
+            Ok(raised < soft_cap.amount)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Joins the assets with commas, e.g. `100uusd, 200asset0000`
+fn join_assets(assets: &[Asset]) -> String {
+    assets
+        .iter()
+        .map(|asset| asset.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Adds the `asset_{i}` and `amount_{i}` attributes of every asset to the event
+fn with_assets(event: Event, assets: &[Asset]) -> Event {
+    assets.iter().enumerate().fold(event, |event, (i, asset)| {
+        event
+            .add_attribute(format!("asset_{}", i), asset.info.to_string())
+            .add_attribute(format!("amount_{}", i), asset.amount.to_string())
+    })
+}
+
+/// Adds the `weight_{i}` attributes of the normalized pool weights to the event
+fn with_weights(event: Event, weights: &[Decimal256]) -> Event {
+    weights
+        .iter()
+        .enumerate()
+        .fold(event, |event, (i, weight)| {
+            event.add_attribute(format!("weight_{}", i), weight.to_string())
+        })
+}
+
+fn join_weights<'a>(weights: impl Iterator<Item = &'a Uint128>) -> String {
+    weights
+        .map(|weight| weight.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn assert_not_cancelled(pair_info: &PairInfo) -> Result<(), ContractError> {
+    if pair_info.cancelled {
+        return Err(ContractError::SaleCancelled {});
+    }
+
+    Ok(())
+}
+
+fn assert_refunds_settled(
+    storage: &dyn Storage,
+    pair_info: &PairInfo,
+    time: u64,
+) -> Result<(), ContractError> {
+    if is_refunding(storage, pair_info, time)?
+        && !REFUNDABLE.may_load(storage)?.unwrap_or_default().is_zero()
+    {
+        return Err(ContractError::RefundsNotSettled {});
+    }
+
+    Ok(())
+}
+
+fn remaining_allowance(purchase_cap: &PurchaseCap, purchased: Uint128) -> Uint128 {
+    match purchase_cap {
+        PurchaseCap::Received { amount, .. } | PurchaseCap::Spent { amount, .. } => {
+            amount.saturating_sub(purchased)
+        }
+    }
+}
+
+fn empty_stats(pair_info: &PairInfo) -> Vec<AssetStats> {
+    pair_info
+        .asset_infos
+        .iter()
+        .map(|asset_info| AssetStats {
+            info: asset_info.info.clone(),
+            volume_in: Uint128::zero(),
+            volume_out: Uint128::zero(),
+            commission_amount: Uint128::zero(),
+            protocol_fee_amount: Uint128::zero(),
+        })
+        .collect()
 }
 
 pub fn query_pool(deps: Deps, env: Env) -> StdResult<PoolResponse> {
     let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
-    let assets: [WeightedAsset; 2] = pair_info.query_pools(deps, &env.contract.address)?;
+    let assets: Vec<WeightedAsset> = get_pools(deps, &pair_info, &env.contract.address)?;
     let total_share: Uint128 = query_supply(deps, &pair_info.liquidity_token)?;
 
     let resp = PoolResponse {
@@ -504,39 +2872,36 @@ pub fn query_simulation(
     deps: Deps,
     env: Env,
     offer_asset: Asset,
+    ask_asset_info: Option<AssetInfo>,
     block_time: u64,
 ) -> StdResult<SimulationResponse> {
     let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
 
-    let pools: [WeightedAsset; 2] = pair_info.query_pools(deps, &env.contract.address)?;
+    let pools: Vec<WeightedAsset> = get_pools(deps, &pair_info, &env.contract.address)?;
+    let (offer_index, ask_index) =
+        get_swap_indexes(&pools, &offer_asset.info, ask_asset_info.as_ref())?;
+    let offer_pool: WeightedAsset = pools[offer_index].clone();
+    let ask_pool: WeightedAsset = pools[ask_index].clone();
 
-    let offer_pool: WeightedAsset;
-    let ask_pool: WeightedAsset;
-    if offer_asset.info.equal(&pools[0].info) {
-        offer_pool = pools[0].clone();
-        ask_pool = pools[1].clone();
-    } else if offer_asset.info.equal(&pools[1].info) {
-        offer_pool = pools[1].clone();
-        ask_pool = pools[0].clone();
-    } else {
-        return Err(StdError::generic_err(
-            "Given offer asset does not belong to pairs",
-        ));
-    }
+    assert_swap_direction(&pair_info, &offer_pool.info)?;
 
     let ask_weight = get_current_weight(
         ask_pool.start_weight,
         ask_pool.end_weight,
+        ask_pool.curve.as_ref(),
         pair_info.start_time,
         pair_info.end_time,
+        pair_info.closed_at,
         block_time,
     )?;
 
     let offer_weight = get_current_weight(
         offer_pool.start_weight,
         offer_pool.end_weight,
+        offer_pool.curve.as_ref(),
         pair_info.start_time,
         pair_info.end_time,
+        pair_info.closed_at,
         block_time,
     )?;
 
@@ -546,6 +2911,9 @@ pub fn query_simulation(
         ask_pool.amount,
         ask_weight,
         offer_asset.amount,
+        pair_info.swap_fee,
+        pair_info.max_in_ratio,
+        pair_info.max_out_ratio,
     )?;
 
     Ok(SimulationResponse {
@@ -554,6 +2922,8 @@ pub fn query_simulation(
         commission_amount,
         ask_weight: ask_weight.to_string(),
         offer_weight: offer_weight.to_string(),
+        swap_fee: pair_info.swap_fee,
+        protocol_fee_amount: compute_protocol_fee_amount(deps, commission_amount)?,
     })
 }
 
@@ -561,38 +2931,35 @@ pub fn query_reverse_simulation(
     deps: Deps,
     env: Env,
     ask_asset: Asset,
+    offer_asset_info: Option<AssetInfo>,
     block_time: u64,
 ) -> StdResult<ReverseSimulationResponse> {
     let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
 
-    let pools: [WeightedAsset; 2] = pair_info.query_pools(deps, &env.contract.address)?;
+    let pools: Vec<WeightedAsset> = get_pools(deps, &pair_info, &env.contract.address)?;
+    let (ask_index, offer_index) =
+        get_swap_indexes(&pools, &ask_asset.info, offer_asset_info.as_ref())?;
+    let offer_pool: WeightedAsset = pools[offer_index].clone();
+    let ask_pool: WeightedAsset = pools[ask_index].clone();
 
-    let offer_pool: WeightedAsset;
-    let ask_pool: WeightedAsset;
-    if ask_asset.info.equal(&pools[0].info) {
-        ask_pool = pools[0].clone();
-        offer_pool = pools[1].clone();
-    } else if ask_asset.info.equal(&pools[1].info) {
-        ask_pool = pools[1].clone();
-        offer_pool = pools[0].clone();
-    } else {
-        return Err(StdError::generic_err(
-            "Given ask asset is not blong to pairs",
-        ));
-    }
+    assert_swap_direction(&pair_info, &offer_pool.info)?;
 
     let ask_weight = get_current_weight(
         ask_pool.start_weight,
         ask_pool.end_weight,
+        ask_pool.curve.as_ref(),
         pair_info.start_time,
         pair_info.end_time,
+        pair_info.closed_at,
         block_time,
     )?;
     let offer_weight = get_current_weight(
         offer_pool.start_weight,
         offer_pool.end_weight,
+        offer_pool.curve.as_ref(),
         pair_info.start_time,
         pair_info.end_time,
+        pair_info.closed_at,
         block_time,
     )?;
 
@@ -602,6 +2969,9 @@ pub fn query_reverse_simulation(
         ask_pool.amount,
         ask_weight,
         ask_asset.amount,
+        pair_info.swap_fee,
+        pair_info.max_in_ratio,
+        pair_info.max_out_ratio,
     )?;
 
     Ok(ReverseSimulationResponse {
@@ -610,9 +2980,88 @@ pub fn query_reverse_simulation(
         commission_amount,
         ask_weight: ask_weight.to_string(),
         offer_weight: offer_weight.to_string(),
+        swap_fee: pair_info.swap_fee,
+        protocol_fee_amount: compute_protocol_fee_amount(deps, commission_amount)?,
+    })
+}
+
+pub fn query_provide_single_simulation(
+    deps: Deps,
+    env: Env,
+    asset: Asset,
+    block_time: u64,
+) -> StdResult<ProvideSingleSimulationResponse> {
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+
+    let pools: Vec<WeightedAsset> = get_pools(deps, &pair_info, &env.contract.address)?;
+    let (index, weight) = get_normalized_weight(&pair_info, &pools, &asset.info, block_time)?;
+    let total_share: Uint128 = query_supply(deps, &pair_info.liquidity_token)?;
+
+    if total_share.is_zero() || pools[index].amount.is_zero() {
+        return Err(ContractError::InsufficientLiquidity {}.into());
+    }
+
+    let (share, fee_amount) = compute_single_asset_join(
+        pools[index].amount,
+        weight,
+        total_share,
+        asset.amount,
+        pair_info.swap_fee,
+        pair_info.max_in_ratio,
+    )?;
+
+    Ok(ProvideSingleSimulationResponse { share, fee_amount })
+}
+
+pub fn query_withdraw_single_simulation(
+    deps: Deps,
+    env: Env,
+    share: Uint128,
+    asset_info: AssetInfo,
+    block_time: u64,
+) -> StdResult<WithdrawSingleSimulationResponse> {
+    let pair_info: PairInfo = PAIR_INFO.load(deps.storage)?;
+
+    let pools: Vec<WeightedAsset> = get_pools(deps, &pair_info, &env.contract.address)?;
+    let (index, weight) = get_normalized_weight(&pair_info, &pools, &asset_info, block_time)?;
+    let total_share: Uint128 = query_supply(deps, &pair_info.liquidity_token)?;
+
+    let (return_amount, fee_amount) = compute_single_asset_exit(
+        pools[index].amount,
+        weight,
+        total_share,
+        share,
+        pair_info.swap_fee,
+        pair_info.max_out_ratio,
+    )?;
+
+    Ok(WithdrawSingleSimulationResponse {
+        return_amount,
+        fee_amount,
     })
 }
 
+/// Returns the protocol fee collector and the protocol share of the commission from the factory,
+/// pairs instantiated without the factory or without a fee collector pay no protocol fee
+fn query_protocol_fee(deps: Deps) -> StdResult<Option<(Addr, Decimal)>> {
+    let factory_addr = FACTORY_ADDR.load(deps.storage)?;
+    // pairs instantiated without the factory do not pay the protocol fee
+    let protocol_fee = query_factory_config_if_exists(deps, &factory_addr)?.and_then(|config| {
+        let protocol_fee_share = config.protocol_fee_share;
+        config
+            .fee_address
+            .map(|fee_address| (fee_address, protocol_fee_share))
+    });
+
+    Ok(protocol_fee)
+}
+
+fn compute_protocol_fee_amount(deps: Deps, commission_amount: Uint128) -> StdResult<Uint128> {
+    Ok(query_protocol_fee(deps)?
+        .map(|(_, protocol_fee_share)| commission_amount * protocol_fee_share)
+        .unwrap_or_default())
+}
+
 pub fn amount_of(coins: &[Coin], denom: String) -> Uint128 {
     match coins.iter().find(|x| x.denom == denom) {
         Some(coin) => coin.amount,
@@ -627,30 +3076,36 @@ fn get_ask_by_spot_price(
     ask_weight: Decimal256,
     offer_amount: Uint128,
 ) -> Uint128 {
-    let ask_pool: Uint256 = ask_pool.into();
-    let offer_pool: Uint256 = offer_pool.into();
     let offer_amount: Uint256 = offer_amount.into();
 
-    let ask_ratio = Decimal256::from_uint256(ask_pool)
-        .div(Decimal256::from_str(&ask_weight.to_string()).unwrap());
-    let offer_ratio = Decimal256::from_uint256(offer_pool)
-        .div(Decimal256::from_str(&offer_weight.to_string()).unwrap());
-
-    let ask_amount = ask_ratio.div(offer_ratio).mul(offer_amount);
+    let ask_amount =
+        calc_spot_price(offer_pool, offer_weight, ask_pool, ask_weight).mul(offer_amount);
 
     ask_amount.into()
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn compute_swap(
     offer_pool: Uint128,
     offer_weight: Decimal256,
     ask_pool: Uint128,
     ask_weight: Decimal256,
     offer_amount: Uint128,
-) -> StdResult<(Uint128, Uint128, Uint128)> {
+    swap_fee: Decimal,
+    max_in_ratio: Decimal,
+    max_out_ratio: Decimal,
+) -> Result<(Uint128, Uint128, Uint128), ContractError> {
     // offer => ask
+    if offer_amount > offer_pool * max_in_ratio {
+        return Err(ContractError::MaxInRatioExceeded { max_in_ratio });
+    }
+
     let return_amount =
-        calc_out_given_in(offer_pool, offer_weight, ask_pool, ask_weight, offer_amount);
+        calc_out_given_in(offer_pool, offer_weight, ask_pool, ask_weight, offer_amount)?;
+
+    if return_amount > ask_pool * max_out_ratio {
+        return Err(ContractError::MaxOutRatioExceeded { max_out_ratio });
+    }
 
     // calculate spread & commission
     let spot_price =
@@ -660,35 +3115,47 @@ pub fn compute_swap(
         .checked_sub(return_amount)
         .unwrap_or_else(|_| Uint128::zero());
 
-    let commission_amount: Uint128 = return_amount * Decimal::from_str(COMMISSION_RATE).unwrap();
+    let commission_amount: Uint128 = return_amount * swap_fee;
 
     // commission will be absorbed to pool
-    let return_amount: Uint128 = return_amount.checked_sub(commission_amount).unwrap();
+    let return_amount: Uint128 = return_amount.checked_sub(commission_amount)?;
 
     Ok((return_amount, spread_amount, commission_amount))
 }
 
+#[allow(clippy::too_many_arguments)]
 fn compute_offer_amount(
     offer_pool: Uint128,
     offer_weight: Decimal256,
     ask_pool: Uint128,
     ask_weight: Decimal256,
     ask_amount: Uint128,
-) -> StdResult<(Uint128, Uint128, Uint128)> {
+    swap_fee: Decimal,
+    max_in_ratio: Decimal,
+    max_out_ratio: Decimal,
+) -> Result<(Uint128, Uint128, Uint128), ContractError> {
     // ask => offer
 
-    let one_minus_commission = Decimal256::one() - Decimal256::from_str(COMMISSION_RATE).unwrap();
+    let one_minus_commission = Decimal256::one() - Decimal256::from(swap_fee);
 
     let before_commission_deduction =
         ask_amount * (Decimal256::one() / one_minus_commission).into();
 
+    if before_commission_deduction > ask_pool * max_out_ratio {
+        return Err(ContractError::MaxOutRatioExceeded { max_out_ratio });
+    }
+
     let offer_amount = calc_in_given_out(
         offer_pool,
         offer_weight,
         ask_pool,
         ask_weight,
         before_commission_deduction,
-    );
+    )?;
+
+    if offer_amount > offer_pool * max_in_ratio {
+        return Err(ContractError::MaxInRatioExceeded { max_in_ratio });
+    }
 
     let spot_price =
         get_ask_by_spot_price(offer_pool, offer_weight, ask_pool, ask_weight, offer_amount);
@@ -697,12 +3164,55 @@ fn compute_offer_amount(
         .checked_sub(before_commission_deduction)
         .unwrap_or_else(|_| Uint128::zero());
 
-    let commission_amount =
-        before_commission_deduction * Decimal::from_str(COMMISSION_RATE).unwrap();
+    let commission_amount = before_commission_deduction * swap_fee;
 
     Ok((offer_amount, spread_amount, commission_amount))
 }
 
+/// Computes the share minted for a single asset deposit, the swap fee is charged on the part
+/// of the deposit which is not proportional to the pool, `1 - weight` of it
+pub fn compute_single_asset_join(
+    pool: Uint128,
+    weight: Decimal256,
+    total_share: Uint128,
+    amount: Uint128,
+    swap_fee: Decimal,
+    max_in_ratio: Decimal,
+) -> Result<(Uint128, Uint128), ContractError> {
+    if amount > pool * max_in_ratio {
+        return Err(ContractError::MaxInRatioExceeded { max_in_ratio });
+    }
+
+    let fee_ratio: Decimal = ((Decimal256::one() - weight) * Decimal256::from(swap_fee)).into();
+    let fee_amount: Uint128 = amount * fee_ratio;
+
+    let share = calc_share_given_single_in(pool, weight, total_share, amount - fee_amount)?;
+
+    Ok((share, fee_amount))
+}
+
+/// Computes the amount of an asset returned for a single asset withdrawal, the swap fee is
+/// charged on the part of the return which is not proportional to the pool, `1 - weight` of it
+pub fn compute_single_asset_exit(
+    pool: Uint128,
+    weight: Decimal256,
+    total_share: Uint128,
+    share: Uint128,
+    swap_fee: Decimal,
+    max_out_ratio: Decimal,
+) -> Result<(Uint128, Uint128), ContractError> {
+    let before_fee_deduction = calc_single_out_given_share(pool, weight, total_share, share)?;
+
+    if before_fee_deduction > pool * max_out_ratio {
+        return Err(ContractError::MaxOutRatioExceeded { max_out_ratio });
+    }
+
+    let fee_ratio: Decimal = ((Decimal256::one() - weight) * Decimal256::from(swap_fee)).into();
+    let fee_amount: Uint128 = before_fee_deduction * fee_ratio;
+
+    Ok((before_fee_deduction - fee_amount, fee_amount))
+}
+
 /// If `belief_price` and `max_spread` both are given,
 /// we compute new spread else we just use astroport-lbp
 /// spread to check `max_spread`
@@ -712,7 +3222,7 @@ pub fn assert_max_spread(
     offer_amount: Uint128,
     return_amount: Uint128,
     spread_amount: Uint128,
-) -> StdResult<()> {
+) -> Result<(), ContractError> {
     if let (Some(max_spread), Some(belief_price)) = (max_spread, belief_price) {
         let expected_return =
             offer_amount * Decimal::from(Decimal256::one() / Decimal256::from(belief_price));
@@ -723,11 +3233,11 @@ pub fn assert_max_spread(
         if return_amount < expected_return
             && Decimal::from_ratio(spread_amount, expected_return) > max_spread
         {
-            return Err(StdError::generic_err("Operation exceeds max spread limit"));
+            return Err(ContractError::MaxSpreadExceeded {});
         }
     } else if let Some(max_spread) = max_spread {
         if Decimal::from_ratio(spread_amount, return_amount + spread_amount) > max_spread {
-            return Err(StdError::generic_err("Operation exceeds max spread limit"));
+            return Err(ContractError::MaxSpreadExceeded {});
         }
     }
 
@@ -736,71 +3246,241 @@ pub fn assert_max_spread(
 
 fn assert_slippage_tolerance(
     slippage_tolerance: &Option<Decimal>,
-    deposits: &[Uint128; 2],
-    pools: &[WeightedAsset; 2],
-) -> StdResult<()> {
+    deposits: &[Uint128],
+    pools: &[WeightedAsset],
+) -> Result<(), ContractError> {
     if let Some(slippage_tolerance) = *slippage_tolerance {
         let slippage_tolerance: Decimal256 = slippage_tolerance.into();
         if slippage_tolerance > Decimal256::one() {
-            return Err(StdError::generic_err(
-                "slippage_tolerance cannot bigger than 1",
-            ));
+            return Err(ContractError::InvalidSlippageTolerance {});
         }
 
         let one_minus_slippage_tolerance = Decimal256::one() - slippage_tolerance;
-        let deposits: [Uint256; 2] = [deposits[0].into(), deposits[1].into()];
-        let pools: [Uint256; 2] = [pools[0].amount.into(), pools[1].amount.into()];
-
-        // Ensure each prices are not dropped as much as slippage tolerance rate
-        if Decimal256::from_ratio(deposits[0], deposits[1]) * one_minus_slippage_tolerance
-            > Decimal256::from_ratio(pools[0], pools[1])
-            || Decimal256::from_ratio(deposits[1], deposits[0]) * one_minus_slippage_tolerance
-                > Decimal256::from_ratio(pools[1], pools[0])
-        {
-            return Err(StdError::generic_err(
-                "Operation exceeds max splippage tolerance",
-            ));
+        let deposits: Vec<Uint256> = deposits.iter().map(|deposit| (*deposit).into()).collect();
+        let pools: Vec<Uint256> = pools.iter().map(|pool| pool.amount.into()).collect();
+
+        // Ensure each prices are not dropped as much as slippage tolerance rate,
+        // every asset is compared against the first one
+        for i in 1..deposits.len() {
+            if Decimal256::from_ratio(deposits[0], deposits[i]) * one_minus_slippage_tolerance
+                > Decimal256::from_ratio(pools[0], pools[i])
+                || Decimal256::from_ratio(deposits[i], deposits[0]) * one_minus_slippage_tolerance
+                    > Decimal256::from_ratio(pools[i], pools[0])
+            {
+                return Err(ContractError::MaxSlippageExceeded {});
+            }
         }
     }
 
     Ok(())
 }
 
-/// Uses start_time and end_time parameters, start_weight and end_weight for both assets
-/// and current timestamp to calculate the weight for assets
+/// Uses start_time and end_time parameters, start_weight, end_weight and the weight curve
+/// of an asset and current timestamp to calculate the weight for the asset,
+/// a sale closed by its hard cap is finished
 fn get_current_weight(
     start_weight: Uint128,
     end_weight: Uint128,
+    curve: Option<&WeightCurve>,
     start_time: u64,
     end_time: u64,
+    closed_at: Option<u64>,
     block_time: u64,
-) -> StdResult<Decimal256> {
+) -> Result<Decimal256, ContractError> {
     if block_time < start_time {
-        return Err(StdError::generic_err("Sale has not started yet"));
+        return Err(ContractError::SaleNotStarted {});
+    }
+
+    if block_time > end_time || closed_at.is_some() {
+        return Err(ContractError::SaleEnded {});
     }
 
-    if block_time > end_time {
-        return Err(StdError::generic_err("Sale has already finished"));
+    match curve {
+        None | Some(WeightCurve::Linear) => Ok(interpolate_weight(
+            start_weight,
+            end_weight,
+            start_time,
+            end_time,
+            block_time,
+        )),
+        Some(WeightCurve::Exponential { half_life }) => {
+            // Share of the way from start_weight to end_weight, normalized to reach 1 at end_time
+            let elapsed = half_pow(Decimal256::from_ratio(block_time - start_time, *half_life))?;
+            let total = half_pow(Decimal256::from_ratio(end_time - start_time, *half_life))?;
+            let progress = Decimal256::one()
+                .sub(elapsed)
+                .div(Decimal256::one().sub(total));
+
+            let start_weight_fixed = uint2dec(start_weight);
+            if end_weight > start_weight {
+                Ok(start_weight_fixed.add(uint2dec(end_weight - start_weight).mul(progress)))
+            } else {
+                Ok(start_weight_fixed.sub(uint2dec(start_weight - end_weight).mul(progress)))
+            }
+        }
+        Some(WeightCurve::Stepwise { steps }) => {
+            let step = (block_time - start_time) as u128 * *steps as u128
+                / (end_time - start_time) as u128;
+
+            Ok(interpolate_weight(
+                start_weight,
+                end_weight,
+                0,
+                *steps,
+                step as u64,
+            ))
+        }
+        Some(WeightCurve::Piecewise { points }) => {
+            let mut prev = (start_time, start_weight);
+            for point in points
+                .iter()
+                .chain(std::iter::once(&(end_time, end_weight)))
+            {
+                if block_time <= point.0 {
+                    return Ok(interpolate_weight(
+                        prev.1, point.1, prev.0, point.0, block_time,
+                    ));
+                }
+                prev = *point;
+            }
+
+            Ok(uint2dec(end_weight))
+        }
     }
+}
 
+/// Linearly interpolates the weight between two (timestamp, weight) points
+fn interpolate_weight(
+    start_weight: Uint128,
+    end_weight: Uint128,
+    start_time: u64,
+    end_time: u64,
+    block_time: u64,
+) -> Decimal256 {
     let start_weight_fixed = uint2dec(start_weight);
-    let time_diff = uint2dec(Uint128::from(end_time - start_time));
+    // the weight change multiplied by the elapsed time does not fit into u128 for large weights
+    let elapsed = Uint256::from(u128::from(block_time - start_time));
+    let time_diff = Uint256::from(u128::from(end_time - start_time));
 
     if end_weight > start_weight {
-        let ratio = uint2dec(Uint128::from(
-            (end_weight.u128() - start_weight.u128()) * (block_time - start_time) as u128,
-        ))
-        .div(time_diff);
+        let ratio = Decimal256::from_ratio(
+            Uint256::from(end_weight - start_weight) * elapsed,
+            time_diff,
+        );
 
-        Ok(start_weight_fixed.add(ratio))
+        start_weight_fixed.add(ratio)
     } else {
-        let ratio = uint2dec(Uint128::from(
-            (start_weight.u128() - end_weight.u128()) * (block_time - start_time) as u128,
-        ))
-        .div(time_diff);
+        let ratio = Decimal256::from_ratio(
+            Uint256::from(start_weight - end_weight) * elapsed,
+            time_diff,
+        );
+
+        start_weight_fixed.sub(ratio)
+    }
+}
+
+fn assert_max_ratio(max_ratio: Decimal) -> Result<(), ContractError> {
+    if max_ratio.is_zero() || max_ratio >= Decimal::one() {
+        return Err(ContractError::InvalidMaxRatio {});
+    }
+
+    Ok(())
+}
+
+fn assert_swap_fee(swap_fee: Decimal) -> Result<(), ContractError> {
+    if swap_fee >= Decimal::one() {
+        return Err(ContractError::InvalidSwapFee {});
+    }
 
-        Ok(start_weight_fixed.sub(ratio))
+    Ok(())
+}
+
+/// Checks the pair swap direction allows to offer the given asset
+fn assert_swap_direction(
+    pair_info: &PairInfo,
+    offer_asset_info: &AssetInfo,
+) -> Result<(), ContractError> {
+    let allowed_offer_asset_info = match pair_info.swap_direction {
+        SwapDirection::Both => return Ok(()),
+        SwapDirection::Asset0ToAsset1 => &pair_info.asset_infos[0].info,
+        SwapDirection::Asset1ToAsset0 => &pair_info.asset_infos[1].info,
+    };
+
+    if !offer_asset_info.equal(allowed_offer_asset_info) {
+        return Err(ContractError::SwapDirectionNotAllowed {
+            allowed: allowed_offer_asset_info.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Decodes a hex encoded sha256 hash
+fn decode_hash(hash: &str) -> Result<[u8; 32], ContractError> {
+    let mut buf = [0u8; 32];
+    hex::decode_to_slice(hash, &mut buf).map_err(|_| ContractError::InvalidHash {
+        hash: hash.to_string(),
+    })?;
+
+    Ok(buf)
+}
+
+/// Checks the address is a leaf of the merkle tree, the leaf is the sha256 hash of the address
+/// and each parent is the sha256 hash of its sorted children
+fn verify_merkle_proof(
+    merkle_root: &str,
+    address: &str,
+    merkle_proof: &[String],
+) -> Result<bool, ContractError> {
+    let mut hash: [u8; 32] = sha2::Sha256::digest(address.as_bytes()).into();
+
+    for proof in merkle_proof {
+        let proof = decode_hash(proof)?;
+        let mut hashes = [hash, proof];
+        if hash.cmp(&proof) == Ordering::Greater {
+            hashes.swap(0, 1);
+        }
+        hash = sha2::Sha256::digest(&hashes.concat()).into();
+    }
+
+    Ok(hash == decode_hash(merkle_root)?)
+}
+
+/// Checks the weight curve parameters against the sale period
+fn assert_weight_curve(
+    curve: &WeightCurve,
+    start_time: u64,
+    end_time: u64,
+) -> Result<(), ContractError> {
+    match curve {
+        WeightCurve::Linear => {}
+        WeightCurve::Exponential { half_life } => {
+            if *half_life == 0 || *half_life > end_time - start_time {
+                return Err(ContractError::InvalidHalfLife {});
+            }
+        }
+        WeightCurve::Stepwise { steps } => {
+            if *steps == 0 || *steps > end_time - start_time {
+                return Err(ContractError::InvalidSteps {});
+            }
+        }
+        WeightCurve::Piecewise { points } => {
+            let mut prev_time = start_time;
+            for (time, weight) in points.iter() {
+                if *time <= prev_time || *time >= end_time {
+                    return Err(ContractError::InvalidCurvePoints {});
+                }
+
+                if weight.is_zero() {
+                    return Err(ContractError::ZeroWeight {});
+                }
+
+                prev_time = *time;
+            }
+        }
     }
+
+    Ok(())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
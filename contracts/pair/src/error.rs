@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Decimal, OverflowError, StdError, Uint128};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -11,4 +11,212 @@ pub enum ContractError {
 
     #[error("Event of zero transfer")]
     ZeroAmount {},
+
+    #[error("Pair is paused")]
+    Paused {},
+
+    #[error("Sender is not in the allowlist")]
+    NotAllowed {},
+
+    #[error("Purchase cap exceeded, remaining allowance: {remaining}")]
+    PurchaseCapExceeded { remaining: Uint128 },
+
+    #[error("Offer amount exceeds {max_in_ratio} of the offer pool")]
+    MaxInRatioExceeded { max_in_ratio: Decimal },
+
+    #[error("Return amount exceeds {max_out_ratio} of the ask pool")]
+    MaxOutRatioExceeded { max_out_ratio: Decimal },
+
+    #[error("start_time is less then current time")]
+    InvalidStartTime {},
+
+    #[error("end_time is less then or same as start_time")]
+    InvalidEndTime {},
+
+    #[error("A pair must have from {min} to {max} assets")]
+    InvalidAssetCount { min: usize, max: usize },
+
+    #[error("Assets of a pair must be unique")]
+    DuplicateAssets {},
+
+    #[error("The operation is only available in two asset pairs")]
+    TwoAssetPairsOnly {},
+
+    #[error("Asset does not belong to the pair")]
+    AssetMismatch {},
+
+    #[error("Native token balance mismatch between the argument and the transferred")]
+    NativeBalanceMismatch {},
+
+    #[error("The other asset of the swap must be given in pairs of more than two assets")]
+    OtherAssetRequired {},
+
+    #[error("Weights can not be 0")]
+    ZeroWeight {},
+
+    #[error("end_weights must be given for every asset of the pair")]
+    WeightCountMismatch {},
+
+    #[error("Weights are too large")]
+    WeightsTooLarge {},
+
+    #[error("Amounts are too large for the weighted math")]
+    AmountTooLarge {},
+
+    #[error("half_life must be between 1 and the sale duration")]
+    InvalidHalfLife {},
+
+    #[error("steps must be between 1 and the sale duration in seconds")]
+    InvalidSteps {},
+
+    #[error("Weight curve points must be in ascending order within the sale period")]
+    InvalidCurvePoints {},
+
+    #[error("Weight schedule update would move the price discontinuously")]
+    PriceDeviationExceeded {},
+
+    #[error("swap_fee must be less than 1")]
+    InvalidSwapFee {},
+
+    #[error("swap_fee must be between {min_swap_fee} and {max_swap_fee}")]
+    SwapFeeOutOfBounds {
+        min_swap_fee: Decimal,
+        max_swap_fee: Decimal,
+    },
+
+    #[error("max_in_ratio and max_out_ratio must be greater than 0 and less than 1")]
+    InvalidMaxRatio {},
+
+    #[error("Sale has not started yet")]
+    SaleNotStarted {},
+
+    #[error("Sale has already finished")]
+    SaleEnded {},
+
+    #[error("The sale has already started")]
+    SaleStarted {},
+
+    #[error("The operation is not available while the sale is running")]
+    SaleRunning {},
+
+    #[error("The sale has not ended yet")]
+    SaleNotEnded {},
+
+    #[error("The sale has been cancelled")]
+    SaleCancelled {},
+
+    #[error("The sale has already graduated")]
+    SaleGraduated {},
+
+    #[error("Pool has insufficient liquidity")]
+    InsufficientLiquidity {},
+
+    #[error("Liquidity of other providers must be withdrawn before the sale is cancelled")]
+    OtherProvidersLiquidity {},
+
+    #[error("Liquidity can not be withdrawn until refunds are settled")]
+    RefundsNotSettled {},
+
+    #[error("Swap direction is not allowed, only {allowed} can be offered")]
+    SwapDirectionNotAllowed { allowed: String },
+
+    #[error("Operation exceeds max spread limit")]
+    MaxSpreadExceeded {},
+
+    #[error("slippage_tolerance cannot bigger than 1")]
+    InvalidSlippageTolerance {},
+
+    #[error("Operation exceeds max slippage tolerance")]
+    MaxSlippageExceeded {},
+
+    #[error("Share {share} is less than min_share")]
+    MinShareNotReached { share: Uint128 },
+
+    #[error("Return amount {return_amount} is less than min_return")]
+    MinReturnNotReached { return_amount: Uint128 },
+
+    #[error("Required offer amount {required_amount} exceeds max_offer_amount")]
+    MaxOfferAmountExceeded { required_amount: Uint128 },
+
+    #[error("Offer amount exceeds the remaining hard cap {remaining}")]
+    HardCapExceeded { remaining: Uint128 },
+
+    #[error("Hard cap can not be zero")]
+    ZeroHardCap {},
+
+    #[error("Soft cap can not be greater than the hard cap")]
+    SoftCapAboveHardCap {},
+
+    #[error("Purchases of a sale below its soft cap can only be refunded")]
+    SoftCapNotReached {},
+
+    #[error("Refunds are only available after a sale which did not reach its soft cap")]
+    RefundsUnavailable {},
+
+    #[error("Nothing to refund")]
+    NothingToRefund {},
+
+    #[error("Returned amount exceeds the received amount")]
+    RefundExceedsPurchase {},
+
+    #[error("Vesting cliff can not be greater than its duration")]
+    InvalidVesting {},
+
+    #[error("Purchases of the pair do not vest")]
+    NoVesting {},
+
+    #[error("Nothing to claim")]
+    NothingToClaim {},
+
+    #[error("Constant product pair code id is not configured in the factory")]
+    XykPairCodeIdNotSet {},
+
+    #[error("New owner cannot be same")]
+    SameOwner {},
+
+    #[error("Parameter expires_in cannot be higher than {max}")]
+    ExpirationTooLong { max: u64 },
+
+    #[error("Ownership proposal not found")]
+    OwnershipProposalNotFound {},
+
+    #[error("Ownership proposal expired")]
+    OwnershipProposalExpired {},
+
+    #[error("Invalid sha256 hash: {hash}")]
+    InvalidHash { hash: String },
+
+    #[error("start must be less than end and end can not be in the future")]
+    InvalidTwapPeriod {},
+
+    #[error("Prices are not recorded before the given time")]
+    PriceNotRecorded {},
+
+    #[error("Price accumulator increment exceeds Uint128")]
+    PriceAccumulatorOverflow {},
+
+    #[error("points must be between 2 and {max}")]
+    InvalidPricePoints { max: u32 },
+
+    #[error("Unknown reply id: {id}")]
+    UnknownReplyId { id: u64 },
+
+    #[error("Failed to parse the reply")]
+    InvalidReply {},
+}
+
+impl From<OverflowError> for ContractError {
+    fn from(o: OverflowError) -> Self {
+        StdError::from(o).into()
+    }
+}
+
+/// Queries return the typed errors with their messages
+impl From<ContractError> for StdError {
+    fn from(err: ContractError) -> Self {
+        match err {
+            ContractError::Std(err) => err,
+            err => StdError::generic_err(err.to_string()),
+        }
+    }
 }
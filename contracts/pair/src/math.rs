@@ -1,19 +1,23 @@
+use crate::error::ContractError;
+use astroport_lbp::U1024;
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{OverflowError, OverflowOperation, Uint128};
 use fixed::transcendental::pow;
 use fixed::types::I64F64 as FixedFloat;
 use std::ops::{Add, Div, Mul, Sub};
 use std::str::FromStr;
 
+const DECIMAL_FRACTIONAL: u128 = 1_000_000_000_000_000_000;
+
 pub fn calc_out_given_in(
     balance_in: Uint128,
     weight_in: Decimal256,
     balance_out: Uint128,
     weight_out: Decimal256,
     amount_in: Uint128,
-) -> Uint128 {
+) -> Result<Uint128, ContractError> {
     if amount_in.is_zero() {
-        return Uint128::zero();
+        return Ok(Uint128::zero());
     }
 
     let adjusted_in = balance_in.add(amount_in);
@@ -21,13 +25,9 @@ pub fn calc_out_given_in(
 
     let weight_ratio = weight_in.div(weight_out);
 
-    let multiplier = FixedFloat::from_num(1).sub(fixed_pow(y, weight_ratio));
-
-    let amount_out: u128 = FixedFloat::from_num(balance_out.u128())
-        .mul(&multiplier)
-        .to_num();
+    let multiplier = FixedFloat::from_num(1).sub(fixed_pow(y, weight_ratio)?);
 
-    Uint128::from(amount_out)
+    fixed_mul(balance_out, multiplier)
 }
 
 pub fn calc_in_given_out(
@@ -36,19 +36,87 @@ pub fn calc_in_given_out(
     balance_out: Uint128,
     weight_out: Decimal256,
     amount_out: Uint128,
-) -> Uint128 {
-    let updated_balance = balance_out.checked_sub(amount_out).unwrap();
+) -> Result<Uint128, ContractError> {
+    if amount_out >= balance_out {
+        return Err(ContractError::InsufficientLiquidity {});
+    }
+
+    let updated_balance = balance_out - amount_out;
     let weight_ratio = weight_out.div(weight_in);
 
     let y = decimal_from_ratio(balance_out, updated_balance);
 
-    let multiplier = fixed_pow(y, weight_ratio).sub(FixedFloat::from_num(1));
+    let multiplier = fixed_pow(y, weight_ratio)?.sub(FixedFloat::from_num(1));
+
+    fixed_mul(balance_in, multiplier)
+}
+
+/// Calculates the share issued for a single asset deposit,
+/// `total_share * ((1 + amount_in / balance_in) ^ weight - 1)`
+/// where `weight` is the normalized weight of the deposited asset
+pub fn calc_share_given_single_in(
+    balance_in: Uint128,
+    weight: Decimal256,
+    total_share: Uint128,
+    amount_in: Uint128,
+) -> Result<Uint128, ContractError> {
+    if amount_in.is_zero() {
+        return Ok(Uint128::zero());
+    }
+
+    let y = decimal_from_ratio(balance_in.add(amount_in), balance_in);
+
+    let multiplier = fixed_pow(y, weight)?.sub(FixedFloat::from_num(1));
+
+    fixed_mul(total_share, multiplier)
+}
+
+/// Calculates the amount of an asset returned for a single asset withdrawal,
+/// `balance_out * (1 - (1 - share_in / total_share) ^ (1 / weight))`
+/// where `weight` is the normalized weight of the withdrawn asset
+pub fn calc_single_out_given_share(
+    balance_out: Uint128,
+    weight: Decimal256,
+    total_share: Uint128,
+    share_in: Uint128,
+) -> Result<Uint128, ContractError> {
+    if share_in >= total_share {
+        return Ok(balance_out);
+    }
+
+    let y = decimal_from_ratio(total_share - share_in, total_share);
 
-    let amount_in: u128 = FixedFloat::from_num(balance_in.u128())
-        .mul(&multiplier)
+    let multiplier = FixedFloat::from_num(1).sub(fixed_pow(y, Decimal256::one().div(weight))?);
+
+    fixed_mul(balance_out, multiplier)
+}
+
+/// Calculates the spot price of the offer asset in the ask asset,
+/// `(ask_pool / ask_weight) / (offer_pool / offer_weight)`
+pub fn calc_spot_price(
+    offer_pool: Uint128,
+    offer_weight: Decimal256,
+    ask_pool: Uint128,
+    ask_weight: Decimal256,
+) -> Decimal256 {
+    let offer_ratio = Decimal256::from_uint256(Uint256::from(offer_pool)).div(offer_weight);
+    let ask_ratio = Decimal256::from_uint256(Uint256::from(ask_pool)).div(ask_weight);
+
+    ask_ratio.div(offer_ratio)
+}
+
+/// Calculates `0.5 ^ i`
+pub fn half_pow(i: Decimal256) -> Result<Decimal256, ContractError> {
+    // 2^-60 is already below the Decimal256 precision
+    if i > Decimal256::from_uint256(60u64) {
+        return Ok(Decimal256::zero());
+    }
+
+    let p: u128 = fixed_pow(Decimal256::percent(50), i)?
+        .mul(FixedFloat::from_num(DECIMAL_FRACTIONAL))
         .to_num();
 
-    Uint128::from(amount_in)
+    Ok(Decimal256::from_ratio(p, DECIMAL_FRACTIONAL))
 }
 
 fn decimal_from_ratio(nom: Uint128, denom: Uint128) -> Decimal256 {
@@ -59,16 +127,48 @@ fn decimal_from_ratio(nom: Uint128, denom: Uint128) -> Decimal256 {
     Decimal256::from_ratio(nom, denom)
 }
 
-fn fixed_pow(n: Decimal256, i: Decimal256) -> FixedFloat {
+/// Multiplies the amount by the multiplier in fixed point, amounts which do not fit into
+/// the fixed point type (above i64::MAX) are rejected
+fn fixed_mul(amount: Uint128, multiplier: FixedFloat) -> Result<Uint128, ContractError> {
+    FixedFloat::checked_from_num(amount.u128())
+        .and_then(|amount| amount.checked_mul(multiplier))
+        .and_then(|result| result.checked_to_num::<u128>())
+        .map(Uint128::from)
+        .ok_or(ContractError::AmountTooLarge {})
+}
+
+fn fixed_pow(n: Decimal256, i: Decimal256) -> Result<FixedFloat, ContractError> {
+    let overflow = || OverflowError::new(OverflowOperation::Pow, n, i);
+
     // Truncate Decimal256
-    let n: FixedFloat = FixedFloat::from_str(&n.to_string()).unwrap();
-    let i: FixedFloat = FixedFloat::from_str(&i.to_string()).unwrap();
-    let p: FixedFloat = pow(n, i).unwrap();
+    let n: FixedFloat = FixedFloat::from_str(&n.to_string()).map_err(|_| overflow())?;
+    let i: FixedFloat = FixedFloat::from_str(&i.to_string()).map_err(|_| overflow())?;
+    let p: FixedFloat = pow(n, i).map_err(|_| overflow())?;
 
-    p
+    Ok(p)
 }
 
 pub fn uint2dec(i: Uint128) -> Decimal256 {
     let i: Uint256 = i.into();
     Decimal256::from_uint256(i)
 }
+
+/// Calculates the geometric mean of the amounts rounded down,
+/// which is `sqrt(amount_0 * amount_1)` for two amounts
+pub fn geometric_mean(amounts: &[Uint128]) -> Uint128 {
+    let n = U1024::from(amounts.len());
+    let product = amounts.iter().fold(U1024::one(), |product, amount| {
+        product * U1024::from(amount.u128())
+    });
+
+    // the mean is not greater than the biggest amount, so it is found bit by bit
+    let mut mean: u128 = 0;
+    for bit in (0..128).rev() {
+        let candidate = mean | (1u128 << bit);
+        if U1024::from(candidate).pow(n) <= product {
+            mean = candidate;
+        }
+    }
+
+    Uint128::from(mean)
+}
@@ -1,10 +1,14 @@
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_binary, from_slice, to_binary, Coin, Decimal, OwnedDeps, Querier, QuerierResult,
+    from_binary, from_slice, to_binary, Addr, Coin, Decimal, OwnedDeps, Querier, QuerierResult,
     QueryRequest, SystemError, SystemResult, Uint128, WasmQuery,
 };
 use std::collections::HashMap;
 
+use astroport_lbp::factory::{
+    ConfigResponse as FactoryConfigResponse, QueryMsg as FactoryQueryMsg,
+};
+
 use cw20::{BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
 use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
 
@@ -27,6 +31,16 @@ pub struct WasmMockQuerier {
     base: MockQuerier<TerraQueryWrapper>,
     token_querier: TokenQuerier,
     tax_querier: TaxQuerier,
+    factory_querier: FactoryQuerier,
+}
+
+#[derive(Clone, Default)]
+pub struct FactoryQuerier {
+    contract_addr: String,
+    owner: String,
+    fee_address: Option<Addr>,
+    protocol_fee_share: Decimal,
+    xyk_pair_code_id: Option<u64>,
 }
 
 #[derive(Clone, Default)]
@@ -127,6 +141,28 @@ impl WasmMockQuerier {
                 }
             }
             QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
+                if let Ok(FactoryQueryMsg::Config {}) = from_binary(msg) {
+                    if contract_addr != &self.factory_querier.contract_addr {
+                        return SystemResult::Err(SystemError::NoSuchContract {
+                            addr: contract_addr.clone(),
+                        });
+                    }
+
+                    return SystemResult::Ok(
+                        to_binary(&FactoryConfigResponse {
+                            owner: Addr::unchecked(&self.factory_querier.owner),
+                            pair_code_id: 321u64,
+                            token_code_id: 123u64,
+                            min_swap_fee: Decimal::zero(),
+                            max_swap_fee: Decimal::percent(1),
+                            fee_address: self.factory_querier.fee_address.clone(),
+                            protocol_fee_share: self.factory_querier.protocol_fee_share,
+                            xyk_pair_code_id: self.factory_querier.xyk_pair_code_id,
+                        })
+                        .into(),
+                    );
+                }
+
                 match from_binary(&msg).unwrap() {
                     Cw20QueryMsg::TokenInfo {} => {
                         let balances: &HashMap<String, Uint128> =
@@ -186,9 +222,32 @@ impl WasmMockQuerier {
             base,
             token_querier: TokenQuerier::default(),
             tax_querier: TaxQuerier::default(),
+            factory_querier: FactoryQuerier::default(),
         }
     }
 
+    // configure the factory config mock querier
+    pub fn with_factory(&mut self, contract_addr: &str, owner: &str) {
+        self.factory_querier = FactoryQuerier {
+            contract_addr: contract_addr.to_string(),
+            owner: owner.to_string(),
+            fee_address: None,
+            protocol_fee_share: Decimal::zero(),
+            xyk_pair_code_id: None,
+        };
+    }
+
+    // configure the factory protocol fee mock querier
+    pub fn with_protocol_fee(&mut self, fee_address: &str, protocol_fee_share: Decimal) {
+        self.factory_querier.fee_address = Some(Addr::unchecked(fee_address));
+        self.factory_querier.protocol_fee_share = protocol_fee_share;
+    }
+
+    // configure the factory graduation mock querier
+    pub fn with_xyk_pair_code_id(&mut self, xyk_pair_code_id: u64) {
+        self.factory_querier.xyk_pair_code_id = Some(xyk_pair_code_id);
+    }
+
     // configure the mint whitelist mock querier
     pub fn with_token_balances(&mut self, balances: &[(&String, &[(&String, &Uint128)])]) {
         self.token_querier = TokenQuerier::new(balances);
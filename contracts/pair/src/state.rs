@@ -1,4 +1,76 @@
-use astroport_lbp::asset::PairInfo;
-use cw_storage_plus::Item;
+use astroport_lbp::asset::{Asset, PairInfo};
+use astroport_lbp::pair::{AssetStats, PurchaseCap, VestingConfig, WeightSchedule};
+use cosmwasm_std::{Addr, Uint128};
+use cw_storage_plus::{Item, Map, U64Key};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
 pub const PAIR_INFO: Item<PairInfo> = Item::new("pair_info");
+
+pub const FACTORY_ADDR: Item<Addr> = Item::new("factory_addr");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnershipProposal {
+    pub owner: Addr,
+    pub ttl: u64,
+}
+
+pub const OWNERSHIP_PROPOSAL: Item<OwnershipProposal> = Item::new("ownership_proposal");
+
+pub const WEIGHT_SCHEDULES: Item<Vec<WeightSchedule>> = Item::new("weight_schedules");
+
+pub const STATS: Item<Vec<AssetStats>> = Item::new("stats");
+
+pub const MERKLE_ROOT: Item<String> = Item::new("merkle_root");
+
+pub const PURCHASE_CAP: Item<PurchaseCap> = Item::new("purchase_cap");
+
+pub const PURCHASES: Map<&Addr, Uint128> = Map::new("purchases");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceCumulative {
+    pub block_time: u64,
+    pub price0_cumulative: Uint128,
+    pub price1_cumulative: Uint128,
+}
+
+/// Price accumulators keyed by the block time they were updated at
+pub const PRICE_CUMULATIVES: Map<U64Key, PriceCumulative> = Map::new("price_cumulatives");
+
+/// Constant product pair the sale graduated to
+pub const GRADUATED_PAIR: Item<Addr> = Item::new("graduated_pair");
+
+/// Assets provided to the constant product pair once it is instantiated
+pub const TMP_GRADUATION_ASSETS: Item<[Asset; 2]> = Item::new("tmp_graduation_assets");
+
+pub const VESTING: Item<VestingConfig> = Item::new("vesting");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct VestedPurchase {
+    pub purchased: Uint128,
+    pub claimed: Uint128,
+}
+
+pub const VESTED_PURCHASES: Map<&Addr, VestedPurchase> = Map::new("vested_purchases");
+
+/// Purchased amount not claimed yet, it is held in the pair but excluded from the pool
+pub const VESTING_RESERVE: Item<Uint128> = Item::new("vesting_reserve");
+
+pub const SOFT_CAP: Item<Asset> = Item::new("soft_cap");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Contribution {
+    /// Amount of the soft cap asset paid
+    pub paid: Uint128,
+    /// Amount of the other asset received
+    pub received: Uint128,
+}
+
+pub const CONTRIBUTIONS: Map<&Addr, Contribution> = Map::new("contributions");
+
+/// Sum of the paid amounts which can be refunded, it decreases with sales back to the pair and
+/// refunds
+pub const REFUNDABLE: Item<Uint128> = Item::new("refundable");
+
+/// Inflow of its asset which closes the sale once reached
+pub const HARD_CAP: Item<Asset> = Item::new("hard_cap");
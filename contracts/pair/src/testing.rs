@@ -1,25 +1,40 @@
 use crate::contract::{
-    assert_max_spread, compute_swap, execute, instantiate, query_pair_info, query_pool,
-    query_reverse_simulation, query_simulation, reply, COMMISSION_RATE,
+    assert_max_spread, compute_swap, execute, instantiate, query_allowance, query_contribution,
+    query_cumulative_prices, query_eligibility, query_graduation, query_hard_cap, query_pair_info,
+    query_pool, query_price_curve, query_provide_single_simulation, query_reverse_simulation,
+    query_simulation, query_soft_cap, query_spot_price, query_stats, query_twap,
+    query_vesting_info, query_weight_schedules, query_withdraw_single_simulation, reply,
+    vested_amount,
 };
 use crate::mock_querier::mock_dependencies;
 use proptest::prelude::*;
 
 use crate::error::ContractError;
 use crate::math::uint2dec;
-use astroport_lbp::asset::{Asset, AssetInfo, PairInfo, WeightedAsset, WeightedAssetInfo};
+use crate::state::{PAIR_INFO, PRICE_CUMULATIVES};
+use astroport_lbp::asset::{
+    Asset, AssetInfo, LiquidityPolicy, PairInfo, SwapDirection, WeightCurve, WeightedAsset,
+    WeightedAssetInfo,
+};
+use astroport_lbp::factory::ExecuteMsg as FactoryExecuteMsg;
 use astroport_lbp::pair::{
-    Cw20HookMsg, ExecuteMsg, InstantiateMsg, PoolResponse, ReverseSimulationResponse,
-    SimulationResponse,
+    AllowanceResponse, AssetStats, ContributionResponse, Cw20HookMsg, EligibilityResponse,
+    ExecuteMsg, GraduationResponse, HardCapResponse, InstantiateMsg, PoolResponse,
+    PriceCurveResponse, PricePoint, ProvideSingleSimulationResponse, PurchaseCap,
+    ReverseSimulationResponse, SimulationResponse, SoftCapResponse, SpotPriceResponse,
+    TwapResponse, VestingConfig, VestingInfoResponse, WithdrawSingleSimulationResponse,
+    XykPairExecuteMsg, XykPairInstantiateMsg,
 };
 use astroport_lbp::token::InstantiateMsg as TokenInstantiateMsg;
 use cosmwasm_bignumber::Decimal256;
 use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, to_binary, Addr, BankMsg, BlockInfo, Coin, ContractResult, Decimal, DepsMut, Env, Reply,
-    ReplyOn, Response, StdError, SubMsg, SubMsgExecutionResponse, Timestamp, Uint128, WasmMsg,
+    attr, to_binary, Addr, BankMsg, BlockInfo, Coin, ContractResult, CosmosMsg, Decimal, DepsMut,
+    Env, Event, Order, Reply, ReplyOn, Response, StdError, StdResult, SubMsg,
+    SubMsgExecutionResponse, Timestamp, Uint128, WasmMsg,
 };
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
+use sha2::{Digest, Sha256};
 use std::str::FromStr;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -67,13 +82,14 @@ fn proper_initialization() {
     let end_time = start_time + 1000;
 
     let msg = InstantiateMsg {
-        asset_infos: [
+        asset_infos: vec![
             WeightedAssetInfo {
                 info: AssetInfo::NativeToken {
                     denom: "uusd".to_string(),
                 },
                 start_weight: Uint128::from(1u128),
                 end_weight: Uint128::from(1u128),
+                curve: None,
             },
             WeightedAssetInfo {
                 info: AssetInfo::Token {
@@ -81,12 +97,19 @@ fn proper_initialization() {
                 },
                 start_weight: Uint128::from(1u128),
                 end_weight: Uint128::from(1u128),
+                curve: None,
             },
         ],
         token_code_id: 10u64,
         start_time,
         end_time,
         description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
     };
 
     // we can just call .unwrap() to assert this was a success
@@ -135,6 +158,7 @@ fn proper_initialization() {
                 },
                 start_weight: Uint128::from(1u128),
                 end_weight: Uint128::from(1u128),
+                curve: None,
             },
             WeightedAssetInfo {
                 info: AssetInfo::Token {
@@ -142,10 +166,12 @@ fn proper_initialization() {
                 },
                 start_weight: Uint128::from(1u128),
                 end_weight: Uint128::from(1u128),
+                curve: None,
             },
         ]
     );
     assert_eq!("description", pair_info.description.unwrap());
+    assert_eq!(Addr::unchecked("addr0000"), pair_info.owner);
 }
 
 #[test]
@@ -167,13 +193,14 @@ fn provide_liquidity() {
     )]);
 
     let msg = InstantiateMsg {
-        asset_infos: [
+        asset_infos: vec![
             WeightedAssetInfo {
                 info: AssetInfo::NativeToken {
                     denom: "uusd".to_string(),
                 },
                 start_weight: Uint128::from(1u128),
                 end_weight: Uint128::from(1u128),
+                curve: None,
             },
             WeightedAssetInfo {
                 info: AssetInfo::Token {
@@ -181,12 +208,19 @@ fn provide_liquidity() {
                 },
                 start_weight: Uint128::from(1u128),
                 end_weight: Uint128::from(1u128),
+                curve: None,
             },
         ],
         token_code_id: 10u64,
         start_time,
         end_time,
         description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
     };
 
     let env = mock_env();
@@ -199,7 +233,7 @@ fn provide_liquidity() {
 
     // successfully provide liquidity for the exist pool
     let msg = ExecuteMsg::ProvideLiquidity {
-        assets: [
+        assets: vec![
             Asset {
                 info: AssetInfo::Token {
                     contract_addr: Addr::unchecked("asset0000"),
@@ -263,6 +297,20 @@ fn provide_liquidity() {
             reply_on: ReplyOn::Never
         }
     );
+    assert_eq!(
+        res.events,
+        vec![Event::new("lbp_provide").add_attributes(vec![
+            attr("sender", "addr0000"),
+            attr("share", "100000000000000000000"),
+            attr("fee_amount", "0"),
+            attr("asset_0", "uusd"),
+            attr("amount_0", "100000000000000000000"),
+            attr("asset_1", "asset0000"),
+            attr("amount_1", "100000000000000000000"),
+            attr("weight_0", "0.5"),
+            attr("weight_1", "0.5"),
+        ])]
+    );
 
     // provide more liquidity 1:2, which is not propotional to 1:1,
     // then it must accept 1:1 and treat left amount as donation
@@ -294,7 +342,7 @@ fn provide_liquidity() {
     ]);
 
     let msg = ExecuteMsg::ProvideLiquidity {
-        assets: [
+        assets: vec![
             Asset {
                 info: AssetInfo::Token {
                     contract_addr: Addr::unchecked("asset0000"),
@@ -364,7 +412,7 @@ fn provide_liquidity() {
 
     // check wrong argument
     let msg = ExecuteMsg::ProvideLiquidity {
-        assets: [
+        assets: vec![
             Asset {
                 info: AssetInfo::Token {
                     contract_addr: Addr::unchecked("asset0000"),
@@ -389,13 +437,7 @@ fn provide_liquidity() {
         }],
     );
     let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
-    match res {
-        ContractError::Std(StdError::GenericErr { msg, .. }) => assert_eq!(
-            msg,
-            "Native token balance missmatch between the argument and the transferred".to_string()
-        ),
-        _ => panic!("Must return generic error"),
-    }
+    assert_eq!(res, ContractError::NativeBalanceMismatch {});
 
     // initialize token balance to 1:1
     deps.querier.with_balance(&[(
@@ -427,7 +469,7 @@ fn provide_liquidity() {
 
     // failed because the price is under slippage_tolerance
     let msg = ExecuteMsg::ProvideLiquidity {
-        assets: [
+        assets: vec![
             Asset {
                 info: AssetInfo::Token {
                     contract_addr: Addr::unchecked("asset0000"),
@@ -446,19 +488,14 @@ fn provide_liquidity() {
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
     let info = mock_info(
-        "addr0001",
+        "addr0000",
         &[Coin {
             denom: "uusd".to_string(),
             amount: Uint128::from(100_000000000000000000u128),
         }],
     );
     let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
-    match res {
-        ContractError::Std(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Operation exceeds max splippage tolerance")
-        }
-        _ => panic!("DO NOT ENTER HERE"),
-    }
+    assert_eq!(res, ContractError::MaxSlippageExceeded {});
 
     // initialize token balance to 1:1
     deps.querier.with_balance(&[(
@@ -471,7 +508,7 @@ fn provide_liquidity() {
 
     // failed because the price is under slippage_tolerance
     let msg = ExecuteMsg::ProvideLiquidity {
-        assets: [
+        assets: vec![
             Asset {
                 info: AssetInfo::Token {
                     contract_addr: Addr::unchecked("asset0000"),
@@ -490,19 +527,14 @@ fn provide_liquidity() {
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
     let info = mock_info(
-        "addr0001",
+        "addr0000",
         &[Coin {
             denom: "uusd".to_string(),
             amount: Uint128::from(98_000000000000000000u128),
         }],
     );
     let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
-    match res {
-        ContractError::Std(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Operation exceeds max splippage tolerance")
-        }
-        _ => panic!("DO NOT ENTER HERE"),
-    }
+    assert_eq!(res, ContractError::MaxSlippageExceeded {});
 
     // initialize token balance to 1:1
     deps.querier.with_balance(&[(
@@ -517,7 +549,7 @@ fn provide_liquidity() {
 
     // successfully provides
     let msg = ExecuteMsg::ProvideLiquidity {
-        assets: [
+        assets: vec![
             Asset {
                 info: AssetInfo::Token {
                     contract_addr: Addr::unchecked("asset0000"),
@@ -536,7 +568,7 @@ fn provide_liquidity() {
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
     let info = mock_info(
-        "addr0001",
+        "addr0000",
         &[Coin {
             denom: "uusd".to_string(),
             amount: Uint128::from(100_000000000000000000u128),
@@ -555,7 +587,7 @@ fn provide_liquidity() {
 
     // successfully provides
     let msg = ExecuteMsg::ProvideLiquidity {
-        assets: [
+        assets: vec![
             Asset {
                 info: AssetInfo::Token {
                     contract_addr: Addr::unchecked("asset0000"),
@@ -573,6 +605,16 @@ fn provide_liquidity() {
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(99_000000000000000000u128),
+        }],
+    );
+    let _res = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap();
+
+    // only the sale owner can provide liquidity
     let info = mock_info(
         "addr0001",
         &[Coin {
@@ -580,7 +622,36 @@ fn provide_liquidity() {
             amount: Uint128::from(99_000000000000000000u128),
         }],
     );
-    let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    // assets of other pairs are rejected
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: Uint128::from(100_000000000000000000u128),
+            },
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uluna".to_string(),
+                },
+                amount: Uint128::from(99_000000000000000000u128),
+            },
+        ],
+        slippage_tolerance: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uluna".to_string(),
+            amount: Uint128::from(99_000000000000000000u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::AssetMismatch {});
 }
 
 #[test]
@@ -612,13 +683,14 @@ fn withdraw_liquidity() {
     ]);
 
     let msg = InstantiateMsg {
-        asset_infos: [
+        asset_infos: vec![
             WeightedAssetInfo {
                 info: AssetInfo::NativeToken {
                     denom: "uusd".to_string(),
                 },
                 start_weight: Uint128::from(1u128),
                 end_weight: Uint128::from(1u128),
+                curve: None,
             },
             WeightedAssetInfo {
                 info: AssetInfo::Token {
@@ -626,12 +698,19 @@ fn withdraw_liquidity() {
                 },
                 start_weight: Uint128::from(1u128),
                 end_weight: Uint128::from(1u128),
+                curve: None,
             },
         ],
         token_code_id: 10u64,
         start_time,
         end_time,
         description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
     };
 
     let env = mock_env();
@@ -656,6 +735,20 @@ fn withdraw_liquidity() {
     let msg_refund_0 = res.messages.get(0).expect("no message");
     let msg_refund_1 = res.messages.get(1).expect("no message");
     let msg_burn_liquidity = res.messages.get(2).expect("no message");
+    assert_eq!(
+        res.events,
+        vec![Event::new("lbp_withdraw").add_attributes(vec![
+            attr("sender", "addr0000"),
+            attr("share", "100"),
+            attr("fee_amount", "0"),
+            attr("asset_0", "uusd"),
+            attr("amount_0", "100"),
+            attr("asset_1", "asset0000"),
+            attr("amount_1", "100"),
+            attr("weight_0", "0.5"),
+            attr("weight_1", "0.5"),
+        ])]
+    );
     assert_eq!(
         msg_refund_0,
         &SubMsg {
@@ -719,48 +812,42 @@ fn withdraw_liquidity() {
 }
 
 #[test]
-fn try_native_to_token() {
+fn liquidity_policy() {
     let start_time = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
     let end_time = start_time + 1000;
-    let total_share = Uint128::from(30000000000u128);
-    let asset_pool_amount = Uint128::from(20000000000u128);
-    let collateral_pool_amount = Uint128::from(30000000000u128);
-    let price = Decimal::from_ratio(collateral_pool_amount, asset_pool_amount);
-    let exchange_rate = Decimal::from(Decimal256::one() / Decimal256::from(price));
-    let offer_amount = Uint128::from(1500000000u128);
 
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
-        amount: collateral_pool_amount + offer_amount, /* user deposit must be pre-applied */
+        amount: Uint128::from(200u128),
     }]);
 
     deps.querier.with_tax(
         Decimal::zero(),
         &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
     );
-
     deps.querier.with_token_balances(&[
         (
             &"liquidity0000".to_string(),
-            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
+            &[(&"addr0000".to_string(), &Uint128::from(100u128))],
         ),
         (
             &"asset0000".to_string(),
-            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(100u128))],
         ),
     ]);
 
-    let msg = InstantiateMsg {
-        asset_infos: [
+    let mut msg = InstantiateMsg {
+        asset_infos: vec![
             WeightedAssetInfo {
                 info: AssetInfo::NativeToken {
                     denom: "uusd".to_string(),
                 },
                 start_weight: Uint128::from(1u128),
                 end_weight: Uint128::from(1u128),
+                curve: None,
             },
             WeightedAssetInfo {
                 info: AssetInfo::Token {
@@ -768,205 +855,136 @@ fn try_native_to_token() {
                 },
                 start_weight: Uint128::from(1u128),
                 end_weight: Uint128::from(1u128),
+                curve: None,
             },
         ],
         token_code_id: 10u64,
         start_time,
         end_time,
         description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
     };
 
     let env = mock_env();
     let info = mock_info("addr0000", &[]);
-    // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let _res = instantiate(deps.as_mut(), env, info.clone(), msg.clone()).unwrap();
 
     // store liquidity token
     store_liquidity_token(deps.as_mut());
 
-    // normal swap
-    let msg = ExecuteMsg::Swap {
-        offer_asset: Asset {
-            info: AssetInfo::NativeToken {
-                denom: "uusd".to_string(),
+    let pair_info: PairInfo = query_pair_info(deps.as_ref()).unwrap();
+    assert_eq!(LiquidityPolicy::SaleOwner, pair_info.liquidity_policy);
+
+    let provide_msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: Uint128::from(100u128),
             },
-            amount: offer_amount,
-        },
-        belief_price: None,
-        max_spread: None,
-        to: None,
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::from(100u128),
+            },
+        ],
+        slippage_tolerance: None,
+    };
+    let provide_info = |sender: &str| {
+        mock_info(
+            sender,
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(100u128),
+            }],
+        )
     };
-    let env = mock_env_with_block_time(start_time);
-    let info = mock_info(
-        "addr0000",
-        &[Coin {
-            denom: "uusd".to_string(),
-            amount: offer_amount,
-        }],
-    );
 
-    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
-    let msg_transfer = res.messages.get(0).expect("no message");
+    // the pool can not be seeded once the sale has started
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(start_time),
+        provide_info("addr0000"),
+        provide_msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::SaleStarted {});
 
-    // current price is 1.5, so expected return without spread is 1000
-    // 952.380953 = 20000 - 20000 * 30000 / (30000 + 1500)
-    let expected_ret_amount = Uint128::from(952_380_953u128);
-    let expected_spread_amount = (offer_amount * exchange_rate)
-        .checked_sub(expected_ret_amount)
-        .unwrap();
-    let expected_commission_amount =
-        expected_ret_amount.multiply_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO); // 0.15%
-    let expected_return_amount = expected_ret_amount
-        .checked_sub(expected_commission_amount)
-        .unwrap();
-    let expected_tax_amount = Uint128::zero(); // no tax for token
+    let withdraw_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {}).unwrap(),
+        amount: Uint128::from(100u128),
+    });
 
-    // check simulation res
-    deps.querier.with_balance(&[(
-        &MOCK_CONTRACT_ADDR.to_string(),
-        &[Coin {
-            denom: "uusd".to_string(),
-            amount: collateral_pool_amount, /* user deposit must be pre-applied */
-        }],
-    )]);
+    // liquidity is locked while the sale is running
+    for block_time in [start_time, start_time + 500, end_time] {
+        let res = execute(
+            deps.as_mut(),
+            mock_env_with_block_time(block_time),
+            mock_info("liquidity0000", &[]),
+            withdraw_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(res, ContractError::SaleRunning {});
+    }
 
-    let simulation_res: SimulationResponse = query_simulation(
-        deps.as_ref(),
-        env.clone(),
-        Asset {
-            info: AssetInfo::NativeToken {
-                denom: "uusd".to_string(),
-            },
-            amount: offer_amount,
-        },
-        start_time,
+    // and is unlocked after the sale ends
+    let _res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(end_time + 1),
+        mock_info("liquidity0000", &[]),
+        withdraw_msg.clone(),
     )
     .unwrap();
 
-    let amount_diff =
-        expected_return_amount.u128() as u128 - simulation_res.return_amount.u128() as u128;
-    let commission_diff =
-        expected_commission_amount.u128() as u128 - simulation_res.commission_amount.u128() as u128;
-    let spread_diff = simulation_res.spread_amount.u128() as u128 - expected_spread_amount.u128();
-
-    let diff_tolerance = 10u128;
-
-    assert_eq!(amount_diff < diff_tolerance, true);
-    assert_eq!(commission_diff < diff_tolerance, true);
-    assert_eq!(spread_diff < diff_tolerance, true);
-
-    assert_eq!(String::from("1"), simulation_res.ask_weight);
-    assert_eq!(String::from("1"), simulation_res.offer_weight);
+    // anyone can manage the liquidity of an open pool at any time
+    msg.liquidity_policy = Some(LiquidityPolicy::Open);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut());
 
-    // check reverse simulation res
-    let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
-        deps.as_ref(),
-        env,
-        Asset {
-            info: AssetInfo::Token {
-                contract_addr: Addr::unchecked("asset0000"),
-            },
-            amount: expected_return_amount,
-        },
-        start_time,
+    let _res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(start_time + 500),
+        provide_info("addr0001"),
+        provide_msg,
     )
     .unwrap();
 
-    let offer_diff =
-        reverse_simulation_res.offer_amount.u128() as u128 - offer_amount.u128() as u128;
-    let commission_diff = reverse_simulation_res.commission_amount.u128() as u128
-        - expected_commission_amount.u128() as u128;
-    let spread_diff =
-        reverse_simulation_res.spread_amount.u128() as u128 - expected_spread_amount.u128() as u128;
-
-    assert_eq!(offer_diff < diff_tolerance, true);
-    assert_eq!(commission_diff < diff_tolerance, true);
-    assert_eq!(spread_diff < diff_tolerance, true);
-
-    assert_eq!(String::from("1"), reverse_simulation_res.ask_weight);
-    assert_eq!(String::from("1"), reverse_simulation_res.offer_weight);
-
-    assert_eq!(
-        res.attributes,
-        vec![
-            attr("action", "swap"),
-            attr("offer_asset", "uusd"),
-            attr("ask_asset", "asset0000"),
-            attr("offer_amount", offer_amount.to_string()),
-            attr("return_amount", simulation_res.return_amount.to_string()),
-            attr("tax_amount", expected_tax_amount.to_string()),
-            attr("spread_amount", simulation_res.spread_amount.to_string()),
-            attr(
-                "commission_amount",
-                simulation_res.commission_amount.to_string()
-            ),
-        ]
-    );
-
-    assert_eq!(
-        &SubMsg {
-            id: 0,
-            msg: WasmMsg::Execute {
-                contract_addr: "asset0000".to_string(),
-                msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                    recipient: "addr0000".to_string(),
-                    amount: Uint128::from(simulation_res.return_amount),
-                })
-                .unwrap(),
-                funds: vec![],
-            }
-            .into(),
-            gas_limit: None,
-            reply_on: ReplyOn::Never
-        },
-        msg_transfer,
-    );
+    let _res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(start_time + 500),
+        mock_info("liquidity0000", &[]),
+        withdraw_msg,
+    )
+    .unwrap();
 }
 
 #[test]
-fn try_token_to_native() {
+fn ownership_transfer() {
     let start_time = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
     let end_time = start_time + 1000;
-    let total_share = Uint128::from(20000000000u128);
-    let asset_pool_amount = Uint128::from(30000000000u128);
-    let collateral_pool_amount = Uint128::from(20000000000u128);
-    let price = Decimal::from_ratio(collateral_pool_amount, asset_pool_amount);
-    let exchange_rate = price;
-    let offer_amount = Uint128::from(1500000000u128);
 
-    let mut deps = mock_dependencies(&[Coin {
-        denom: "uusd".to_string(),
-        amount: collateral_pool_amount,
-    }]);
-    deps.querier.with_tax(
-        Decimal::percent(1),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
-    );
-    deps.querier.with_token_balances(&[
-        (
-            &"liquidity0000".to_string(),
-            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
-        ),
-        (
-            &"asset0000".to_string(),
-            &[(
-                &MOCK_CONTRACT_ADDR.to_string(),
-                &(asset_pool_amount + offer_amount),
-            )],
-        ),
-    ]);
+    let mut deps = mock_dependencies(&[]);
 
     let msg = InstantiateMsg {
-        asset_infos: [
+        asset_infos: vec![
             WeightedAssetInfo {
                 info: AssetInfo::NativeToken {
                     denom: "uusd".to_string(),
                 },
                 start_weight: Uint128::from(1u128),
                 end_weight: Uint128::from(1u128),
+                curve: None,
             },
             WeightedAssetInfo {
                 info: AssetInfo::Token {
@@ -974,200 +992,4480 @@ fn try_token_to_native() {
                 },
                 start_weight: Uint128::from(1u128),
                 end_weight: Uint128::from(1u128),
+                curve: None,
             },
         ],
         token_code_id: 10u64,
         start_time,
         end_time,
         description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
     };
 
     let env = mock_env();
     let info = mock_info("addr0000", &[]);
-    // we can just call .unwrap() to assert this was a success
     let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-    // store liquidity token
-    store_liquidity_token(deps.as_mut());
+    // only the current owner can propose a new one
+    let msg = ExecuteMsg::ProposeNewOwner {
+        owner: String::from("addr0001"),
+        expires_in: 100,
+    };
+    let info = mock_info("addr0001", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
 
-    // unauthorized access; can not execute swap directy for token swap
-    let msg = ExecuteMsg::Swap {
-        offer_asset: Asset {
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // only the proposed owner can claim
+    let info = mock_info("addr0002", &[]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        ExecuteMsg::ClaimOwnership {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    // the proposal expires
+    let info = mock_info("addr0001", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(env.block.time.seconds() + 101),
+        info.clone(),
+        ExecuteMsg::ClaimOwnership {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::OwnershipProposalExpired {});
+
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        ExecuteMsg::ClaimOwnership {},
+    )
+    .unwrap();
+
+    let pair_info: PairInfo = query_pair_info(deps.as_ref()).unwrap();
+    assert_eq!(Addr::unchecked("addr0001"), pair_info.owner);
+
+    // the proposal is removed once claimed
+    let info = mock_info("addr0001", &[]);
+    let res = execute(deps.as_mut(), env, info, ExecuteMsg::ClaimOwnership {}).unwrap_err();
+    assert_eq!(res, ContractError::OwnershipProposalNotFound {});
+}
+
+#[test]
+fn pause() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let end_time = start_time + 1000;
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(100u128),
+    }]);
+
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&"addr0000".to_string(), &Uint128::from(100u128))],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(100u128))],
+        ),
+    ]);
+    deps.querier.with_factory("factory0000", "owner0000");
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("factory0000", &[]);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut());
+
+    // only the sale owner and the factory owner can pause the pair
+    let info = mock_info("addr0001", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Pause {}).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    let info = mock_info("owner0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Pause {}).unwrap();
+    assert_eq!(res.attributes, vec![attr("action", "pause")]);
+
+    let pair_info: PairInfo = query_pair_info(deps.as_ref()).unwrap();
+    assert!(pair_info.paused);
+
+    // swaps and providing liquidity are blocked
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::from(10u128),
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        merkle_proof: None,
+    };
+    let info = mock_info(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10u128),
+        }],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(start_time + 100),
+        info,
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Paused {});
+
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: Uint128::from(100u128),
+            },
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::from(100u128),
+            },
+        ],
+        slippage_tolerance: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(100u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::Paused {});
+
+    // withdrawals are only available after the sale
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {}).unwrap(),
+        amount: Uint128::from(100u128),
+    });
+    let info = mock_info("liquidity0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::Paused {});
+
+    let _res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(end_time + 1),
+        info.clone(),
+        msg.clone(),
+    )
+    .unwrap();
+
+    // the sale owner can unpause the pair
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Unpause {},
+    )
+    .unwrap();
+    assert_eq!(res.attributes, vec![attr("action", "unpause")]);
+
+    let pair_info: PairInfo = query_pair_info(deps.as_ref()).unwrap();
+    assert!(!pair_info.paused);
+
+    let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+}
+
+#[test]
+fn update_swap_fee() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let end_time = start_time + 1000;
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(1_000_000_000u128),
+    }]);
+
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&"addr0000".to_string(), &Uint128::from(1_000_000_000u128))],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(1_000_000_000u128),
+            )],
+        ),
+    ]);
+    deps.querier.with_factory("factory0000", "owner0000");
+
+    let mut msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::one(),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("factory0000", &[]);
+    let res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::InvalidSwapFee {});
+
+    msg.swap_fee = Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // only the sale owner can update the swap fee
+    let msg = ExecuteMsg::UpdateSwapFee {
+        swap_fee: Decimal::permille(3),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0001", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    // the swap fee must stay within the factory bounds
+    let info = mock_info("addr0000", &[]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::UpdateSwapFee {
+            swap_fee: Decimal::percent(2),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::SwapFeeOutOfBounds {
+            min_swap_fee: Decimal::zero(),
+            max_swap_fee: Decimal::percent(1),
+        }
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "update_swap_fee"), attr("swap_fee", "0.003"),]
+    );
+
+    let pair_info: PairInfo = query_pair_info(deps.as_ref()).unwrap();
+    assert_eq!(pair_info.swap_fee, Decimal::permille(3));
+
+    // simulations report and apply the swap fee
+    let simulation_res = query_simulation(
+        deps.as_ref(),
+        env.clone(),
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::from(1_000_000u128),
+        },
+        None,
+        start_time,
+    )
+    .unwrap();
+    assert_eq!(simulation_res.swap_fee, Decimal::permille(3));
+    assert_eq!(
+        simulation_res.commission_amount,
+        (simulation_res.return_amount + simulation_res.commission_amount) * Decimal::permille(3)
+    );
+
+    let reverse_simulation_res = query_reverse_simulation(
+        deps.as_ref(),
+        env,
+        Asset {
             info: AssetInfo::Token {
                 contract_addr: Addr::unchecked("asset0000"),
             },
-            amount: offer_amount,
+            amount: Uint128::from(1_000_000u128),
+        },
+        None,
+        start_time,
+    )
+    .unwrap();
+    assert_eq!(reverse_simulation_res.swap_fee, Decimal::permille(3));
+
+    // the swap fee is fixed once the sale starts
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(start_time),
+        info,
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::SaleStarted {});
+}
+
+#[test]
+fn try_native_to_token() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let end_time = start_time + 1000;
+    let total_share = Uint128::from(30000000000u128);
+    let asset_pool_amount = Uint128::from(20000000000u128);
+    let collateral_pool_amount = Uint128::from(30000000000u128);
+    let price = Decimal::from_ratio(collateral_pool_amount, asset_pool_amount);
+    let exchange_rate = Decimal::from(Decimal256::one() / Decimal256::from(price));
+    let offer_amount = Uint128::from(1500000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount, /* user deposit must be pre-applied */
+    }]);
+
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // store liquidity token
+    store_liquidity_token(deps.as_mut());
+
+    // normal swap
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        merkle_proof: None,
+    };
+    let env = mock_env_with_block_time(start_time);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let msg_transfer = res.messages.get(0).expect("no message");
+
+    // current price is 1.5, so expected return without spread is 1000
+    // 952.380953 = 20000 - 20000 * 30000 / (30000 + 1500)
+    let expected_ret_amount = Uint128::from(952_380_953u128);
+    let expected_spread_amount = (offer_amount * exchange_rate)
+        .checked_sub(expected_ret_amount)
+        .unwrap();
+    let expected_commission_amount =
+        expected_ret_amount.multiply_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO); // 0.15%
+    let expected_return_amount = expected_ret_amount
+        .checked_sub(expected_commission_amount)
+        .unwrap();
+    let expected_tax_amount = Uint128::zero(); // no tax for token
+
+    // check simulation res
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount, /* user deposit must be pre-applied */
+        }],
+    )]);
+
+    let simulation_res: SimulationResponse = query_simulation(
+        deps.as_ref(),
+        env.clone(),
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        None,
+        start_time,
+    )
+    .unwrap();
+
+    let amount_diff =
+        expected_return_amount.u128() as u128 - simulation_res.return_amount.u128() as u128;
+    let commission_diff =
+        expected_commission_amount.u128() as u128 - simulation_res.commission_amount.u128() as u128;
+    let spread_diff = simulation_res.spread_amount.u128() as u128 - expected_spread_amount.u128();
+
+    let diff_tolerance = 10u128;
+
+    assert_eq!(amount_diff < diff_tolerance, true);
+    assert_eq!(commission_diff < diff_tolerance, true);
+    assert_eq!(spread_diff < diff_tolerance, true);
+
+    assert_eq!(String::from("1"), simulation_res.ask_weight);
+    assert_eq!(String::from("1"), simulation_res.offer_weight);
+
+    // check reverse simulation res
+    let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
+        deps.as_ref(),
+        env,
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: expected_return_amount,
+        },
+        None,
+        start_time,
+    )
+    .unwrap();
+
+    let offer_diff =
+        reverse_simulation_res.offer_amount.u128() as u128 - offer_amount.u128() as u128;
+    let commission_diff = reverse_simulation_res.commission_amount.u128() as u128
+        - expected_commission_amount.u128() as u128;
+    let spread_diff =
+        reverse_simulation_res.spread_amount.u128() as u128 - expected_spread_amount.u128() as u128;
+
+    assert_eq!(offer_diff < diff_tolerance, true);
+    assert_eq!(commission_diff < diff_tolerance, true);
+    assert_eq!(spread_diff < diff_tolerance, true);
+
+    assert_eq!(String::from("1"), reverse_simulation_res.ask_weight);
+    assert_eq!(String::from("1"), reverse_simulation_res.offer_weight);
+
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "swap"),
+            attr("offer_asset", "uusd"),
+            attr("ask_asset", "asset0000"),
+            attr("offer_amount", offer_amount.to_string()),
+            attr("return_amount", simulation_res.return_amount.to_string()),
+            attr("tax_amount", expected_tax_amount.to_string()),
+            attr("spread_amount", simulation_res.spread_amount.to_string()),
+            attr(
+                "commission_amount",
+                simulation_res.commission_amount.to_string()
+            ),
+            attr("protocol_fee_amount", "0"),
+        ]
+    );
+
+    // the weights and the price of the bought asset after the trade
+    assert_eq!(
+        res.events,
+        vec![Event::new("lbp_swap").add_attributes(vec![
+            attr("sender", "addr0000"),
+            attr("receiver", "addr0000"),
+            attr("offer_asset", "uusd"),
+            attr("ask_asset", "asset0000"),
+            attr("offer_amount", offer_amount.to_string()),
+            attr("return_amount", simulation_res.return_amount.to_string()),
+            attr("refund_amount", "0"),
+            attr("tax_amount", expected_tax_amount.to_string()),
+            attr("spread_amount", simulation_res.spread_amount.to_string()),
+            attr(
+                "commission_amount",
+                simulation_res.commission_amount.to_string()
+            ),
+            attr("protocol_fee_amount", "0"),
+            attr("offer_pool", "31500000000"),
+            attr("ask_pool", "19049047619"),
+            attr("offer_weight", "0.5"),
+            attr("ask_weight", "0.5"),
+            attr("spot_price", "1.653625978055779881"),
+        ])]
+    );
+
+    assert_eq!(
+        &SubMsg {
+            id: 0,
+            msg: WasmMsg::Execute {
+                contract_addr: "asset0000".to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "addr0000".to_string(),
+                    amount: Uint128::from(simulation_res.return_amount),
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into(),
+            gas_limit: None,
+            reply_on: ReplyOn::Never
+        },
+        msg_transfer,
+    );
+}
+
+#[test]
+fn try_token_to_native() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let end_time = start_time + 1000;
+    let total_share = Uint128::from(20000000000u128);
+    let asset_pool_amount = Uint128::from(30000000000u128);
+    let collateral_pool_amount = Uint128::from(20000000000u128);
+    let price = Decimal::from_ratio(collateral_pool_amount, asset_pool_amount);
+    let exchange_rate = price;
+    let offer_amount = Uint128::from(1500000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &(asset_pool_amount + offer_amount),
+            )],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // store liquidity token
+    store_liquidity_token(deps.as_mut());
+
+    // unauthorized access; can not execute swap directy for token swap
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: offer_amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        merkle_proof: None,
+    };
+    let env = mock_env_with_block_time(env.block.time.seconds() + start_time);
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    match res {
+        ContractError::Unauthorized { .. } => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // normal sell
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: offer_amount,
+        msg: to_binary(&Cw20HookMsg::Swap {
+            ask_asset_info: None,
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            merkle_proof: None,
+        })
+        .unwrap(),
+    });
+    let env = mock_env_with_block_time(start_time);
+    let info = mock_info("asset0000", &[]);
+
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let msg_transfer = res.messages.get(0).expect("no message");
+
+    // current price is 1.5, so expected return without spread is 1000
+    // 952.380953 = 20000 - 20000 * 30000 / (30000 + 1500)
+    let expected_ret_amount = Uint128::from(952_380_953u128);
+    let expected_spread_amount = (offer_amount * exchange_rate)
+        .checked_sub(expected_ret_amount)
+        .unwrap();
+    let expected_commission_amount =
+        expected_ret_amount.multiply_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO); // 0.15%
+    let expected_return_amount = expected_ret_amount
+        .checked_sub(expected_commission_amount)
+        .unwrap();
+    let expected_tax_amount = std::cmp::min(
+        Uint128::from(1000000u128),
+        expected_return_amount
+            .checked_sub(
+                expected_return_amount
+                    .multiply_ratio(Uint128::from(100u128), Uint128::from(101u128)),
+            )
+            .unwrap(),
+    );
+    // check simulation res
+    // return asset token balance as normal
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &(asset_pool_amount))],
+        ),
+    ]);
+
+    let simulation_res: SimulationResponse = query_simulation(
+        deps.as_ref(),
+        env.clone(),
+        Asset {
+            amount: offer_amount,
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        },
+        None,
+        start_time,
+    )
+    .unwrap();
+
+    let ret_diff =
+        (expected_return_amount.u128() as i128 - simulation_res.return_amount.u128() as i128).abs();
+    let commission_diff = (expected_commission_amount.u128() as i128
+        - simulation_res.commission_amount.u128() as i128)
+        .abs();
+    let spread_diff =
+        (expected_spread_amount.u128() as i128 - simulation_res.spread_amount.u128() as i128).abs();
+
+    let diff_tolerance = 10i128;
+
+    assert_eq!(ret_diff < diff_tolerance, true);
+    assert_eq!(commission_diff < diff_tolerance, true);
+    assert_eq!(spread_diff < diff_tolerance, true);
+
+    // check reverse simulation res
+    let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
+        deps.as_ref(),
+        env,
+        Asset {
+            amount: expected_return_amount,
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+        },
+        None,
+        start_time,
+    )
+    .unwrap();
+
+    let offer_diff =
+        (offer_amount.u128() as i128 - reverse_simulation_res.offer_amount.u128() as i128).abs();
+    let commission_diff = (expected_commission_amount.u128() as i128
+        - reverse_simulation_res.commission_amount.u128() as i128)
+        .abs();
+    let spread_diff = (expected_spread_amount.u128() as i128
+        - reverse_simulation_res.spread_amount.u128() as i128)
+        .abs();
+
+    let diff_tolerance = 5i128;
+
+    assert_eq!(offer_diff < diff_tolerance, true);
+    assert_eq!(commission_diff < diff_tolerance, true);
+    assert_eq!(spread_diff < diff_tolerance, true);
+
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "swap"),
+            attr("offer_asset", "asset0000"),
+            attr("ask_asset", "uusd"),
+            attr("offer_amount", offer_amount.to_string()),
+            attr("return_amount", simulation_res.return_amount.to_string()),
+            attr("tax_amount", expected_tax_amount.to_string()),
+            attr("spread_amount", simulation_res.spread_amount.to_string()),
+            attr("commission_amount", expected_commission_amount.to_string()),
+            attr("protocol_fee_amount", "0"),
+        ]
+    );
+
+    assert_eq!(
+        &SubMsg {
+            id: 0,
+            msg: BankMsg::Send {
+                to_address: "addr0000".to_string(),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: simulation_res
+                        .return_amount
+                        .checked_sub(expected_tax_amount)
+                        .unwrap(),
+                }],
+            }
+            .into(),
+            gas_limit: None,
+            reply_on: ReplyOn::Never
+        },
+        msg_transfer,
+    );
+
+    // failed due to non asset token contract try to execute sell
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: offer_amount,
+        msg: to_binary(&Cw20HookMsg::Swap {
+            ask_asset_info: None,
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            merkle_proof: None,
+        })
+        .unwrap(),
+    });
+    let env = mock_env_with_block_time(start_time);
+    let info = mock_info("liquidtity0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    match res {
+        ContractError::Unauthorized { .. } => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn protocol_fee() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let end_time = start_time + 1000;
+    let total_share = Uint128::from(30_000_000_000u128);
+    let asset_pool_amount = Uint128::from(20_000_000_000u128);
+    let collateral_pool_amount = Uint128::from(30_000_000_000u128);
+    let offer_amount = Uint128::from(1_500_000_000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+        ),
+    ]);
+    deps.querier.with_factory("factory0000", "owner0000");
+    deps.querier
+        .with_protocol_fee("collector0000", Decimal::percent(50));
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let env = mock_env_with_block_time(start_time);
+    let info = mock_info("factory0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut());
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        merkle_proof: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let commission_amount = Uint128::from_str(&res.attributes[7].value).unwrap();
+    let protocol_fee_amount = commission_amount * Decimal::percent(50);
+    assert!(!protocol_fee_amount.is_zero());
+    assert_eq!(
+        res.attributes[8],
+        attr("protocol_fee_amount", protocol_fee_amount.to_string())
+    );
+
+    // the protocol share of the commission is sent to the collector
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(
+        res.messages[1],
+        SubMsg {
+            id: 0,
+            msg: WasmMsg::Execute {
+                contract_addr: "asset0000".to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "collector0000".to_string(),
+                    amount: protocol_fee_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into(),
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+        }
+    );
+
+    // the swap totals are accumulated per asset
+    let return_amount = Uint128::from_str(&res.attributes[4].value).unwrap();
+    let stats = query_stats(deps.as_ref()).unwrap();
+    assert_eq!(
+        stats.assets,
+        [
+            AssetStats {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                volume_in: offer_amount,
+                volume_out: Uint128::zero(),
+                commission_amount: Uint128::zero(),
+                protocol_fee_amount: Uint128::zero(),
+            },
+            AssetStats {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                volume_in: Uint128::zero(),
+                volume_out: return_amount,
+                commission_amount,
+                protocol_fee_amount,
+            },
+        ]
+    );
+
+    // simulations report the protocol fee
+    let simulation_res = query_simulation(
+        deps.as_ref(),
+        env.clone(),
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        None,
+        start_time,
+    )
+    .unwrap();
+    assert_eq!(
+        simulation_res.protocol_fee_amount,
+        simulation_res.commission_amount * Decimal::percent(50)
+    );
+
+    let reverse_simulation_res = query_reverse_simulation(
+        deps.as_ref(),
+        env,
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: Uint128::from(1_000_000u128),
+        },
+        None,
+        start_time,
+    )
+    .unwrap();
+    assert_eq!(
+        reverse_simulation_res.protocol_fee_amount,
+        reverse_simulation_res.commission_amount * Decimal::percent(50)
+    );
+}
+
+#[test]
+fn swap_direction() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let end_time = start_time + 1000;
+    let total_share = Uint128::from(30_000_000_000u128);
+    let asset_pool_amount = Uint128::from(20_000_000_000u128);
+    let collateral_pool_amount = Uint128::from(30_000_000_000u128);
+    let offer_amount = Uint128::from(1_500_000_000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+        ),
+    ]);
+
+    // buy-only sale, the token can only be bought with uusd
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: Some(SwapDirection::Asset0ToAsset1),
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let env = mock_env_with_block_time(start_time);
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut());
+
+    let pair_info = query_pair_info(deps.as_ref()).unwrap();
+    assert_eq!(SwapDirection::Asset0ToAsset1, pair_info.swap_direction);
+
+    let expected_err = "Swap direction is not allowed, only uusd can be offered";
+
+    // selling the token is rejected
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: offer_amount,
+        msg: to_binary(&Cw20HookMsg::Swap {
+            ask_asset_info: None,
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            merkle_proof: None,
+        })
+        .unwrap(),
+    });
+    let info = mock_info("asset0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::SwapDirectionNotAllowed {
+            allowed: "uusd".to_string()
+        }
+    );
+
+    let res = query_simulation(
+        deps.as_ref(),
+        env.clone(),
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: offer_amount,
+        },
+        None,
+        start_time,
+    )
+    .unwrap_err();
+    assert_eq!(res, StdError::generic_err(expected_err));
+
+    let res = query_reverse_simulation(
+        deps.as_ref(),
+        env.clone(),
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        None,
+        start_time,
+    )
+    .unwrap_err();
+    assert_eq!(res, StdError::generic_err(expected_err));
+
+    // buying the token is allowed
+    query_simulation(
+        deps.as_ref(),
+        env.clone(),
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        None,
+        start_time,
+    )
+    .unwrap();
+
+    query_reverse_simulation(
+        deps.as_ref(),
+        env.clone(),
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: Uint128::from(1_000_000u128),
+        },
+        None,
+        start_time,
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        merkle_proof: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    execute(deps.as_mut(), env, info, msg).unwrap();
+}
+
+#[test]
+fn merkle_allowlist() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let end_time = start_time + 1000;
+    let total_share = Uint128::from(30_000_000_000u128);
+    let asset_pool_amount = Uint128::from(20_000_000_000u128);
+    let collateral_pool_amount = Uint128::from(30_000_000_000u128);
+    let offer_amount = Uint128::from(1_500_000_000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("owner0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let info = mock_info("owner0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut());
+
+    // the allowlist has addr0000 and addr0001
+    let leaf0: [u8; 32] = Sha256::digest(b"addr0000").into();
+    let leaf1: [u8; 32] = Sha256::digest(b"addr0001").into();
+    let merkle_root = if leaf0 < leaf1 {
+        hex::encode(Sha256::digest(&[leaf0, leaf1].concat()))
+    } else {
+        hex::encode(Sha256::digest(&[leaf1, leaf0].concat()))
+    };
+    let merkle_proof = vec![hex::encode(leaf1)];
+
+    // everyone is eligible without a merkle root
+    let res = query_eligibility(deps.as_ref(), "addr0002".to_string(), None).unwrap();
+    assert_eq!(
+        res,
+        EligibilityResponse {
+            merkle_root: None,
+            eligible: true,
+        }
+    );
+
+    // only the sale owner can set the merkle root
+    let msg = ExecuteMsg::UpdateMerkleRoot {
+        merkle_root: Some(merkle_root.clone()),
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    let msg = ExecuteMsg::UpdateMerkleRoot {
+        merkle_root: Some("merkle_root".to_string()),
+    };
+    let info = mock_info("owner0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::InvalidHash {
+            hash: "merkle_root".to_string()
+        }
+    );
+
+    let msg = ExecuteMsg::UpdateMerkleRoot {
+        merkle_root: Some(merkle_root.clone()),
+    };
+    let info = mock_info("owner0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_merkle_root"),
+            attr("merkle_root", merkle_root.clone()),
+        ]
+    );
+
+    // the merkle root can not be changed once the sale has started
+    let env = mock_env_with_block_time(start_time);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::SaleStarted {});
+
+    let res = query_eligibility(
+        deps.as_ref(),
+        "addr0000".to_string(),
+        Some(merkle_proof.clone()),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        EligibilityResponse {
+            merkle_root: Some(merkle_root),
+            eligible: true,
+        }
+    );
+
+    let res = query_eligibility(deps.as_ref(), "addr0000".to_string(), None).unwrap();
+    assert!(!res.eligible);
+
+    let res = query_eligibility(
+        deps.as_ref(),
+        "addr0002".to_string(),
+        Some(merkle_proof.clone()),
+    )
+    .unwrap();
+    assert!(!res.eligible);
+
+    let swap_msg = |merkle_proof: Option<Vec<String>>| ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        merkle_proof,
+    };
+    let funds = [Coin {
+        denom: "uusd".to_string(),
+        amount: offer_amount,
+    }];
+
+    // the proof of another address is rejected
+    let info = mock_info("addr0002", &funds);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        swap_msg(Some(merkle_proof.clone())),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NotAllowed {});
+
+    let info = mock_info("addr0000", &funds);
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), swap_msg(None)).unwrap_err();
+    assert_eq!(res, ContractError::NotAllowed {});
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        swap_msg(Some(merkle_proof.clone())),
+    )
+    .unwrap();
+
+    // the allowlist applies to the sender of the swap, not to its receiver
+    let routed_swap_msg = |to: &str| ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: Some(Addr::unchecked(to)),
+        merkle_proof: Some(merkle_proof.clone()),
+    };
+    let info = mock_info("addr0002", &funds);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        routed_swap_msg("addr0000"),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NotAllowed {});
+
+    let info = mock_info("addr0000", &funds);
+    execute(deps.as_mut(), env, info, routed_swap_msg("addr0002")).unwrap();
+}
+
+#[test]
+fn purchase_cap() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let end_time = start_time + 1000;
+    let total_share = Uint128::from(30_000_000_000u128);
+    let asset_pool_amount = Uint128::from(20_000_000_000u128);
+    let collateral_pool_amount = Uint128::from(30_000_000_000u128);
+    let offer_amount = Uint128::from(1_500_000_000u128);
+    let cap_amount = Uint128::from(1_500_000_000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("owner0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let info = mock_info("owner0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut());
+
+    let res = query_allowance(deps.as_ref(), "addr0000".to_string()).unwrap();
+    assert_eq!(
+        res,
+        AllowanceResponse {
+            purchase_cap: None,
+            purchased: Uint128::zero(),
+            remaining: None,
+        }
+    );
+
+    // only the sale owner can set the purchase cap
+    let purchase_cap = PurchaseCap::Received {
+        info: AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        amount: cap_amount,
+    };
+    let msg = ExecuteMsg::UpdatePurchaseCap {
+        purchase_cap: Some(purchase_cap.clone()),
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    let wrong_msg = ExecuteMsg::UpdatePurchaseCap {
+        purchase_cap: Some(PurchaseCap::Spent {
+            info: AssetInfo::NativeToken {
+                denom: "ukrw".to_string(),
+            },
+            amount: cap_amount,
+        }),
+    };
+    let info = mock_info("owner0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), wrong_msg).unwrap_err();
+    assert_eq!(res, ContractError::AssetMismatch {});
+
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_purchase_cap"),
+            attr("purchase_cap", "received 1500000000asset0000"),
+        ]
+    );
+
+    // the purchase cap can not be changed once the sale has started
+    let env = mock_env_with_block_time(start_time);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::SaleStarted {});
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        merkle_proof: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    let return_amount = Uint128::from_str(&res.attributes[4].value).unwrap();
+
+    let res = query_allowance(deps.as_ref(), "addr0000".to_string()).unwrap();
+    assert_eq!(
+        res,
+        AllowanceResponse {
+            purchase_cap: Some(purchase_cap.clone()),
+            purchased: return_amount,
+            remaining: Some(cap_amount - return_amount),
+        }
+    );
+
+    // the second purchase would exceed the cap
+    let res = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::PurchaseCapExceeded {
+            remaining: cap_amount - return_amount,
+        }
+    );
+
+    // other addresses have their own allowance
+    let res = query_allowance(deps.as_ref(), "addr0001".to_string()).unwrap();
+    assert_eq!(
+        res,
+        AllowanceResponse {
+            purchase_cap: Some(purchase_cap),
+            purchased: Uint128::zero(),
+            remaining: Some(cap_amount),
+        }
+    );
+
+    let info = mock_info(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // the cap applies to the sender of the swap, naming another receiver does not bypass it
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: Some(Addr::unchecked("addr0002")),
+        merkle_proof: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::PurchaseCapExceeded {
+            remaining: cap_amount - return_amount,
+        }
+    );
+}
+
+#[test]
+fn max_ratios() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let end_time = start_time + 1000;
+    let total_share = Uint128::from(30_000_000_000u128);
+    let asset_pool_amount = Uint128::from(20_000_000_000u128);
+    let collateral_pool_amount = Uint128::from(30_000_000_000u128);
+    let offer_amount = Uint128::from(3_500_000_000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+        ),
+    ]);
+
+    let mut msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: Some(Decimal::zero()),
+        max_out_ratio: None,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::InvalidMaxRatio {});
+
+    msg.max_in_ratio = None;
+    msg.max_out_ratio = Some(Decimal::one());
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::InvalidMaxRatio {});
+
+    // the ratios default to 30%
+    msg.max_out_ratio = None;
+    let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    let pair_info = query_pair_info(deps.as_ref()).unwrap();
+    assert_eq!(pair_info.max_in_ratio, Decimal::percent(30));
+    assert_eq!(pair_info.max_out_ratio, Decimal::percent(30));
+
+    msg.max_in_ratio = Some(Decimal::percent(10));
+    msg.max_out_ratio = Some(Decimal::percent(5));
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut());
+
+    let env = mock_env_with_block_time(start_time);
+    let swap_msg = |amount: Uint128| ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        merkle_proof: None,
+    };
+    let funds = |amount: Uint128| {
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount,
+        }]
+    };
+
+    // 3_500_000_000 uusd is more than 10% of the offer pool
+    let info = mock_info("addr0000", &funds(offer_amount));
+    let res = execute(deps.as_mut(), env.clone(), info, swap_msg(offer_amount)).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::MaxInRatioExceeded {
+            max_in_ratio: Decimal::percent(10)
+        }
+    );
+
+    let res = query_simulation(
+        deps.as_ref(),
+        env.clone(),
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        None,
+        start_time,
+    )
+    .unwrap_err();
+    assert_eq!(
+        res,
+        StdError::generic_err("Offer amount exceeds 0.1 of the offer pool")
+    );
+
+    // 2_500_000_000 uusd would return more than 5% of the ask pool
+    let offer_amount = Uint128::from(2_500_000_000u128);
+    let info = mock_info("addr0000", &funds(offer_amount));
+    let res = execute(deps.as_mut(), env.clone(), info, swap_msg(offer_amount)).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::MaxOutRatioExceeded {
+            max_out_ratio: Decimal::percent(5)
+        }
+    );
+
+    let res = query_reverse_simulation(
+        deps.as_ref(),
+        env.clone(),
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: Uint128::from(1_500_000_000u128),
+        },
+        None,
+        start_time,
+    )
+    .unwrap_err();
+    assert_eq!(
+        res,
+        StdError::generic_err("Return amount exceeds 0.05 of the ask pool")
+    );
+
+    // trades within the ratios go through
+    let offer_amount = Uint128::from(1_000_000_000u128);
+    let info = mock_info("addr0000", &funds(offer_amount));
+    execute(deps.as_mut(), env, info, swap_msg(offer_amount)).unwrap();
+}
+
+#[test]
+fn twap() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let end_time = start_time + 1000;
+    let total_share = Uint128::from(30_000_000_000u128);
+    let asset_pool_amount = Uint128::from(20_000_000_000u128);
+    let collateral_pool_amount = Uint128::from(30_000_000_000u128);
+    let offer_amount = Uint128::from(1_500_000_000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let instantiate_time = mock_env().block.time.seconds();
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut());
+
+    // the swap accumulates the prices before the swap: 30_000_000_000 uusd for 20_000_000_000 asset
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        merkle_proof: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let env = mock_env_with_block_time(start_time + 100);
+    execute(deps.as_mut(), env, info, msg).unwrap();
+
+    let env = mock_env_with_block_time(start_time + 200);
+    let res = query_twap(deps.as_ref(), env.clone(), start_time, start_time + 100).unwrap();
+    assert_eq!(
+        res,
+        TwapResponse {
+            start: start_time,
+            end: start_time + 100,
+            price0_average: Decimal::from_str("0.666666666666666666").unwrap(),
+            price1_average: Decimal::from_ratio(3u128, 2u128),
+        }
+    );
+
+    // after the last update the prices come from the current pools:
+    // 31_500_000_000 uusd for 20_000_000_000 asset
+    let res = query_twap(
+        deps.as_ref(),
+        env.clone(),
+        start_time + 100,
+        start_time + 200,
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        TwapResponse {
+            start: start_time + 100,
+            end: start_time + 200,
+            price0_average: Decimal::from_str("0.634920634920634920").unwrap(),
+            price1_average: Decimal::from_ratio(63u128, 40u128),
+        }
+    );
+
+    let res = query_cumulative_prices(deps.as_ref(), env.clone()).unwrap();
+    assert_eq!(res.total_share, total_share);
+    assert_eq!(
+        res.price1_cumulative_last,
+        Uint128::from(
+            1_500_000_000_000_000_000u128 * u128::from(start_time + 100 - instantiate_time)
+        ) + Uint128::from(157_500_000_000_000_000_000u128)
+    );
+
+    let res = query_twap(deps.as_ref(), env.clone(), start_time, start_time).unwrap_err();
+    assert_eq!(
+        res,
+        StdError::generic_err("start must be less than end and end can not be in the future")
+    );
+
+    let res = query_twap(deps.as_ref(), env.clone(), start_time, start_time + 201).unwrap_err();
+    assert_eq!(
+        res,
+        StdError::generic_err("start must be less than end and end can not be in the future")
+    );
+
+    let res = query_twap(deps.as_ref(), env, instantiate_time - 1, start_time).unwrap_err();
+    assert_eq!(
+        res,
+        StdError::generic_err("Prices are not recorded before the given time")
+    );
+
+    // records closer than 10 minutes to the previous one are moved by the next update
+    for time in [start_time + 150, start_time + 300] {
+        let msg = ExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: offer_amount,
+            },
+            ask_asset_info: None,
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            merkle_proof: None,
+        };
+        let info = mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: offer_amount,
+            }],
+        );
+        execute(deps.as_mut(), mock_env_with_block_time(time), info, msg).unwrap();
+    }
+
+    // the instantiate record is older than the history period, but the last one before it is kept
+    let record_times: Vec<u64> = PRICE_CUMULATIVES
+        .range(&deps.storage, None, None, Order::Ascending)
+        .map(|item| item.unwrap().1.block_time)
+        .collect();
+    assert_eq!(
+        record_times,
+        vec![instantiate_time, start_time + 100, start_time + 300]
+    );
+
+    let env = mock_env_with_block_time(start_time + 300);
+    let res = query_twap(deps.as_ref(), env, start_time + 100, start_time + 300).unwrap();
+    assert_eq!(res.price1_average, Decimal::from_ratio(3u128, 2u128));
+}
+
+#[test]
+fn spot_price() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let end_time = start_time + 1000;
+    let total_share = Uint128::from(30_000_000_000u128);
+    let asset_pool_amount = Uint128::from(20_000_000_000u128);
+    let collateral_pool_amount = Uint128::from(30_000_000_000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(3u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                start_weight: Uint128::from(3u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut());
+
+    let asset_info = AssetInfo::Token {
+        contract_addr: Addr::unchecked("asset0000"),
+    };
+    let uusd_info = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+
+    // (30_000_000_000 / 1) / (20_000_000_000 / 3) uusd per asset at the start
+    let env = mock_env_with_block_time(start_time);
+    let res = query_spot_price(deps.as_ref(), env.clone(), asset_info.clone(), None, None).unwrap();
+    assert_eq!(
+        res,
+        SpotPriceResponse {
+            base_asset: asset_info.clone(),
+            quote_asset: uusd_info.clone(),
+            at_time: start_time,
+            spot_price: Decimal::from_ratio(9u128, 2u128),
+            spot_price_with_fee: Decimal::from_str("4.506760140210315473").unwrap(),
+        }
+    );
+
+    let res = query_spot_price(deps.as_ref(), env.clone(), uusd_info.clone(), None, None).unwrap();
+    assert_eq!(res.quote_asset, asset_info);
+    assert_eq!(
+        res.spot_price,
+        Decimal::from_str("0.222222222222222222").unwrap()
+    );
+
+    // the weights at the requested time are used
+    let res = query_spot_price(
+        deps.as_ref(),
+        env.clone(),
+        asset_info.clone(),
+        None,
+        Some(start_time + 500),
+    )
+    .unwrap();
+    assert_eq!(res.at_time, start_time + 500);
+    assert_eq!(res.spot_price, Decimal::from_ratio(3u128, 2u128));
+
+    // and they stay at the start and end weights outside of the sale
+    let res = query_spot_price(
+        deps.as_ref(),
+        env.clone(),
+        asset_info.clone(),
+        None,
+        Some(start_time - 100),
+    )
+    .unwrap();
+    assert_eq!(res.spot_price, Decimal::from_ratio(9u128, 2u128));
+
+    let res = query_spot_price(
+        deps.as_ref(),
+        env.clone(),
+        asset_info.clone(),
+        None,
+        Some(end_time + 100),
+    )
+    .unwrap();
+    assert_eq!(res.spot_price, Decimal::from_ratio(1u128, 2u128));
+    assert_eq!(
+        res.spot_price_with_fee,
+        Decimal::from_str("0.500751126690035052").unwrap()
+    );
+
+    // the price curve samples the whole sale period
+    let res = query_price_curve(deps.as_ref(), env.clone(), asset_info.clone(), None, 3).unwrap();
+    assert_eq!(
+        res,
+        PriceCurveResponse {
+            base_asset: asset_info.clone(),
+            quote_asset: uusd_info,
+            points: vec![
+                PricePoint {
+                    time: start_time,
+                    base_weight: "3".to_string(),
+                    quote_weight: "1".to_string(),
+                    spot_price: Decimal::from_ratio(9u128, 2u128),
+                },
+                PricePoint {
+                    time: start_time + 500,
+                    base_weight: "2".to_string(),
+                    quote_weight: "2".to_string(),
+                    spot_price: Decimal::from_ratio(3u128, 2u128),
+                },
+                PricePoint {
+                    time: end_time,
+                    base_weight: "1".to_string(),
+                    quote_weight: "3".to_string(),
+                    spot_price: Decimal::from_ratio(1u128, 2u128),
+                },
+            ],
+        }
+    );
+
+    for points in [1, 101] {
+        let res = query_price_curve(deps.as_ref(), env.clone(), asset_info.clone(), None, points)
+            .unwrap_err();
+        assert_eq!(
+            res,
+            StdError::generic_err("points must be between 2 and 100")
+        );
+    }
+
+    let res = query_spot_price(
+        deps.as_ref(),
+        env,
+        AssetInfo::NativeToken {
+            denom: "ukrw".to_string(),
+        },
+        None,
+        None,
+    )
+    .unwrap_err();
+    assert_eq!(
+        res,
+        StdError::generic_err("Asset does not belong to the pair")
+    );
+}
+
+#[test]
+fn graduate_to_pool() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let end_time = start_time + 1000;
+    let asset_pool_amount = Uint128::from(20_000_000_000u128);
+    let collateral_pool_amount = Uint128::from(30_000_000_000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[
+                (&"addr0000".to_string(), &Uint128::from(24_000_000_000u128)),
+                (&"addr0001".to_string(), &Uint128::from(6_000_000_000u128)),
+            ],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+        ),
+    ]);
+    deps.querier.with_factory("factory0000", "owner0000");
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(4u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                start_weight: Uint128::from(4u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let info = mock_info("factory0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut());
+
+    // the sale must be over
+    let info = mock_info("addr0000", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(end_time),
+        info,
+        ExecuteMsg::GraduateToPool {},
+    );
+    assert_eq!(res.unwrap_err(), ContractError::SaleNotEnded {});
+
+    let env = mock_env_with_block_time(end_time + 1);
+
+    // only the sale owner can graduate
+    let info = mock_info("addr0001", &[]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        ExecuteMsg::GraduateToPool {},
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let info = mock_info("addr0000", &[]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::GraduateToPool {},
+    );
+    assert_eq!(res.unwrap_err(), ContractError::XykPairCodeIdNotSet {});
+
+    deps.querier.with_xyk_pair_code_id(456u64);
+
+    // the owner holds 80% of the liquidity: 24_000_000_000 uusd and 16_000_000_000 asset,
+    // the final price is (30_000_000_000 / 4) / (20_000_000_000 / 1) = 0.375 asset per uusd
+    // so 6_000_000_000 uusd are seeded and 18_000_000_000 uusd go back to the owner
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::GraduateToPool {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "graduate_to_pool"),
+            attr("withdrawn_share", "24000000000"),
+            attr("graduation_assets", "5999000000uusd, 16000000000asset0000"),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("liquidity0000"),
+                msg: to_binary(&Cw20ExecuteMsg::BurnFrom {
+                    owner: String::from("addr0000"),
+                    amount: Uint128::from(24_000_000_000u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(BankMsg::Send {
+                to_address: String::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(17_999_000_000u128),
+                }],
+            }),
+            SubMsg {
+                id: 2,
+                msg: WasmMsg::Instantiate {
+                    admin: Some(String::from("owner0000")),
+                    code_id: 456u64,
+                    msg: to_binary(&XykPairInstantiateMsg {
+                        asset_infos: [
+                            AssetInfo::NativeToken {
+                                denom: "uusd".to_string(),
+                            },
+                            AssetInfo::Token {
+                                contract_addr: Addr::unchecked("asset0000"),
+                            },
+                        ],
+                        token_code_id: 123u64,
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                    label: String::from("astroport-lbp graduated pair"),
+                }
+                .into(),
+                gas_limit: None,
+                reply_on: ReplyOn::Success,
+            },
+        ]
+    );
+
+    // store the graduated pair by name: xyk0000
+    let reply_msg = Reply {
+        id: 2,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(vec![10, 7, 120, 121, 107, 48, 48, 48, 48].into()),
+        }),
+    };
+    let res = reply(deps.as_mut(), env.clone(), reply_msg).unwrap();
+    let graduation_assets = [
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::from(5_999_000_000u128),
+        },
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: Uint128::from(16_000_000_000u128),
+        },
+    ];
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::IncreaseAllowance {
+                    spender: String::from("xyk0000"),
+                    amount: Uint128::from(16_000_000_000u128),
+                    expires: None,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("xyk0000"),
+                msg: to_binary(&XykPairExecuteMsg::ProvideLiquidity {
+                    assets: graduation_assets,
+                    slippage_tolerance: None,
+                    receiver: Some(String::from("addr0000")),
+                })
+                .unwrap(),
+                funds: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(5_999_000_000u128),
+                }],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("factory0000"),
+                msg: to_binary(&FactoryExecuteMsg::RegisterGraduatedPair {
+                    asset_infos: vec![
+                        AssetInfo::NativeToken {
+                            denom: "uusd".to_string(),
+                        },
+                        AssetInfo::Token {
+                            contract_addr: Addr::unchecked("asset0000"),
+                        },
+                    ],
+                    graduated_pair: String::from("xyk0000"),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+
+    assert_eq!(
+        query_graduation(deps.as_ref()).unwrap(),
+        GraduationResponse {
+            graduated_pair: Some(Addr::unchecked("xyk0000")),
+        }
+    );
+
+    // a sale graduates only once
+    let res = execute(deps.as_mut(), env, info, ExecuteMsg::GraduateToPool {});
+    assert_eq!(res.unwrap_err(), ContractError::SaleGraduated {});
+}
+
+#[test]
+fn vesting() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let end_time = start_time + 1000;
+    let total_share = Uint128::from(30_000_000_000u128);
+    let asset_pool_amount = Uint128::from(20_000_000_000u128);
+    let collateral_pool_amount = Uint128::from(30_000_000_000u128);
+    let offer_amount = Uint128::from(1_500_000_000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("owner0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let info = mock_info("owner0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut());
+
+    // only the sale owner can set the vesting
+    let vesting = VestingConfig {
+        info: AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        cliff: 100,
+        duration: 1000,
+    };
+    let msg = ExecuteMsg::UpdateVesting {
+        vesting: Some(vesting.clone()),
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    let info = mock_info("owner0000", &[]);
+    let wrong_msg = ExecuteMsg::UpdateVesting {
+        vesting: Some(VestingConfig {
+            info: AssetInfo::NativeToken {
+                denom: "ukrw".to_string(),
+            },
+            cliff: 100,
+            duration: 1000,
+        }),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), wrong_msg).unwrap_err();
+    assert_eq!(res, ContractError::AssetMismatch {});
+
+    let wrong_msg = ExecuteMsg::UpdateVesting {
+        vesting: Some(VestingConfig {
+            cliff: 1001,
+            ..vesting.clone()
+        }),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), wrong_msg).unwrap_err();
+    assert_eq!(res, ContractError::InvalidVesting {});
+
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_vesting"),
+            attr("vesting", "asset0000 cliff 100 duration 1000"),
+        ]
+    );
+
+    // the vesting can not be changed once the sale has started
+    let env = mock_env_with_block_time(start_time);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::SaleStarted {});
+
+    // the purchased asset is recorded instead of sent
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        merkle_proof: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert!(res.messages.is_empty());
+    let return_amount = Uint128::from_str(&res.attributes[4].value).unwrap();
+
+    // the unclaimed amount is not a part of the pool
+    let res = query_pool(deps.as_ref(), env).unwrap();
+    assert_eq!(res.assets[1].amount, asset_pool_amount - return_amount);
+
+    let res = query_vesting_info(
+        deps.as_ref(),
+        mock_env_with_block_time(end_time + 99),
+        "addr0000".to_string(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        VestingInfoResponse {
+            vesting: Some(vesting.clone()),
+            purchased: return_amount,
+            claimed: Uint128::zero(),
+            vested: Uint128::zero(),
+            claimable: Uint128::zero(),
+        }
+    );
+
+    let info = mock_info("addr0000", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(end_time + 99),
+        info.clone(),
+        ExecuteMsg::Claim {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NothingToClaim {});
+
+    // half of the purchase vests in 500 seconds after the sale
+    let half_amount = return_amount.multiply_ratio(1u128, 2u128);
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(end_time + 500),
+        info.clone(),
+        ExecuteMsg::Claim {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: String::from("asset0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("addr0000"),
+                amount: half_amount,
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "claim"),
+            attr("claimed_asset", format!("{}asset0000", half_amount)),
+        ]
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(end_time + 2000),
+        info,
+        ExecuteMsg::Claim {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "claim"),
+            attr(
+                "claimed_asset",
+                format!("{}asset0000", return_amount - half_amount)
+            ),
+        ]
+    );
+
+    let env = mock_env_with_block_time(end_time + 2000);
+    let res = query_vesting_info(deps.as_ref(), env.clone(), "addr0000".to_string()).unwrap();
+    assert_eq!(
+        res,
+        VestingInfoResponse {
+            vesting: Some(vesting),
+            purchased: return_amount,
+            claimed: return_amount,
+            vested: return_amount,
+            claimable: Uint128::zero(),
+        }
+    );
+
+    // other addresses have nothing to claim
+    let info = mock_info("addr0001", &[]);
+    let res = execute(deps.as_mut(), env, info, ExecuteMsg::Claim {}).unwrap_err();
+    assert_eq!(res, ContractError::NothingToClaim {});
+}
+
+#[test]
+fn soft_cap() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let end_time = start_time + 1000;
+    let total_share = Uint128::from(30_000_000_000u128);
+    let asset_pool_amount = Uint128::from(20_000_000_000u128);
+    let collateral_pool_amount = Uint128::from(30_000_000_000u128);
+    let offer_amount = Uint128::from(1_500_000_000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("owner0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let info = mock_info("owner0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut());
+
+    // only the sale owner can set the soft cap
+    let soft_cap = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: Uint128::from(10_000_000_000u128),
+    };
+    let msg = ExecuteMsg::UpdateSoftCap {
+        soft_cap: Some(soft_cap.clone()),
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    let info = mock_info("owner0000", &[]);
+    let wrong_msg = ExecuteMsg::UpdateSoftCap {
+        soft_cap: Some(Asset {
+            info: AssetInfo::NativeToken {
+                denom: "ukrw".to_string(),
+            },
+            amount: Uint128::from(10_000_000_000u128),
+        }),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), wrong_msg).unwrap_err();
+    assert_eq!(res, ContractError::AssetMismatch {});
+
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_soft_cap"),
+            attr("soft_cap", "10000000000uusd"),
+        ]
+    );
+
+    // the soft cap can not be changed once the sale has started
+    let env = mock_env_with_block_time(start_time);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::SaleStarted {});
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        merkle_proof: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let return_amount = Uint128::from_str(&res.attributes[4].value).unwrap();
+
+    assert_eq!(
+        query_contribution(deps.as_ref(), "addr0000".to_string()).unwrap(),
+        ContributionResponse {
+            paid: offer_amount,
+            received: return_amount,
+        }
+    );
+    assert_eq!(
+        query_soft_cap(deps.as_ref(), env.clone()).unwrap(),
+        SoftCapResponse {
+            soft_cap: Some(soft_cap.clone()),
+            raised: offer_amount,
+            refundable: offer_amount,
+            refunding: false,
+        }
+    );
+
+    // refunds are not available while the sale is running
+    let refund_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: return_amount,
+        msg: to_binary(&Cw20HookMsg::Refund {}).unwrap(),
+    });
+    let info = mock_info("asset0000", &[]);
+    let res = execute(deps.as_mut(), env, info, refund_msg).unwrap_err();
+    assert_eq!(res, ContractError::RefundsUnavailable {});
+
+    let env = mock_env_with_block_time(end_time + 1);
+    assert_eq!(
+        query_soft_cap(deps.as_ref(), env.clone()).unwrap(),
+        SoftCapResponse {
+            soft_cap: Some(soft_cap.clone()),
+            raised: offer_amount,
+            refundable: offer_amount,
+            refunding: true,
+        }
+    );
+
+    // the liquidity is locked until everything is refunded
+    let withdraw_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("owner0000"),
+        amount: Uint128::from(100u128),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {}).unwrap(),
+    });
+    let info = mock_info("liquidity0000", &[]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        withdraw_msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::RefundsNotSettled {});
+
+    // only the bought token can be returned
+    let half_amount = return_amount.multiply_ratio(1u128, 2u128);
+    let refund_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: half_amount,
+        msg: to_binary(&Cw20HookMsg::Refund {}).unwrap(),
+    });
+    let res = execute(deps.as_mut(), env.clone(), info, refund_msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    let info = mock_info("asset0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), refund_msg).unwrap();
+    let refund_amount = offer_amount.multiply_ratio(half_amount, return_amount);
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: String::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: refund_amount,
+            }],
+        })]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "refund"),
+            attr("returned_asset", format!("{}asset0000", half_amount)),
+            attr("refund_asset", format!("{}uusd", refund_amount)),
+        ]
+    );
+
+    let refund_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: return_amount,
+        msg: to_binary(&Cw20HookMsg::Refund {}).unwrap(),
+    });
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), refund_msg).unwrap_err();
+    assert_eq!(res, ContractError::RefundExceedsPurchase {});
+
+    let refund_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: return_amount - half_amount,
+        msg: to_binary(&Cw20HookMsg::Refund {}).unwrap(),
+    });
+    let res = execute(deps.as_mut(), env.clone(), info, refund_msg).unwrap();
+    assert_eq!(
+        res.attributes[2],
+        attr(
+            "refund_asset",
+            format!("{}uusd", offer_amount - refund_amount)
+        )
+    );
+
+    assert_eq!(
+        query_contribution(deps.as_ref(), "addr0000".to_string()).unwrap(),
+        ContributionResponse {
+            paid: Uint128::zero(),
+            received: Uint128::zero(),
+        }
+    );
+
+    // the liquidity can be withdrawn once the refunds are settled
+    let info = mock_info("liquidity0000", &[]);
+    execute(deps.as_mut(), env, info, withdraw_msg).unwrap();
+}
+
+#[test]
+fn soft_cap_sell_back() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let end_time = start_time + 1000;
+    let total_share = Uint128::from(30_000_000_000u128);
+    let asset_pool_amount = Uint128::from(20_000_000_000u128);
+    let collateral_pool_amount = Uint128::from(30_000_000_000u128);
+    let offer_amount = Uint128::from(1_500_000_000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("owner0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let info = mock_info("owner0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut());
+
+    let soft_cap = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: Uint128::from(10_000_000_000u128),
+    };
+    let msg = ExecuteMsg::UpdateSoftCap {
+        soft_cap: Some(soft_cap.clone()),
+    };
+    let info = mock_info("owner0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let env = mock_env_with_block_time(start_time);
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        merkle_proof: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let return_amount = Uint128::from_str(&res.attributes[4].value).unwrap();
+
+    let sell_msg = |sender: &str, amount: Uint128| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from(sender),
+            amount,
+            msg: to_binary(&Cw20HookMsg::Swap {
+                ask_asset_info: None,
+                belief_price: None,
+                max_spread: None,
+                to: None,
+                merkle_proof: None,
+            })
+            .unwrap(),
+        })
+    };
+
+    // selling back reduces the paid amount by the share of the sold tokens
+    let half_amount = return_amount.multiply_ratio(1u128, 2u128);
+    let info = mock_info("asset0000", &[]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        sell_msg("addr0000", half_amount),
+    )
+    .unwrap();
+    let half_sold_for = Uint128::from_str(&res.attributes[4].value).unwrap();
+    let paid = offer_amount - offer_amount.multiply_ratio(half_amount, return_amount);
+    assert_eq!(
+        query_contribution(deps.as_ref(), "addr0000".to_string()).unwrap(),
+        ContributionResponse {
+            paid,
+            received: return_amount - half_amount,
+        }
+    );
+
+    // selling the rest back at a loss settles the whole contribution
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        sell_msg("addr0000", return_amount - half_amount),
+    )
+    .unwrap();
+    let sold_for = Uint128::from_str(&res.attributes[4].value).unwrap();
+    assert!(sold_for < paid);
+    assert_eq!(
+        query_contribution(deps.as_ref(), "addr0000".to_string()).unwrap(),
+        ContributionResponse {
+            paid: Uint128::zero(),
+            received: Uint128::zero(),
+        }
+    );
+
+    let raised = offer_amount - half_sold_for - sold_for;
+    assert_eq!(
+        query_soft_cap(deps.as_ref(), env.clone()).unwrap(),
+        SoftCapResponse {
+            soft_cap: Some(soft_cap.clone()),
+            raised,
+            refundable: Uint128::zero(),
+            refunding: false,
+        }
+    );
+
+    // buying for another address and selling back from a third one does not add to the raised
+    // amount, the contribution stays with the receiver of the purchase
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: Some(Addr::unchecked("addr0001")),
+        merkle_proof: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let return_amount = Uint128::from_str(&res.attributes[4].value).unwrap();
+
+    let info = mock_info("asset0000", &[]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        sell_msg("addr0002", return_amount),
+    )
+    .unwrap();
+    let sold_for = Uint128::from_str(&res.attributes[4].value).unwrap();
+    assert_eq!(
+        query_soft_cap(deps.as_ref(), env).unwrap(),
+        SoftCapResponse {
+            soft_cap: Some(soft_cap.clone()),
+            raised: raised + offer_amount - sold_for,
+            refundable: offer_amount,
+            refunding: false,
+        }
+    );
+    assert_eq!(
+        query_contribution(deps.as_ref(), "addr0001".to_string()).unwrap(),
+        ContributionResponse {
+            paid: offer_amount,
+            received: return_amount,
+        }
+    );
+
+    let env = mock_env_with_block_time(end_time + 1);
+    assert!(
+        query_soft_cap(deps.as_ref(), env.clone())
+            .unwrap()
+            .refunding
+    );
+
+    // nothing is left to refund for the seller, the receiver of the second purchase can still
+    // get a refund by returning the tokens
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Refund {}).unwrap_err();
+    assert_eq!(res, ContractError::NothingToRefund {});
+
+    let withdraw_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("owner0000"),
+        amount: Uint128::from(100u128),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {}).unwrap(),
+    });
+    let info = mock_info("liquidity0000", &[]);
+    let res = execute(deps.as_mut(), env, info.clone(), withdraw_msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::RefundsNotSettled {});
+
+    // refunds close after 7 days and the liquidity is released even if not everything is refunded
+    let env = mock_env_with_block_time(end_time + 604800 + 1);
+    assert!(
+        !query_soft_cap(deps.as_ref(), env.clone())
+            .unwrap()
+            .refunding
+    );
+
+    let refund_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0001"),
+        amount: return_amount,
+        msg: to_binary(&Cw20HookMsg::Refund {}).unwrap(),
+    });
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("asset0000", &[]),
+        refund_msg,
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::RefundsUnavailable {});
+
+    execute(deps.as_mut(), env, info, withdraw_msg).unwrap();
+}
+
+#[test]
+fn hard_cap() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let end_time = start_time + 1000;
+    let total_share = Uint128::from(30_000_000_000u128);
+    let asset_pool_amount = Uint128::from(20_000_000_000u128);
+    let collateral_pool_amount = Uint128::from(30_000_000_000u128);
+    let offer_amount = Uint128::from(1_500_000_000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("owner0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let info = mock_info("owner0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut());
+
+    // only the sale owner can set the hard cap
+    let hard_cap = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: Uint128::from(2_000_000_000u128),
+    };
+    let msg = ExecuteMsg::UpdateHardCap {
+        hard_cap: Some(hard_cap.clone()),
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    let info = mock_info("owner0000", &[]);
+    let wrong_msg = ExecuteMsg::UpdateHardCap {
+        hard_cap: Some(Asset {
+            info: AssetInfo::NativeToken {
+                denom: "ukrw".to_string(),
+            },
+            amount: Uint128::from(2_000_000_000u128),
+        }),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), wrong_msg).unwrap_err();
+    assert_eq!(res, ContractError::AssetMismatch {});
+
+    let zero_msg = ExecuteMsg::UpdateHardCap {
+        hard_cap: Some(Asset {
+            info: hard_cap.info.clone(),
+            amount: Uint128::zero(),
+        }),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), zero_msg).unwrap_err();
+    assert_eq!(res, ContractError::ZeroHardCap {});
+
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_hard_cap"),
+            attr("hard_cap", "2000000000uusd"),
+        ]
+    );
+
+    // the soft cap can not exceed the hard cap
+    let soft_cap_msg = ExecuteMsg::UpdateSoftCap {
+        soft_cap: Some(Asset {
+            info: hard_cap.info.clone(),
+            amount: Uint128::from(3_000_000_000u128),
+        }),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), soft_cap_msg).unwrap_err();
+    assert_eq!(res, ContractError::SoftCapAboveHardCap {});
+
+    // the hard cap can not be changed once the sale has started
+    let env = mock_env_with_block_time(start_time + 100);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::SaleStarted {});
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        merkle_proof: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+
+    // the first swap stays below the hard cap and is filled completely
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(res.attributes.len(), 9);
+    assert_eq!(res.attributes[3], attr("offer_amount", "1500000000"));
+
+    // the second one is filled up to the hard cap and the rest is refunded
+    let env = mock_env_with_block_time(start_time + 200);
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(res.attributes[3], attr("offer_amount", "500000000"));
+    assert_eq!(res.attributes[9], attr("refund_amount", "1000000000"));
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1_000_000_000u128),
+            }],
+        }))
+    );
+
+    assert_eq!(
+        query_hard_cap(deps.as_ref()).unwrap(),
+        HardCapResponse {
+            hard_cap: Some(hard_cap),
+            inflow: Uint128::from(2_000_000_000u128),
+            closed_at: Some(start_time + 200),
+        }
+    );
+
+    // the sale is finished once the hard cap is reached
+    let env = mock_env_with_block_time(start_time + 300);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::SaleEnded {});
+
+    let res = query_simulation(
+        deps.as_ref(),
+        env,
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        None,
+        start_time + 300,
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn cancel_sale() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 100;
+    let end_time = start_time + 1000;
+    let asset_pool_amount = Uint128::from(20_000_000_000u128);
+    let collateral_pool_amount = Uint128::from(30_000_000_000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[
+                (&"addr0000".to_string(), &Uint128::from(24_000_000_000u128)),
+                (&"addr0001".to_string(), &Uint128::from(6_000_000_000u128)),
+            ],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: Some(LiquidityPolicy::Open),
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let info = mock_info("factory0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut());
+
+    // only the sale owner can cancel the sale
+    let info = mock_info("addr0001", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::CancelSale {}).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    // the sale can not be cancelled once it has started
+    let info = mock_info("addr0000", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(start_time),
+        info.clone(),
+        ExecuteMsg::CancelSale {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::SaleStarted {});
+
+    // liquidity of other providers is not taken
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::CancelSale {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::OtherProvidersLiquidity {});
+
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&"addr0000".to_string(), &Uint128::from(30_000_000_000u128))],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+        ),
+    ]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::CancelSale {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "cancel_sale"),
+            attr("withdrawn_share", "30000000000"),
+            attr("refund_assets", "30000000000uusd, 20000000000asset0000"),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("liquidity0000"),
+                msg: to_binary(&Cw20ExecuteMsg::BurnFrom {
+                    owner: String::from("addr0000"),
+                    amount: Uint128::from(30_000_000_000u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(BankMsg::Send {
+                to_address: String::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: collateral_pool_amount,
+                }],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0000"),
+                    amount: asset_pool_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("factory0000"),
+                msg: to_binary(&FactoryExecuteMsg::Unregister {
+                    asset_infos: vec![
+                        AssetInfo::NativeToken {
+                            denom: "uusd".to_string(),
+                        },
+                        AssetInfo::Token {
+                            contract_addr: Addr::unchecked("asset0000"),
+                        },
+                    ],
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+
+    let pair_info = query_pair_info(deps.as_ref()).unwrap();
+    assert!(pair_info.cancelled);
+
+    // a cancelled sale can not be cancelled again, traded or provided with liquidity
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::CancelSale {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::SaleCancelled {});
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::from(1_000_000u128),
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        merkle_proof: None,
+    };
+    let info = mock_info(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1_000_000u128),
+        }],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(start_time + 100),
+        info,
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::SaleCancelled {});
+}
+
+#[test]
+fn multi_asset_pool() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 100;
+    let end_time = start_time + 1000;
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(1_000_000_000u128),
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let weighted_asset_info = |info: AssetInfo| WeightedAssetInfo {
+        info,
+        start_weight: Uint128::from(1u128),
+        end_weight: Uint128::from(1u128),
+        curve: None,
+    };
+    let token = |contract_addr: &str| AssetInfo::Token {
+        contract_addr: Addr::unchecked(contract_addr),
+    };
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+
+    let mut msg = InstantiateMsg {
+        asset_infos: vec![weighted_asset_info(uusd.clone())],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: Some(LiquidityPolicy::Open),
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    // a pair must have from 2 to 8 assets
+    let info = mock_info("factory0000", &[]);
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::InvalidAssetCount { min: 2, max: 8 });
+
+    msg.asset_infos = (0..9)
+        .map(|i| weighted_asset_info(token(&format!("asset000{}", i))))
+        .collect();
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::InvalidAssetCount { min: 2, max: 8 });
+
+    // assets must be unique
+    msg.asset_infos = vec![
+        weighted_asset_info(uusd.clone()),
+        weighted_asset_info(token("asset0000")),
+        weighted_asset_info(uusd.clone()),
+    ];
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::DuplicateAssets {});
+
+    // swap direction is only restricted in two asset pairs
+    msg.asset_infos = vec![
+        weighted_asset_info(uusd.clone()),
+        weighted_asset_info(token("asset0000")),
+        weighted_asset_info(token("asset0001")),
+    ];
+    msg.swap_direction = Some(SwapDirection::Asset0ToAsset1);
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::TwoAssetPairsOnly {});
+
+    msg.swap_direction = None;
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut());
+
+    deps.querier.with_token_balances(&[
+        (&"liquidity0000".to_string(), &[]),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::zero())],
+        ),
+        (
+            &"asset0001".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::zero())],
+        ),
+    ]);
+
+    // the initial share is the geometric mean of the deposits
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![
+            Asset {
+                info: uusd.clone(),
+                amount: Uint128::from(1_000_000_000u128),
+            },
+            Asset {
+                info: token("asset0000"),
+                amount: Uint128::from(8_000_000_000u128),
+            },
+            Asset {
+                info: token("asset0001"),
+                amount: Uint128::from(27_000_000_000u128),
+            },
+        ],
+        slippage_tolerance: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1_000_000_000u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages.last().unwrap(),
+        &SubMsg::new(WasmMsg::Execute {
+            contract_addr: String::from("liquidity0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: String::from("addr0000"),
+                amount: Uint128::from(6_000_000_000u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1_001_000_000u128),
+        }],
+    )]);
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&"addr0000".to_string(), &Uint128::from(6_000_000_000u128))],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(8_000_000_000u128),
+            )],
+        ),
+        (
+            &"asset0001".to_string(),
+            &[(
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(27_000_000_000u128),
+            )],
+        ),
+    ]);
+
+    // the ask asset must be given in pairs of more than two assets
+    let offer_asset = Asset {
+        info: uusd.clone(),
+        amount: Uint128::from(1_000_000u128),
+    };
+    let mut msg = ExecuteMsg::Swap {
+        offer_asset: offer_asset.clone(),
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        merkle_proof: None,
+    };
+    let info = mock_info(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1_000_000u128),
+        }],
+    );
+    let env = mock_env_with_block_time(start_time + 100);
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::OtherAssetRequired {});
+
+    if let ExecuteMsg::Swap { ask_asset_info, .. } = &mut msg {
+        *ask_asset_info = Some(token("asset0002"));
+    }
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::AssetMismatch {});
+
+    if let ExecuteMsg::Swap { ask_asset_info, .. } = &mut msg {
+        *ask_asset_info = Some(token("asset0001"));
+    }
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // the simulation is made against the balances before the offer is received
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1_000_000_000u128),
+        }],
+    )]);
+    let simulation_res: SimulationResponse = query_simulation(
+        deps.as_ref(),
+        env.clone(),
+        offer_asset,
+        Some(token("asset0001")),
+        env.block.time.seconds(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.attributes[..4],
+        vec![
+            attr("action", "swap"),
+            attr("offer_asset", "uusd"),
+            attr("ask_asset", "asset0001"),
+            attr("offer_amount", "1000000"),
+        ]
+    );
+    assert_eq!(
+        res.attributes[4],
+        attr("return_amount", simulation_res.return_amount.to_string())
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: String::from("asset0001"),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("addr0001"),
+                amount: simulation_res.return_amount,
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+
+    // the stats are only recorded for the swapped assets
+    let stats = query_stats(deps.as_ref()).unwrap();
+    assert_eq!(stats.assets[0].volume_in, Uint128::from(1_000_000u128));
+    assert_eq!(stats.assets[1].volume_out, Uint128::zero());
+    assert_eq!(stats.assets[2].volume_out, simulation_res.return_amount);
+
+    // the price oracle is only available in two asset pairs
+    let records = PRICE_CUMULATIVES
+        .range(&deps.storage, None, None, Order::Ascending)
+        .count();
+    assert_eq!(records, 0);
+
+    let res = query_cumulative_prices(deps.as_ref(), env.clone()).unwrap_err();
+    assert_eq!(
+        res,
+        StdError::generic_err("The operation is only available in two asset pairs")
+    );
+
+    let res = query_twap(deps.as_ref(), env, start_time, start_time + 100).unwrap_err();
+    assert_eq!(
+        res,
+        StdError::generic_err("The operation is only available in two asset pairs")
+    );
+}
+
+#[test]
+fn single_asset_liquidity() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 100;
+    let end_time = start_time + 1000;
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(1_010_000_000u128),
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&"addr0000".to_string(), &Uint128::from(1_000_000_000u128))],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(1_000_000_000u128),
+            )],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: Some(LiquidityPolicy::Open),
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let info = mock_info("factory0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut());
+
+    let msg = ExecuteMsg::ProvideSingleLiquidity {
+        asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::from(10_000_000u128),
+        },
+        min_share: Some(Uint128::from(5_000_000u128)),
+    };
+    let info = mock_info(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10_000_000u128),
+        }],
+    );
+
+    // single asset liquidity can not be provided while the sale is running
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(start_time + 100),
+        info.clone(),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::SaleRunning {});
+
+    // the deposit of a half of the pool value is charged the fee on its other half
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::MinShareNotReached {
+            share: Uint128::from(4_983_830u128)
+        }
+    );
+
+    let msg = ExecuteMsg::ProvideSingleLiquidity {
+        asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::from(10_000_000u128),
+        },
+        min_share: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "provide_single_liquidity"),
+            attr("asset", "10000000uusd"),
+            attr("share", "4983830"),
+            attr("fee_amount", "7500"),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: String::from("liquidity0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: String::from("addr0001"),
+                amount: Uint128::from(4_983_830u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1_000_000_000u128),
+        }],
+    )]);
+    let res = query_provide_single_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::from(10_000_000u128),
+        },
+        mock_env().block.time.seconds(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        ProvideSingleSimulationResponse {
+            share: Uint128::from(4_983_830u128),
+            fee_amount: Uint128::from(7_500u128),
+        }
+    );
+
+    // a single asset deposit is limited by max_in_ratio
+    let res = query_provide_single_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::from(300_000_001u128),
+        },
+        mock_env().block.time.seconds(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        res,
+        StdError::generic_err("Offer amount exceeds 0.3 of the offer pool")
+    );
+
+    // only the liquidity token can withdraw liquidity
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0001"),
+        amount: Uint128::from(5_000_000u128),
+        msg: to_binary(&Cw20HookMsg::WithdrawSingleLiquidity {
+            asset_info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            min_return: None,
+        })
+        .unwrap(),
+    });
+    let info = mock_info("asset0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    let info = mock_info("liquidity0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "withdraw_single_liquidity"),
+            attr("withdrawn_share", "5000000"),
+            attr("refund_asset", "9967518asset0000"),
+            attr("fee_amount", "7481"),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0001"),
+                    amount: Uint128::from(9_967_518u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("liquidity0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::from(5_000_000u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+
+    let res = query_withdraw_single_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Uint128::from(5_000_000u128),
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        mock_env().block.time.seconds(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        WithdrawSingleSimulationResponse {
+            return_amount: Uint128::from(9_967_518u128),
+            fee_amount: Uint128::from(7_481u128),
+        }
+    );
+
+    // a single asset withdrawal is limited by max_out_ratio
+    let res = query_withdraw_single_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Uint128::from(500_000_000u128),
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        mock_env().block.time.seconds(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        res,
+        StdError::generic_err("Return amount exceeds 0.3 of the ask pool")
+    );
+
+    // under the sale owner policy only the sale owner provides and only before the sale starts
+    PAIR_INFO
+        .update(deps.as_mut().storage, |mut pair_info| -> StdResult<_> {
+            pair_info.liquidity_policy = LiquidityPolicy::SaleOwner;
+            Ok(pair_info)
+        })
+        .unwrap();
+    let msg = ExecuteMsg::ProvideSingleLiquidity {
+        asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::from(10_000_000u128),
         },
-        belief_price: None,
-        max_spread: None,
-        to: None,
+        min_share: None,
     };
-    let env = mock_env_with_block_time(env.block.time.seconds() + start_time);
-    let info = mock_info("addr0000", &[]);
-    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
-    match res {
-        ContractError::Unauthorized { .. } => (),
-        _ => panic!("DO NOT ENTER HERE"),
-    }
+    let info = mock_info(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10_000_000u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
 
-    // normal sell
-    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
-        sender: "addr0000".to_string(),
-        amount: offer_amount,
-        msg: to_binary(&Cw20HookMsg::Swap {
-            belief_price: None,
-            max_spread: None,
-            to: None,
-        })
-        .unwrap(),
-    });
-    let env = mock_env_with_block_time(start_time);
-    let info = mock_info("asset0000", &[]);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10_000_000u128),
+        }],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(end_time + 1),
+        info,
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::SaleStarted {});
+}
 
-    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
-    let msg_transfer = res.messages.get(0).expect("no message");
+#[test]
+fn swap_exact_out() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 100;
+    let end_time = start_time + 1000;
+    let pool_amount = Uint128::from(1_000_000_000u128);
+    let max_offer_amount = Uint128::from(20_000_000u128);
+    let ask_amount = Uint128::from(10_000_000u128);
 
-    // current price is 1.5, so expected return without spread is 1000
-    // 952.380953 = 20000 - 20000 * 30000 / (30000 + 1500)
-    let expected_ret_amount = Uint128::from(952_380_953u128);
-    let expected_spread_amount = (offer_amount * exchange_rate)
-        .checked_sub(expected_ret_amount)
-        .unwrap();
-    let expected_commission_amount =
-        expected_ret_amount.multiply_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO); // 0.15%
-    let expected_return_amount = expected_ret_amount
-        .checked_sub(expected_commission_amount)
-        .unwrap();
-    let expected_tax_amount = std::cmp::min(
-        Uint128::from(1000000u128),
-        expected_return_amount
-            .checked_sub(
-                expected_return_amount
-                    .multiply_ratio(Uint128::from(100u128), Uint128::from(101u128)),
-            )
-            .unwrap(),
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: pool_amount,
+    }]);
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
     );
-    // check simulation res
-    // return asset token balance as normal
     deps.querier.with_token_balances(&[
         (
             &"liquidity0000".to_string(),
-            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
+            &[(&"addr0000".to_string(), &pool_amount)],
         ),
         (
             &"asset0000".to_string(),
-            &[(&MOCK_CONTRACT_ADDR.to_string(), &(asset_pool_amount))],
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &pool_amount)],
         ),
     ]);
 
-    let simulation_res: SimulationResponse = query_simulation(
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let info = mock_info("factory0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    store_liquidity_token(deps.as_mut());
+
+    let env = mock_env_with_block_time(start_time + 100);
+    let simulation_res: ReverseSimulationResponse = query_reverse_simulation(
         deps.as_ref(),
         env.clone(),
         Asset {
-            amount: offer_amount,
             info: AssetInfo::Token {
                 contract_addr: Addr::unchecked("asset0000"),
             },
+            amount: ask_amount,
         },
-        start_time,
+        None,
+        env.block.time.seconds(),
     )
     .unwrap();
+    let required_amount = simulation_res.offer_amount;
 
-    let ret_diff =
-        (expected_return_amount.u128() as i128 - simulation_res.return_amount.u128() as i128).abs();
-    let commission_diff = (expected_commission_amount.u128() as i128
-        - simulation_res.commission_amount.u128() as i128)
-        .abs();
-    let spread_diff =
-        (expected_spread_amount.u128() as i128 - simulation_res.spread_amount.u128() as i128).abs();
-
-    let diff_tolerance = 10i128;
-
-    assert_eq!(ret_diff < diff_tolerance, true);
-    assert_eq!(commission_diff < diff_tolerance, true);
-    assert_eq!(spread_diff < diff_tolerance, true);
-
-    // check reverse simulation res
-    let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
-        deps.as_ref(),
-        env,
-        Asset {
-            amount: expected_return_amount,
+    // tokens can only be offered with the cw20 hook
+    let msg = ExecuteMsg::SwapExactOut {
+        ask_asset: Asset {
             info: AssetInfo::NativeToken {
                 denom: "uusd".to_string(),
             },
+            amount: ask_amount,
         },
-        start_time,
-    )
-    .unwrap();
-
-    let offer_diff =
-        (offer_amount.u128() as i128 - reverse_simulation_res.offer_amount.u128() as i128).abs();
-    let commission_diff = (expected_commission_amount.u128() as i128
-        - reverse_simulation_res.commission_amount.u128() as i128)
-        .abs();
-    let spread_diff = (expected_spread_amount.u128() as i128
-        - reverse_simulation_res.spread_amount.u128() as i128)
-        .abs();
+        offer_asset_info: None,
+        max_offer_amount,
+        to: None,
+        merkle_proof: None,
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
 
-    let diff_tolerance = 5i128;
+    // the swap fails if the required offer amount exceeds the maximum
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: pool_amount + ask_amount,
+        }],
+    )]);
+    let msg = ExecuteMsg::SwapExactOut {
+        ask_asset: Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: ask_amount,
+        },
+        offer_asset_info: None,
+        max_offer_amount: ask_amount,
+        to: None,
+        merkle_proof: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: ask_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::MaxOfferAmountExceeded { required_amount }
+    );
 
-    assert_eq!(offer_diff < diff_tolerance, true);
-    assert_eq!(commission_diff < diff_tolerance, true);
-    assert_eq!(spread_diff < diff_tolerance, true);
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: pool_amount + max_offer_amount,
+        }],
+    )]);
 
+    let msg = ExecuteMsg::SwapExactOut {
+        ask_asset: Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: ask_amount,
+        },
+        offer_asset_info: None,
+        max_offer_amount,
+        to: None,
+        merkle_proof: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: max_offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let refund_amount = max_offer_amount - required_amount;
     assert_eq!(
         res.attributes,
         vec![
             attr("action", "swap"),
-            attr("offer_asset", "asset0000"),
-            attr("ask_asset", "uusd"),
-            attr("offer_amount", offer_amount.to_string()),
-            attr("return_amount", simulation_res.return_amount.to_string()),
-            attr("tax_amount", expected_tax_amount.to_string()),
+            attr("offer_asset", "uusd"),
+            attr("ask_asset", "asset0000"),
+            attr("offer_amount", required_amount.to_string()),
+            attr("return_amount", ask_amount.to_string()),
+            attr("tax_amount", "0"),
             attr("spread_amount", simulation_res.spread_amount.to_string()),
-            attr("commission_amount", expected_commission_amount.to_string()),
+            attr(
+                "commission_amount",
+                simulation_res.commission_amount.to_string()
+            ),
+            attr("protocol_fee_amount", "0"),
+            attr("refund_amount", refund_amount.to_string()),
         ]
     );
-
     assert_eq!(
-        &SubMsg {
-            id: 0,
-            msg: BankMsg::Send {
-                to_address: "addr0000".to_string(),
+        res.messages,
+        vec![
+            SubMsg::new(BankMsg::Send {
+                to_address: String::from("addr0000"),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: simulation_res
-                        .return_amount
-                        .checked_sub(expected_tax_amount)
-                        .unwrap(),
+                    amount: refund_amount,
                 }],
-            }
-            .into(),
-            gas_limit: None,
-            reply_on: ReplyOn::Never
-        },
-        msg_transfer,
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0000"),
+                    amount: ask_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
     );
 
-    // failed due to non asset token contract try to execute sell
+    // tokens are swapped with the cw20 hook, the sent amount is the maximum offer amount
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: pool_amount,
+        }],
+    )]);
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&"addr0000".to_string(), &pool_amount)],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &(pool_amount + max_offer_amount),
+            )],
+        ),
+    ]);
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
-        sender: "addr0000".to_string(),
-        amount: offer_amount,
-        msg: to_binary(&Cw20HookMsg::Swap {
-            belief_price: None,
-            max_spread: None,
+        sender: String::from("addr0001"),
+        amount: max_offer_amount,
+        msg: to_binary(&Cw20HookMsg::SwapExactOut {
+            ask_asset: Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: ask_amount,
+            },
             to: None,
+            merkle_proof: None,
         })
         .unwrap(),
-    });
-    let env = mock_env_with_block_time(start_time);
-    let info = mock_info("liquidtity0000", &[]);
-    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
-    match res {
-        ContractError::Unauthorized { .. } => (),
-        _ => panic!("DO NOT ENTER HERE"),
-    }
+    });
+
+    let info = mock_info("asset0001", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    let info = mock_info("asset0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.attributes[9],
+        attr("refund_amount", refund_amount.to_string())
+    );
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0001"),
+                    amount: refund_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(BankMsg::Send {
+                to_address: String::from("addr0001"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: ask_amount,
+                }],
+            }),
+        ]
+    );
 }
 
 #[test]
@@ -1223,44 +5521,319 @@ fn test_spread() {
         .as_secs();
     let end_time = start_time + 1000;
 
-    let mut deps = mock_dependencies(&[]);
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &tkn_contract.to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &tkn_amount)],
+        ),
+        (
+            &usdc_contract.to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &usdc_amount)],
+        ),
+        (
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::zero())],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: tkn_contract.clone(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: usdc_contract.clone(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // store liquidity token
+    store_liquidity_token(deps.as_mut());
+
+    // successfully provide liquidity for the exist pool
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: tkn_contract.clone(),
+                },
+                amount: tkn_amount.clone(),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: usdc_contract.clone(),
+                },
+                amount: usdc_amount.clone(),
+            },
+        ],
+        slippage_tolerance: None,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // Check balances
+    let res: PoolResponse = query_pool(deps.as_ref(), env.clone()).unwrap();
+    assert_eq!(
+        res.assets[0].info,
+        AssetInfo::Token {
+            contract_addr: tkn_contract.clone()
+        }
+    );
+    assert_eq!(res.assets[0].amount, tkn_amount);
+
+    assert_eq!(
+        res.assets[1].info,
+        AssetInfo::Token {
+            contract_addr: usdc_contract.clone()
+        }
+    );
+    assert_eq!(res.assets[1].amount, usdc_amount);
+
+    let simulation_res: SimulationResponse = query_simulation(
+        deps.as_ref(),
+        env,
+        Asset {
+            amount: Uint128::from(10_u128 * DECIMAL_FRACTIONAL.u128()),
+            info: AssetInfo::Token {
+                contract_addr: usdc_contract.clone(),
+            },
+        },
+        None,
+        start_time,
+    )
+    .unwrap();
+
+    // Spot price: (ask_pool / ask_weight) / (offer_pool / offer_weight) * offer_amount
+    // (50000000 / 1) / ( 250000 / 1) * 10 * DECIMAL_PRECISION  = 2000000000000
+    let spot_price = Uint128::new(2000000000000);
+
+    // return_amount: ask_pool * (1 - (offer_pool / (offer_pool + offer_amount)) ^ (offer_weight / ask_weight))
+    // 50000000000000000 * (1 - (250000000000000 / 250010000000000)) = 1999920003199
+    let return_amount = Uint128::new(1999920003199);
+    let commission_amount: Uint128 =
+        return_amount * Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO);
+    let return_amount_without_commission = return_amount - commission_amount;
+
+    assert_eq!(
+        simulation_res.return_amount,
+        return_amount_without_commission
+    );
+
+    assert_eq!(simulation_res.spread_amount, spot_price - return_amount);
+}
+
+#[test]
+fn test_deduct() {
+    let mut deps = mock_dependencies(&[]);
+
+    let tax_rate = Decimal::percent(2);
+    let tax_cap = Uint128::from(1_000_000u128);
+    deps.querier.with_tax(
+        Decimal::percent(2),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let amount = Uint128::from(1000_000_000u128);
+    let expected_after_amount = std::cmp::max(
+        amount.checked_sub(amount * tax_rate).unwrap(),
+        amount.checked_sub(tax_cap).unwrap(),
+    );
+
+    let after_amount = (Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount,
+    })
+    .deduct_tax(deps.as_ref())
+    .unwrap();
+
+    assert_eq!(expected_after_amount, after_amount.amount);
+}
+
+#[test]
+fn test_query_pool() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let end_time = start_time + 1000;
+    let total_share_amount = Uint128::from(111u128);
+    let asset_0_amount = Uint128::from(222u128);
+    let asset_1_amount = Uint128::from(333u128);
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: asset_0_amount,
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_1_amount)],
+        ),
+        (
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+        ],
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // store liquidity token
+    store_liquidity_token(deps.as_mut());
+
+    let res: PoolResponse = query_pool(deps.as_ref(), env).unwrap();
+    assert_eq!(
+        res.assets,
+        [
+            WeightedAsset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: asset_0_amount,
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            },
+            WeightedAsset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: asset_1_amount,
+                start_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(1u128),
+                curve: None,
+            }
+        ]
+    );
+    assert_eq!(res.total_share, total_share_amount);
+}
+
+#[test]
+fn test_weight_calculations() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let end_time = start_time + 100;
+    let total_share = Uint128::from(50_000_000_____000_000_000u128);
+    let asset_pool_amount = Uint128::from(250_000_____000_000_000u128);
+    let collateral_pool_amount = total_share.clone();
+
+    let offer_amount = Uint128::from(1_000____000_000_000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount, /* user deposit must be pre-applied */
+    }]);
+
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
 
     deps.querier.with_token_balances(&[
         (
-            &tkn_contract.to_string(),
-            &[(&MOCK_CONTRACT_ADDR.to_string(), &tkn_amount)],
-        ),
-        (
-            &usdc_contract.to_string(),
-            &[(&MOCK_CONTRACT_ADDR.to_string(), &usdc_amount)],
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
         ),
         (
-            &"liquidity0000".to_string(),
-            &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::zero())],
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
         ),
     ]);
 
     let msg = InstantiateMsg {
-        asset_infos: [
+        asset_infos: vec![
             WeightedAssetInfo {
-                info: AssetInfo::Token {
-                    contract_addr: tkn_contract.clone(),
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
                 },
                 start_weight: Uint128::from(1u128),
-                end_weight: Uint128::from(1u128),
+                end_weight: Uint128::from(30u128),
+                curve: None,
             },
             WeightedAssetInfo {
                 info: AssetInfo::Token {
-                    contract_addr: usdc_contract.clone(),
+                    contract_addr: Addr::unchecked("asset0000"),
                 },
-                start_weight: Uint128::from(1u128),
-                end_weight: Uint128::from(1u128),
+                start_weight: Uint128::from(49u128),
+                end_weight: Uint128::from(20u128),
+                curve: None,
             },
         ],
         token_code_id: 10u64,
         start_time,
         end_time,
         description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
     };
 
     let env = mock_env();
@@ -1268,207 +5841,420 @@ fn test_spread() {
     // we can just call .unwrap() to assert this was a success
     let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-    // store liquidity token
-    store_liquidity_token(deps.as_mut());
+    // check simulation res
+    struct TestCase {
+        expected_error: bool,
+        start_time: u64,
+        expected_ask_weight: String,
+        expected_offer_weight: String,
+    }
 
-    // successfully provide liquidity for the exist pool
-    let msg = ExecuteMsg::ProvideLiquidity {
-        assets: [
+    let mut test_cases: Vec<TestCase> = Vec::new();
+    test_cases.push(TestCase {
+        expected_error: true,
+        start_time: start_time - 1,
+        expected_ask_weight: Default::default(),
+        expected_offer_weight: Default::default(),
+    });
+
+    test_cases.push(TestCase {
+        expected_error: true,
+        start_time: end_time + 1,
+        expected_ask_weight: Default::default(),
+        expected_offer_weight: Default::default(),
+    });
+
+    test_cases.push(TestCase {
+        expected_error: false,
+        start_time: start_time,
+        expected_ask_weight: String::from("49"),
+        expected_offer_weight: String::from("1"),
+    });
+    test_cases.push(TestCase {
+        expected_error: false,
+        start_time: start_time + 50,
+        expected_ask_weight: String::from("34.5"),
+        expected_offer_weight: String::from("15.5"),
+    });
+    test_cases.push(TestCase {
+        expected_error: false,
+        start_time: start_time + 100,
+        expected_ask_weight: String::from("20"),
+        expected_offer_weight: String::from("30"),
+    });
+
+    for t in &test_cases {
+        let simulation_res = query_simulation(
+            deps.as_ref(),
+            env.clone(),
             Asset {
-                info: AssetInfo::Token {
-                    contract_addr: tkn_contract.clone(),
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
                 },
-                amount: tkn_amount.clone(),
+                amount: offer_amount,
             },
-            Asset {
+            None,
+            t.start_time,
+        );
+
+        let simulation_res = simulation_res.unwrap_or_else(|e| {
+            if !t.expected_error {
+                panic!("{:?}", e);
+            }
+
+            SimulationResponse {
+                return_amount: Default::default(),
+                spread_amount: Default::default(),
+                commission_amount: Default::default(),
+                ask_weight: Default::default(),
+                offer_weight: Default::default(),
+                swap_fee: Default::default(),
+                protocol_fee_amount: Default::default(),
+            }
+        });
+
+        if !t.expected_error {
+            assert_eq!(simulation_res.ask_weight.as_str(), &t.expected_ask_weight);
+            assert_eq!(
+                simulation_res.offer_weight.as_str(),
+                &t.expected_offer_weight
+            );
+        }
+    }
+}
+
+#[test]
+fn test_large_weights() {
+    let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    // a sale of 10 years
+    let end_time = start_time + 315_360_000;
+    let total_share = Uint128::from(50_000_000_000_000_000u128);
+    let asset_pool_amount = Uint128::from(250_000_000_000_000u128);
+    let collateral_pool_amount = total_share;
+
+    let offer_amount = Uint128::from(1_000_000_000_000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount, /* user deposit must be pre-applied */
+    }]);
+
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            WeightedAssetInfo {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                start_weight: Uint128::from(100_000_000_000_000_000_000_000_000_000_000u128),
+                end_weight: Uint128::from(200_000_000_000_000_000_000_000_000_000_000u128),
+                curve: None,
+            },
+            WeightedAssetInfo {
                 info: AssetInfo::Token {
-                    contract_addr: usdc_contract.clone(),
+                    contract_addr: Addr::unchecked("asset0000"),
                 },
-                amount: usdc_amount.clone(),
+                start_weight: Uint128::from(200_000_000_000_000_000_000_000_000_000_000u128),
+                end_weight: Uint128::from(100_000_000_000_000_000_000_000_000_000_000u128),
+                curve: None,
             },
         ],
-        slippage_tolerance: None,
+        token_code_id: 10u64,
+        start_time,
+        end_time,
+        description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
     };
 
+    let env = mock_env();
     let info = mock_info("addr0000", &[]);
-    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
-
-    // Check balances
-    let res: PoolResponse = query_pool(deps.as_ref(), env.clone()).unwrap();
-    assert_eq!(
-        res.assets[0].info,
-        AssetInfo::Token {
-            contract_addr: tkn_contract.clone()
-        }
-    );
-    assert_eq!(res.assets[0].amount, tkn_amount);
-
-    assert_eq!(
-        res.assets[1].info,
-        AssetInfo::Token {
-            contract_addr: usdc_contract.clone()
-        }
-    );
-    assert_eq!(res.assets[1].amount, usdc_amount);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-    let simulation_res: SimulationResponse = query_simulation(
+    // the weight change multiplied by the elapsed time exceeds u128
+    let simulation_res = query_simulation(
         deps.as_ref(),
         env,
         Asset {
-            amount: Uint128::from(10_u128 * DECIMAL_FRACTIONAL.u128()),
-            info: AssetInfo::Token {
-                contract_addr: usdc_contract.clone(),
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
             },
+            amount: offer_amount,
         },
-        start_time,
+        None,
+        start_time + 157_680_000,
     )
     .unwrap();
-
-    // Spot price: (ask_pool / ask_weight) / (offer_pool / offer_weight) * offer_amount
-    // (50000000 / 1) / ( 250000 / 1) * 10 * DECIMAL_PRECISION  = 2000000000000
-    let spot_price = Uint128::new(2000000000000);
-
-    // return_amount: ask_pool * (1 - (offer_pool / (offer_pool + offer_amount)) ^ (offer_weight / ask_weight))
-    // 50000000000000000 * (1 - (250000000000000 / 250010000000000)) = 1999920003199
-    let return_amount = Uint128::new(1999920003199);
-    let commission_amount: Uint128 = return_amount * Decimal::from_str(COMMISSION_RATE).unwrap();
-    let return_amount_without_commission = return_amount - commission_amount;
-
     assert_eq!(
-        simulation_res.return_amount,
-        return_amount_without_commission
-    );
-
-    assert_eq!(simulation_res.spread_amount, spot_price - return_amount);
-}
-
-#[test]
-fn test_deduct() {
-    let mut deps = mock_dependencies(&[]);
-
-    let tax_rate = Decimal::percent(2);
-    let tax_cap = Uint128::from(1_000_000u128);
-    deps.querier.with_tax(
-        Decimal::percent(2),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+        simulation_res.offer_weight,
+        "150000000000000000000000000000000"
     );
-
-    let amount = Uint128::from(1000_000_000u128);
-    let expected_after_amount = std::cmp::max(
-        amount.checked_sub(amount * tax_rate).unwrap(),
-        amount.checked_sub(tax_cap).unwrap(),
+    assert_eq!(
+        simulation_res.ask_weight,
+        "150000000000000000000000000000000"
     );
-
-    let after_amount = (Asset {
-        info: AssetInfo::NativeToken {
-            denom: "uusd".to_string(),
-        },
-        amount,
-    })
-    .deduct_tax(deps.as_ref())
-    .unwrap();
-
-    assert_eq!(expected_after_amount, after_amount.amount);
 }
 
 #[test]
-fn test_query_pool() {
+fn test_weight_curves() {
     let start_time = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
-    let end_time = start_time + 1000;
-    let total_share_amount = Uint128::from(111u128);
-    let asset_0_amount = Uint128::from(222u128);
-    let asset_1_amount = Uint128::from(333u128);
+    let end_time = start_time + 100;
+    let total_share = Uint128::from(50_000_000_000_000_000u128);
+    let asset_pool_amount = Uint128::from(250_000_000_000_000u128);
+    let collateral_pool_amount = total_share;
+
+    let offer_amount = Uint128::from(1_000_000_000_000u128);
+
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
-        amount: asset_0_amount,
+        amount: collateral_pool_amount + offer_amount,
     }]);
 
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
     deps.querier.with_token_balances(&[
-        (
-            &"asset0000".to_string(),
-            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_1_amount)],
-        ),
         (
             &"liquidity0000".to_string(),
-            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share_amount)],
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
         ),
     ]);
 
-    let msg = InstantiateMsg {
-        asset_infos: [
+    let instantiate_msg = |offer_curve: WeightCurve, ask_curve: WeightCurve| InstantiateMsg {
+        asset_infos: vec![
             WeightedAssetInfo {
                 info: AssetInfo::NativeToken {
                     denom: "uusd".to_string(),
                 },
-                start_weight: Uint128::from(1u128),
-                end_weight: Uint128::from(1u128),
+                start_weight: Uint128::from(2u128),
+                end_weight: Uint128::from(30u128),
+                curve: Some(offer_curve),
             },
             WeightedAssetInfo {
                 info: AssetInfo::Token {
                     contract_addr: Addr::unchecked("asset0000"),
                 },
-                start_weight: Uint128::from(1u128),
-                end_weight: Uint128::from(1u128),
+                start_weight: Uint128::from(49u128),
+                end_weight: Uint128::from(20u128),
+                curve: Some(ask_curve),
             },
         ],
         token_code_id: 10u64,
         start_time,
         end_time,
         description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
     };
 
     let env = mock_env();
     let info = mock_info("addr0000", &[]);
-    // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-    // store liquidity token
-    store_liquidity_token(deps.as_mut());
+    // Invalid curve parameters
+    let invalid_curves = [
+        (
+            WeightCurve::Exponential { half_life: 0 },
+            ContractError::InvalidHalfLife {},
+        ),
+        (
+            WeightCurve::Exponential { half_life: 101 },
+            ContractError::InvalidHalfLife {},
+        ),
+        (
+            WeightCurve::Stepwise { steps: 0 },
+            ContractError::InvalidSteps {},
+        ),
+        (
+            WeightCurve::Stepwise { steps: 101 },
+            ContractError::InvalidSteps {},
+        ),
+        (
+            WeightCurve::Piecewise {
+                points: vec![
+                    (start_time + 60, Uint128::from(10u128)),
+                    (start_time + 20, Uint128::from(40u128)),
+                ],
+            },
+            ContractError::InvalidCurvePoints {},
+        ),
+        (
+            WeightCurve::Piecewise {
+                points: vec![(end_time, Uint128::from(10u128))],
+            },
+            ContractError::InvalidCurvePoints {},
+        ),
+        (
+            WeightCurve::Piecewise {
+                points: vec![(start_time + 20, Uint128::zero())],
+            },
+            ContractError::ZeroWeight {},
+        ),
+    ];
 
-    let res: PoolResponse = query_pool(deps.as_ref(), env).unwrap();
-    assert_eq!(
-        res.assets,
-        [
-            WeightedAsset {
-                info: AssetInfo::NativeToken {
-                    denom: "uusd".to_string(),
-                },
-                amount: asset_0_amount,
-                start_weight: Uint128::from(1u128),
-                end_weight: Uint128::from(1u128),
+    for (curve, expected) in invalid_curves {
+        let res = instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            instantiate_msg(WeightCurve::Linear, curve),
+        );
+        assert_eq!(res.unwrap_err(), expected);
+    }
+
+    // Step-wise curve for the offer asset and piecewise-linear curve for the ask asset
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        instantiate_msg(
+            WeightCurve::Stepwise { steps: 4 },
+            WeightCurve::Piecewise {
+                points: vec![
+                    (start_time + 20, Uint128::from(10u128)),
+                    (start_time + 60, Uint128::from(40u128)),
+                ],
             },
-            WeightedAsset {
+        ),
+    )
+    .unwrap();
+
+    let offer_asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: offer_amount,
+    };
+
+    let test_cases = [
+        (start_time, "2", "49"),
+        (start_time + 10, "2", "29.5"),
+        (start_time + 20, "2", "10"),
+        (start_time + 25, "9", "13.75"),
+        (start_time + 40, "9", "25"),
+        (start_time + 80, "23", "30"),
+        (start_time + 100, "30", "20"),
+    ];
+
+    for (block_time, expected_offer_weight, expected_ask_weight) in test_cases {
+        let simulation_res = query_simulation(
+            deps.as_ref(),
+            env.clone(),
+            offer_asset.clone(),
+            None,
+            block_time,
+        )
+        .unwrap();
+        assert_eq!(simulation_res.offer_weight, expected_offer_weight);
+        assert_eq!(simulation_res.ask_weight, expected_ask_weight);
+
+        // Reverse simulation uses the same curves
+        let reverse_simulation_res = query_reverse_simulation(
+            deps.as_ref(),
+            env.clone(),
+            Asset {
                 info: AssetInfo::Token {
                     contract_addr: Addr::unchecked("asset0000"),
                 },
-                amount: asset_1_amount,
-                start_weight: Uint128::from(1u128),
-                end_weight: Uint128::from(1u128),
-            }
-        ]
-    );
-    assert_eq!(res.total_share, total_share_amount);
+                amount: Uint128::from(1_000u128),
+            },
+            None,
+            block_time,
+        )
+        .unwrap();
+        assert_eq!(reverse_simulation_res.offer_weight, expected_offer_weight);
+        assert_eq!(reverse_simulation_res.ask_weight, expected_ask_weight);
+    }
+
+    // Exponential curve, the distance to end_weight halves every 50 seconds
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        instantiate_msg(
+            WeightCurve::Exponential { half_life: 50 },
+            WeightCurve::Linear,
+        ),
+    )
+    .unwrap();
+
+    let simulation_res = query_simulation(
+        deps.as_ref(),
+        env.clone(),
+        offer_asset.clone(),
+        None,
+        start_time,
+    )
+    .unwrap();
+    assert_eq!(simulation_res.offer_weight, "2");
+
+    // 2 + 28 * (1 - 0.5) / (1 - 0.25)
+    let simulation_res = query_simulation(
+        deps.as_ref(),
+        env.clone(),
+        offer_asset.clone(),
+        None,
+        start_time + 50,
+    )
+    .unwrap();
+    let offer_weight = Decimal256::from_str(&simulation_res.offer_weight).unwrap();
+    assert!(offer_weight > Decimal256::from_str("20.6666").unwrap());
+    assert!(offer_weight < Decimal256::from_str("20.6667").unwrap());
+    assert_eq!(simulation_res.ask_weight, "34.5");
+
+    let simulation_res =
+        query_simulation(deps.as_ref(), env, offer_asset, None, start_time + 100).unwrap();
+    assert_eq!(simulation_res.offer_weight, "30");
 }
 
 #[test]
-fn test_weight_calculations() {
+fn update_weight_schedule() {
     let start_time = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
     let end_time = start_time + 100;
-    let total_share = Uint128::from(50_000_000_____000_000_000u128);
-    let asset_pool_amount = Uint128::from(250_000_____000_000_000u128);
-    let collateral_pool_amount = total_share.clone();
-
-    let offer_amount = Uint128::from(1_000____000_000_000u128);
+    let total_share = Uint128::from(50_000_000_000_000_000u128);
+    let asset_pool_amount = Uint128::from(250_000_000_000_000u128);
+    let offer_amount = Uint128::from(1_000_000_000_000u128);
 
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
-        amount: collateral_pool_amount + offer_amount, /* user deposit must be pre-applied */
+        amount: total_share + offer_amount,
     }]);
 
     deps.querier.with_tax(
@@ -1487,111 +6273,199 @@ fn test_weight_calculations() {
         ),
     ]);
 
-    let msg = InstantiateMsg {
-        asset_infos: [
+    let instantiate_msg = |start_weights: [u128; 2], end_weights: [u128; 2]| InstantiateMsg {
+        asset_infos: vec![
             WeightedAssetInfo {
                 info: AssetInfo::NativeToken {
                     denom: "uusd".to_string(),
                 },
-                start_weight: Uint128::from(1u128),
-                end_weight: Uint128::from(30u128),
+                start_weight: Uint128::from(start_weights[0]),
+                end_weight: Uint128::from(end_weights[0]),
+                curve: None,
             },
             WeightedAssetInfo {
                 info: AssetInfo::Token {
                     contract_addr: Addr::unchecked("asset0000"),
                 },
-                start_weight: Uint128::from(49u128),
-                end_weight: Uint128::from(20u128),
+                start_weight: Uint128::from(start_weights[1]),
+                end_weight: Uint128::from(end_weights[1]),
+                curve: None,
             },
         ],
         token_code_id: 10u64,
         start_time,
         end_time,
         description: Some(String::from("description")),
+        owner: String::from("addr0000"),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+        max_in_ratio: None,
+        max_out_ratio: None,
     };
 
-    let env = mock_env();
     let info = mock_info("addr0000", &[]);
-    // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        instantiate_msg([1, 49], [30, 20]),
+    )
+    .unwrap();
 
-    // check simulation res
-    struct TestCase {
-        expected_error: bool,
-        start_time: u64,
-        expected_ask_weight: String,
-        expected_offer_weight: String,
-    }
+    let offer_asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: offer_amount,
+    };
+    let env = mock_env_with_block_time(start_time + 50);
 
-    let mut test_cases: Vec<TestCase> = Vec::new();
-    test_cases.push(TestCase {
-        expected_error: true,
-        start_time: start_time - 1,
-        expected_ask_weight: Default::default(),
-        expected_offer_weight: Default::default(),
-    });
+    let msg = ExecuteMsg::UpdateWeightSchedule {
+        end_weights: vec![Uint128::from(40u128), Uint128::from(10u128)],
+        end_time: start_time + 150,
+    };
 
-    test_cases.push(TestCase {
-        expected_error: true,
-        start_time: end_time + 1,
-        expected_ask_weight: Default::default(),
-        expected_offer_weight: Default::default(),
-    });
+    // only the sale owner can update the schedule
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0001", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
 
-    test_cases.push(TestCase {
-        expected_error: false,
-        start_time: start_time,
-        expected_ask_weight: String::from("49"),
-        expected_offer_weight: String::from("1"),
-    });
-    test_cases.push(TestCase {
-        expected_error: false,
-        start_time: start_time + 50,
-        expected_ask_weight: String::from("34.5"),
-        expected_offer_weight: String::from("15.5"),
-    });
-    test_cases.push(TestCase {
-        expected_error: false,
-        start_time: start_time + 100,
-        expected_ask_weight: String::from("20"),
-        expected_offer_weight: String::from("30"),
-    });
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::UpdateWeightSchedule {
+            end_weights: vec![Uint128::from(40u128), Uint128::zero()],
+            end_time: start_time + 150,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::ZeroWeight {});
 
-    for t in &test_cases {
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::UpdateWeightSchedule {
+            end_weights: vec![Uint128::from(40u128), Uint128::from(10u128)],
+            end_time: start_time + 50,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::InvalidEndTime {});
+
+    // the scaled weights must fit into Uint128
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::UpdateWeightSchedule {
+            end_weights: vec![Uint128::from(u128::MAX / 2), Uint128::from(10u128)],
+            end_time: start_time + 150,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::WeightsTooLarge {});
+
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_weight_schedule"),
+            attr("start_time", (start_time + 50).to_string()),
+            attr("end_time", (start_time + 150).to_string()),
+            attr("start_weights", "15500000, 34500000"),
+            attr("end_weights", "40000000, 10000000"),
+        ]
+    );
+    assert_eq!(
+        res.events,
+        vec![Event::new("lbp_weights_updated").add_attributes(vec![
+            attr("start_time", (start_time + 50).to_string()),
+            attr("end_time", (start_time + 150).to_string()),
+            attr("asset_0", "uusd"),
+            attr("start_weight_0", "15500000"),
+            attr("end_weight_0", "40000000"),
+            attr("asset_1", "asset0000"),
+            attr("start_weight_1", "34500000"),
+            attr("end_weight_1", "10000000"),
+        ])]
+    );
+
+    // the weights continue from the current ones and move to the new targets
+    let test_cases = [
+        (start_time + 50, "15500000", "34500000"),
+        (start_time + 100, "27750000", "22250000"),
+        (start_time + 150, "40000000", "10000000"),
+    ];
+    for (block_time, expected_offer_weight, expected_ask_weight) in test_cases {
         let simulation_res = query_simulation(
             deps.as_ref(),
             env.clone(),
-            Asset {
-                info: AssetInfo::NativeToken {
-                    denom: "uusd".to_string(),
-                },
-                amount: offer_amount,
-            },
-            t.start_time,
-        );
+            offer_asset.clone(),
+            None,
+            block_time,
+        )
+        .unwrap();
+        assert_eq!(simulation_res.offer_weight, expected_offer_weight);
+        assert_eq!(simulation_res.ask_weight, expected_ask_weight);
+    }
 
-        let simulation_res = simulation_res.unwrap_or_else(|e| {
-            if !t.expected_error {
-                panic!("{:?}", e);
-            }
+    let pair_info: PairInfo = query_pair_info(deps.as_ref()).unwrap();
+    assert_eq!(pair_info.start_time, start_time + 50);
+    assert_eq!(pair_info.end_time, start_time + 150);
 
-            SimulationResponse {
-                return_amount: Default::default(),
-                spread_amount: Default::default(),
-                commission_amount: Default::default(),
-                ask_weight: Default::default(),
-                offer_weight: Default::default(),
-            }
-        });
+    let schedules = query_weight_schedules(deps.as_ref()).unwrap().schedules;
+    assert_eq!(schedules.len(), 2);
+    assert_eq!(schedules[0].start_time, start_time);
+    assert_eq!(schedules[0].end_time, end_time);
+    assert_eq!(
+        schedules[0].asset_infos[1].start_weight,
+        Uint128::from(49u128)
+    );
+    assert_eq!(schedules[1].start_time, start_time + 50);
+    assert_eq!(schedules[1].end_time, start_time + 150);
+    assert_eq!(schedules[1].asset_infos, pair_info.asset_infos);
+
+    // the schedule can not be updated after the sale
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(start_time + 151),
+        info.clone(),
+        ExecuteMsg::UpdateWeightSchedule {
+            end_weights: vec![Uint128::from(40u128), Uint128::from(10u128)],
+            end_time: start_time + 200,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::SaleEnded {});
+
+    // the current weights can not be represented in the range of the new targets
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        instantiate_msg([1, 1_000_000_000_000], [1, 1_000_000_000_000]),
+    )
+    .unwrap();
 
-        if !t.expected_error {
-            assert_eq!(simulation_res.ask_weight.as_str(), &t.expected_ask_weight);
-            assert_eq!(
-                simulation_res.offer_weight.as_str(),
-                &t.expected_offer_weight
-            );
-        }
-    }
+    let res = execute(
+        deps.as_mut(),
+        env,
+        info,
+        ExecuteMsg::UpdateWeightSchedule {
+            end_weights: vec![Uint128::from(1u128), Uint128::from(1u128)],
+            end_time: start_time + 150,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::PriceDeviationExceeded {});
 }
 
 #[test]
@@ -1607,11 +6481,62 @@ fn compute_swap_rounding() {
     let commission_amount = Uint128::from(0_u128);
 
     assert_eq!(
-        compute_swap(offer_pool, offer_weight, ask_pool, ask_weight, offer_amount),
+        compute_swap(
+            offer_pool,
+            offer_weight,
+            ask_pool,
+            ask_weight,
+            offer_amount,
+            Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+            Decimal::percent(30),
+            Decimal::percent(30),
+        ),
         Ok((return_amount, spread_amount, commission_amount))
     );
 }
 
+#[test]
+fn vested_amount_late_sale_end() {
+    let vesting = VestingConfig {
+        info: AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        cliff: 100,
+        duration: 1000,
+    };
+    let end_time = u64::MAX - 50;
+    let purchased = Uint128::from(1_000_u128);
+
+    assert_eq!(
+        vested_amount(&vesting, end_time, purchased, end_time),
+        Uint128::zero()
+    );
+    assert_eq!(
+        vested_amount(&vesting, end_time, purchased, u64::MAX),
+        Uint128::zero()
+    );
+}
+
+#[test]
+fn compute_swap_large_pool() {
+    let offer_pool = Uint128::from(100_000_000_000_000_000_000_u128);
+    let ask_pool = Uint128::from(100_000_000_000_000_000_000_u128);
+
+    assert_eq!(
+        compute_swap(
+            offer_pool,
+            Decimal256::one(),
+            ask_pool,
+            Decimal256::one(),
+            Uint128::from(1_000_000_u128),
+            Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+            Decimal::percent(30),
+            Decimal::percent(30),
+        ),
+        Err(ContractError::AmountTooLarge {})
+    );
+}
+
 proptest! {
     #[test]
     fn compute_swap_test(
@@ -1629,13 +6554,26 @@ proptest! {
         let offer_amount = Uint128::from(offer_amount);
 
 
+        let max_in_ratio = Decimal::percent(30);
+        let max_out_ratio = Decimal::percent(30);
+
         // Make sure there are no overflows
-        compute_swap(
+        let res = compute_swap(
             offer_pool,
             offer_weight,
             ask_pool,
             ask_weight,
             offer_amount,
-        ).unwrap();
+            Decimal::from_ratio(COMMISSION_AMOUNT, COMMISSION_RATIO),
+            max_in_ratio,
+            max_out_ratio,
+        );
+
+        // Trades above the max ratios are rejected instead
+        if offer_amount > offer_pool * max_in_ratio {
+            prop_assert_eq!(res, Err(ContractError::MaxInRatioExceeded { max_in_ratio }));
+        } else if let Err(err) = res {
+            prop_assert_eq!(err, ContractError::MaxOutRatioExceeded { max_out_ratio });
+        }
     }
 }
@@ -21,7 +21,7 @@ use cosmwasm_std::testing::{
     mock_env as mock_env_std, MockApi as MockApiStd, MockQuerier as MockQuerierStd,
     MockStorage as MockStorageStd,
 };
-use cosmwasm_std::{attr, to_binary, Addr, Coin, Uint128};
+use cosmwasm_std::{attr, to_binary, Addr, Coin, Decimal, Uint128};
 use terra_multi_test::{App, BankKeeper, ContractWrapper, Executor, TerraMockQuerier};
 
 use astroport_lbp::asset::{Asset, AssetInfo, PairInfo, WeightedAssetInfo};
@@ -75,13 +75,14 @@ fn instantiate_pair(app: &mut App) -> Addr {
     let end_time = start_time + 1000;
 
     let msg = InstantiateMsg {
-        asset_infos: [
+        asset_infos: vec![
             WeightedAssetInfo {
                 info: AssetInfo::NativeToken {
                     denom: "uluna".to_string(),
                 },
                 start_weight: Uint128::from(1u128),
                 end_weight: Uint128::from(1u128),
+                curve: None,
             },
             WeightedAssetInfo {
                 info: AssetInfo::NativeToken {
@@ -89,12 +90,19 @@ fn instantiate_pair(app: &mut App) -> Addr {
                 },
                 start_weight: Uint128::from(1u128),
                 end_weight: Uint128::from(1u128),
+                curve: None,
             },
         ],
         token_code_id,
         start_time,
         end_time,
         description: None,
+        owner: OWNER.to_string(),
+        liquidity_policy: None,
+        swap_direction: None,
+        swap_fee: Decimal::from_ratio(15u128, 10000u128),
+        max_in_ratio: None,
+        max_out_ratio: None,
     };
 
     app.instantiate_contract(
@@ -126,7 +134,7 @@ fn multi_initialize() {
 #[test]
 fn provide_and_withdraw_liquidity() {
     let mut app = mock_app();
-    let alice_address = Addr::unchecked("alice");
+    let owner = Addr::unchecked(OWNER);
 
     let pair_instance = instantiate_pair(&mut app);
 
@@ -135,9 +143,9 @@ fn provide_and_withdraw_liquidity() {
         .query_wasm_smart(pair_instance.clone(), &QueryMsg::Pair {})
         .unwrap();
 
-    // Set alice balances
+    // Set owner balances
     app.init_bank_balance(
-        &alice_address,
+        &owner,
         vec![
             Coin {
                 denom: "uluna".to_string(),
@@ -154,7 +162,7 @@ fn provide_and_withdraw_liquidity() {
     // Provide liquidity
     let (msg, coins) = provide_liquidity_msg(Uint128::new(100), Uint128::new(100));
     let res = app
-        .execute_contract(alice_address.clone(), pair_instance.clone(), &msg, &coins)
+        .execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
         .unwrap();
 
     assert_eq!(
@@ -170,7 +178,7 @@ fn provide_and_withdraw_liquidity() {
         attr("share", 100u128.to_string())
     );
     assert_eq!(res.events[3].attributes[1], attr("action", "mint"));
-    assert_eq!(res.events[3].attributes[2], attr("to", "alice"));
+    assert_eq!(res.events[3].attributes[2], attr("to", OWNER));
     assert_eq!(res.events[3].attributes[3], attr("amount", 100.to_string()));
 
     // Check withdraw
@@ -180,7 +188,7 @@ fn provide_and_withdraw_liquidity() {
         // Withdraw liquidity
         let res = app
             .execute_contract(
-                alice_address.clone(),
+                owner.clone(),
                 pair_info.liquidity_token.clone(),
                 &Cw20ExecuteMsg::Send {
                     contract: pair_instance.to_string(),
@@ -201,16 +209,16 @@ fn provide_and_withdraw_liquidity() {
             attr("refund_assets", "50uluna, 50uusd")
         );
 
-        assert_eq!(res.events[4].attributes[0], attr("recipient", "alice"));
+        assert_eq!(res.events[4].attributes[0], attr("recipient", OWNER));
         assert_eq!(res.events[4].attributes[2], attr("amount", "50uluna"));
 
-        assert_eq!(res.events[5].attributes[0], attr("recipient", "alice"));
+        assert_eq!(res.events[5].attributes[0], attr("recipient", OWNER));
         assert_eq!(res.events[5].attributes[2], attr("amount", "50uusd"));
     }
 
     // No more liquidity to withdraw. Should return error
     app.execute_contract(
-        alice_address.clone(),
+        owner.clone(),
         pair_info.liquidity_token.clone(),
         &Cw20ExecuteMsg::Send {
             contract: pair_instance.to_string(),
@@ -224,7 +232,7 @@ fn provide_and_withdraw_liquidity() {
 
 fn provide_liquidity_msg(uusd_amount: Uint128, uluna_amount: Uint128) -> (ExecuteMsg, [Coin; 2]) {
     let msg = ExecuteMsg::ProvideLiquidity {
-        assets: [
+        assets: vec![
             Asset {
                 info: AssetInfo::NativeToken {
                     denom: "uusd".to_string(),
@@ -1,17 +1,15 @@
 use cosmwasm_std::{
     entry_point, from_binary, to_binary, Addr, Binary, Coin, CosmosMsg, Deps, DepsMut, Env,
-    MessageInfo, QueryRequest, Response, StdError, StdResult, Uint128, WasmMsg, WasmQuery,
+    MessageInfo, QueryRequest, Response, StdResult, Uint128, WasmMsg, WasmQuery,
 };
 
 use crate::operations::execute_swap_operation;
-use crate::querier::compute_tax;
+use crate::querier::{compute_tax, query_pair_contract};
 use crate::state::{Config, CONFIG};
 
 use crate::error::ContractError;
 use astroport_lbp::asset::{Asset, AssetInfo};
-use astroport_lbp::factory::FactoryPairInfo;
 use astroport_lbp::pair::{QueryMsg as PairQueryMsg, SimulationResponse};
-use astroport_lbp::querier::query_factory_pair_info;
 use astroport_lbp::router::{
     ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
     SimulateSwapOperationsResponse, SwapOperation,
@@ -106,15 +104,15 @@ pub fn execute_swap_operations(
     to: Option<Addr>,
 ) -> Result<Response<TerraMsgWrapper>, ContractError> {
     let operations_len = operations.len();
-    if operations_len == 0 {
-        return Err(ContractError::MustProvideOperations {});
-    }
+    let target_asset_info = match operations.last() {
+        Some(operation) => operation.get_ask_asset_info(),
+        None => return Err(ContractError::MustProvideOperations {}),
+    };
 
     // Assert the operations are properly set
     assert_operations(&operations)?;
 
     let to = if let Some(to) = to { to } else { sender };
-    let target_asset_info = operations.last().unwrap().get_ask_asset_info();
 
     let mut operation_index = 0;
     let mut messages: Vec<CosmosMsg<TerraMsgWrapper>> = operations
@@ -164,10 +162,10 @@ fn assert_minium_receive(
     let swap_amount = receiver_balance.checked_sub(prev_balance)?;
 
     if swap_amount < minimum_receive {
-        return Err(ContractError::Std(StdError::generic_err(format!(
-            "assertion failed; minimum receive amount: {}, swap amount: {}",
-            minimum_receive, swap_amount
-        ))));
+        return Err(ContractError::AssertionMinimumReceive {
+            receive: minimum_receive,
+            amount: swap_amount,
+        });
     }
 
     Ok(Response::default())
@@ -210,7 +208,7 @@ fn simulate_swap_operations(
 
     let operations_len = operations.len();
     if operations_len == 0 {
-        return Err(StdError::generic_err("must provide operations"));
+        return Err(ContractError::MustProvideOperations {}.into());
     }
 
     assert_operations(&operations)?;
@@ -251,7 +249,7 @@ fn simulate_swap_operations(
                 offer_asset_info,
                 ask_asset_info,
             } => {
-                let pair_info: FactoryPairInfo = query_factory_pair_info(
+                let pair_contract = query_pair_contract(
                     deps,
                     &astroport_lbp_factory,
                     &[offer_asset_info.clone(), ask_asset_info.clone()],
@@ -264,12 +262,13 @@ fn simulate_swap_operations(
                 }
                 let mut res: SimulationResponse =
                     deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-                        contract_addr: pair_info.contract_addr.to_string(),
+                        contract_addr: pair_contract.to_string(),
                         msg: to_binary(&PairQueryMsg::Simulation {
                             offer_asset: Asset {
                                 info: offer_asset_info,
                                 amount: offer_amount,
                             },
+                            ask_asset_info: Some(ask_asset_info.clone()),
                             block_time,
                         })?,
                     }))?;
@@ -298,7 +297,7 @@ pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Respons
     Ok(Response::default())
 }
 
-fn assert_operations_order(operations: &[SwapOperation]) -> StdResult<()> {
+fn assert_operations_order(operations: &[SwapOperation]) -> Result<(), ContractError> {
     let mut prev_ask = String::new();
 
     for operation in operations.iter() {
@@ -306,9 +305,7 @@ fn assert_operations_order(operations: &[SwapOperation]) -> StdResult<()> {
         let ask_asset = operation.get_ask_asset_info();
 
         if !prev_ask.is_empty() && prev_ask != offer_asset.to_string() {
-            return Err(StdError::generic_err(
-                "invalid operations order; offer does not equal to prev ask",
-            ));
+            return Err(ContractError::InvalidOperationsOrder {});
         }
 
         prev_ask = ask_asset.to_string()
@@ -317,7 +314,7 @@ fn assert_operations_order(operations: &[SwapOperation]) -> StdResult<()> {
     Ok(())
 }
 
-fn assert_operations(operations: &[SwapOperation]) -> StdResult<()> {
+fn assert_operations(operations: &[SwapOperation]) -> Result<(), ContractError> {
     let mut ask_asset_map: HashMap<String, bool> = HashMap::new();
 
     for operation in operations.iter() {
@@ -329,9 +326,7 @@ fn assert_operations(operations: &[SwapOperation]) -> StdResult<()> {
     }
 
     if ask_asset_map.keys().len() != 1 {
-        return Err(StdError::generic_err(
-            "invalid operations; multiple output token",
-        ));
+        return Err(ContractError::MultipleOutputToken {});
     }
 
     Ok(())
@@ -14,6 +14,15 @@ pub enum ContractError {
 
     #[error("Assertion failed; minimum receive amount: {receive}, swap amount: {amount}")]
     AssertionMinimumReceive { receive: Uint128, amount: Uint128 },
+
+    #[error("Invalid operations order; offer does not equal to prev ask")]
+    InvalidOperationsOrder {},
+
+    #[error("Invalid operations; multiple output token")]
+    MultipleOutputToken {},
+
+    #[error("The router only swaps in two asset pairs")]
+    TwoAssetPairsOnly {},
 }
 
 impl From<OverflowError> for ContractError {
@@ -21,3 +30,13 @@ impl From<OverflowError> for ContractError {
         StdError::from(o).into()
     }
 }
+
+/// Simulations return the typed errors with their messages
+impl From<ContractError> for StdError {
+    fn from(err: ContractError) -> Self {
+        match err {
+            ContractError::Std(err) => err,
+            err => StdError::generic_err(err.to_string()),
+        }
+    }
+}
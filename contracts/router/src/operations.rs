@@ -3,14 +3,13 @@ use cosmwasm_std::{
     StdResult, WasmMsg,
 };
 
-use crate::querier::compute_tax;
+use crate::querier::{compute_tax, query_pair_contract};
 use crate::state::{Config, CONFIG};
 
 use crate::error::ContractError;
 use astroport_lbp::asset::{Asset, AssetInfo};
-use astroport_lbp::factory::FactoryPairInfo;
 use astroport_lbp::pair::ExecuteMsg as PairExecuteMsg;
-use astroport_lbp::querier::{query_balance, query_factory_pair_info, query_token_balance};
+use astroport_lbp::querier::{query_balance, query_token_balance};
 use astroport_lbp::router::SwapOperation;
 use cw20::Cw20ExecuteMsg;
 use terra_cosmwasm::{create_swap_msg, create_swap_send_msg, TerraMsgWrapper};
@@ -67,10 +66,10 @@ pub fn execute_swap_operation(
         } => {
             let config: Config = CONFIG.load(deps.storage)?;
             let astroport_lbp_factory = config.astroport_lbp_factory;
-            let pair_info: FactoryPairInfo = query_factory_pair_info(
+            let pair_contract = query_pair_contract(
                 deps.as_ref(),
                 &astroport_lbp_factory,
-                &[offer_asset_info.clone(), ask_asset_info],
+                &[offer_asset_info.clone(), ask_asset_info.clone()],
             )?;
 
             let amount = match offer_asset_info.clone() {
@@ -87,8 +86,9 @@ pub fn execute_swap_operation(
             };
             vec![asset_into_swap_msg(
                 deps.as_ref(),
-                pair_info.contract_addr,
+                pair_contract,
                 offer_asset,
+                Some(ask_asset_info),
                 None,
                 to,
             )?]
@@ -102,6 +102,7 @@ pub fn asset_into_swap_msg(
     deps: Deps,
     pair_contract: Addr,
     offer_asset: Asset,
+    ask_asset_info: Option<AssetInfo>,
     max_spread: Option<Decimal>,
     to: Option<Addr>,
 ) -> StdResult<CosmosMsg<TerraMsgWrapper>> {
@@ -121,9 +122,11 @@ pub fn asset_into_swap_msg(
                         amount,
                         ..offer_asset
                     },
+                    ask_asset_info,
                     belief_price: None,
                     max_spread,
                     to,
+                    merkle_proof: None,
                 })?,
             }))
         }
@@ -135,9 +138,11 @@ pub fn asset_into_swap_msg(
                 amount: offer_asset.amount,
                 msg: to_binary(&PairExecuteMsg::Swap {
                     offer_asset,
+                    ask_asset_info,
                     belief_price: None,
                     max_spread,
                     to,
+                    merkle_proof: None,
                 })?,
             })?,
         })),
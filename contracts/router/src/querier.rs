@@ -1,6 +1,10 @@
-use cosmwasm_std::{Decimal, Deps, StdResult, Uint128};
+use cosmwasm_std::{to_binary, Addr, Decimal, Deps, QueryRequest, StdResult, Uint128, WasmQuery};
 use terra_cosmwasm::TerraQuerier;
 
+use crate::error::ContractError;
+use astroport_lbp::asset::{AssetInfo, PairInfo};
+use astroport_lbp::factory::QueryMsg as FactoryQueryMsg;
+
 static DECIMAL_FRACTION: Uint128 = Uint128::new(1_000_000_000_000_000_000u128);
 
 pub fn compute_tax(deps: Deps, amount: Uint128, denom: String) -> StdResult<Uint128> {
@@ -19,3 +23,23 @@ pub fn compute_tax(deps: Deps, amount: Uint128, denom: String) -> StdResult<Uint
         tax_cap,
     ))
 }
+
+/// Returns the address of the pair of the two assets, pools of more than two assets are rejected
+pub fn query_pair_contract(
+    deps: Deps,
+    factory_contract: &Addr,
+    asset_infos: &[AssetInfo; 2],
+) -> Result<Addr, ContractError> {
+    let pair_info: PairInfo = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: factory_contract.to_string(),
+        msg: to_binary(&FactoryQueryMsg::Pair {
+            asset_infos: asset_infos.to_vec(),
+        })?,
+    }))?;
+
+    if pair_info.asset_infos.len() != 2 {
+        return Err(ContractError::TwoAssetPairsOnly {});
+    }
+
+    Ok(pair_info.contract_addr)
+}
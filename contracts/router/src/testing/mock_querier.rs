@@ -7,8 +7,9 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use astroport_lbp::asset::{Asset, AssetInfo};
-use astroport_lbp::factory::FactoryPairInfo;
+use astroport_lbp::asset::{
+    Asset, AssetInfo, LiquidityPolicy, PairInfo, SwapDirection, WeightedAssetInfo,
+};
 use astroport_lbp::pair::SimulationResponse;
 use cw20::{BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
 use terra_cosmwasm::{
@@ -18,8 +19,13 @@ use terra_cosmwasm::{
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    Pair { asset_infos: [AssetInfo; 2] },
-    Simulation { offer_asset: Asset },
+    Pair {
+        asset_infos: Vec<AssetInfo>,
+    },
+    Simulation {
+        offer_asset: Asset,
+        ask_asset_info: Option<AssetInfo>,
+    },
 }
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
@@ -102,27 +108,54 @@ pub(crate) fn caps_to_map(caps: &[(&String, &Uint128)]) -> HashMap<String, Uint1
 
 #[derive(Clone, Default)]
 pub struct AstroportFactoryQuerier {
-    pairs: HashMap<String, FactoryPairInfo>,
+    pairs: HashMap<String, PairInfo>,
 }
 
 impl AstroportFactoryQuerier {
-    pub fn new(pairs: &[(&String, &FactoryPairInfo)]) -> Self {
+    pub fn new(pairs: &[(&String, &PairInfo)]) -> Self {
         AstroportFactoryQuerier {
             pairs: pairs_to_map(pairs),
         }
     }
 }
 
-pub(crate) fn pairs_to_map(
-    pairs: &[(&String, &FactoryPairInfo)],
-) -> HashMap<String, FactoryPairInfo> {
-    let mut pairs_map: HashMap<String, FactoryPairInfo> = HashMap::new();
+pub(crate) fn pairs_to_map(pairs: &[(&String, &PairInfo)]) -> HashMap<String, PairInfo> {
+    let mut pairs_map: HashMap<String, PairInfo> = HashMap::new();
     for (key, pair) in pairs.iter() {
         pairs_map.insert(key.to_string(), (*pair).clone());
     }
     pairs_map
 }
 
+/// Returns the info of a pair of equally weighted assets as returned by the factory
+pub(crate) fn pair_info(contract_addr: &str, asset_infos: &[AssetInfo]) -> PairInfo {
+    PairInfo {
+        asset_infos: asset_infos
+            .iter()
+            .map(|info| WeightedAssetInfo {
+                info: info.clone(),
+                start_weight: Uint128::new(1),
+                end_weight: Uint128::new(1),
+                curve: None,
+            })
+            .collect(),
+        contract_addr: Addr::unchecked(contract_addr),
+        liquidity_token: Addr::unchecked("liquidity0000"),
+        owner: Addr::unchecked("owner0000"),
+        start_time: 0,
+        end_time: 0,
+        description: None,
+        liquidity_policy: LiquidityPolicy::Open,
+        swap_direction: SwapDirection::Both,
+        paused: false,
+        swap_fee: Decimal::zero(),
+        max_in_ratio: Decimal::one(),
+        max_out_ratio: Decimal::one(),
+        closed_at: None,
+        cancelled: false,
+    }
+}
+
 impl Querier for WasmMockQuerier {
     fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
         // MockQuerier doesn't support Custom, so we ignore it completely here
@@ -204,26 +237,22 @@ impl WasmMockQuerier {
             QueryMsg::Pair { asset_infos } => {
                 let key = asset_infos[0].to_string() + asset_infos[1].to_string().as_str();
                 match self.astroport_lbp_factory_querier.pairs.get(&key) {
-                    Some(v) => SystemResult::Ok(
-                        to_binary(&FactoryPairInfo {
-                            owner: Addr::unchecked("owner0000"),
-                            contract_addr: Addr::unchecked("pair"),
-                        })
-                        .into(),
-                    ),
+                    Some(v) => SystemResult::Ok(to_binary(&v).into()),
                     None => SystemResult::Err(SystemError::InvalidRequest {
                         error: "No pair info exists".to_string(),
                         request: msg.as_slice().into(),
                     }),
                 }
             }
-            QueryMsg::Simulation { offer_asset } => SystemResult::Ok(
+            QueryMsg::Simulation { offer_asset, .. } => SystemResult::Ok(
                 to_binary(&SimulationResponse {
                     return_amount: offer_asset.amount,
                     commission_amount: Uint128::zero(),
                     ask_weight: "".to_string(),
                     spread_amount: Uint128::zero(),
                     offer_weight: "".to_string(),
+                    swap_fee: Decimal::zero(),
+                    protocol_fee_amount: Uint128::zero(),
                 })
                 .into(),
             ),
@@ -304,7 +333,7 @@ impl WasmMockQuerier {
         self.tax_querier = TaxQuerier::new(rate, caps);
     }
 
-    pub fn with_astroport_lbp_pairs(&mut self, pairs: &[(&String, &FactoryPairInfo)]) {
+    pub fn with_astroport_lbp_pairs(&mut self, pairs: &[(&String, &PairInfo)]) {
         self.astroport_lbp_factory_querier = AstroportFactoryQuerier::new(pairs);
     }
 }
@@ -1,14 +1,13 @@
 use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_binary, to_binary, Addr, Coin, CosmosMsg, Decimal, StdError, SubMsg, Uint128, WasmMsg,
+    from_binary, to_binary, Addr, Coin, CosmosMsg, Decimal, SubMsg, Uint128, WasmMsg,
 };
 
 use crate::contract::{execute, instantiate, query};
 use crate::error::ContractError;
-use crate::testing::mock_querier::mock_dependencies;
+use crate::testing::mock_querier::{mock_dependencies, pair_info};
 
 use astroport_lbp::asset::{Asset, AssetInfo};
-use astroport_lbp::factory::FactoryPairInfo;
 use astroport_lbp::pair::ExecuteMsg as PairExecuteMsg;
 use astroport_lbp::router::{
     ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg,
@@ -362,13 +361,16 @@ fn execute_swap_operation() {
         ))],
     );
 
-    deps.querier.with_astroport_lbp_pairs(&[(
-        &"assetuusd".to_string(),
-        &FactoryPairInfo {
-            owner: Addr::unchecked("owner0000"),
-            contract_addr: Addr::unchecked("pair"),
+    let asset_infos = [
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset"),
         },
-    )]);
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+    ];
+    deps.querier
+        .with_astroport_lbp_pairs(&[(&"assetuusd".to_string(), &pair_info("pair", &asset_infos))]);
     deps.querier.with_token_balances(&[(
         &Addr::unchecked("asset"),
         &[(
@@ -389,7 +391,7 @@ fn execute_swap_operation() {
     };
     let env = mock_env();
     let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
-    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    let res = execute(deps.as_mut(), env, info, msg.clone()).unwrap();
     assert_eq!(
         res.messages,
         vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -405,15 +407,33 @@ fn execute_swap_operation() {
                         },
                         amount: Uint128::new(1000000u128),
                     },
+                    ask_asset_info: Some(AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    }),
                     belief_price: None,
                     max_spread: None,
                     to: Some(Addr::unchecked("addr0000")),
+                    merkle_proof: None,
                 })
                 .unwrap()
             })
             .unwrap()
         }))]
     );
+
+    // pools of more than two assets can not be swapped in
+    let mut pool_asset_infos = asset_infos.to_vec();
+    pool_asset_infos.push(AssetInfo::NativeToken {
+        denom: "uluna".to_string(),
+    });
+    deps.querier.with_astroport_lbp_pairs(&[(
+        &"assetuusd".to_string(),
+        &pair_info("pair", &pool_asset_infos),
+    )]);
+    let env = mock_env();
+    let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::TwoAssetPairsOnly {});
 }
 
 #[test]
@@ -473,17 +493,31 @@ fn query_buy_with_routes() {
     deps.querier.with_astroport_lbp_pairs(&[
         (
             &"ukrwasset0000".to_string(),
-            &FactoryPairInfo {
-                owner: Addr::unchecked("owner0000"),
-                contract_addr: Addr::unchecked("pair0000"),
-            },
+            &pair_info(
+                "pair0000",
+                &[
+                    AssetInfo::NativeToken {
+                        denom: "ukrw".to_string(),
+                    },
+                    AssetInfo::Token {
+                        contract_addr: Addr::unchecked("asset0000"),
+                    },
+                ],
+            ),
         ),
         (
             &"asset0000uluna".to_string(),
-            &FactoryPairInfo {
-                owner: Addr::unchecked("owner0001"),
-                contract_addr: Addr::unchecked("pair0001"),
-            },
+            &pair_info(
+                "pair0001",
+                &[
+                    AssetInfo::Token {
+                        contract_addr: Addr::unchecked("asset0000"),
+                    },
+                    AssetInfo::NativeToken {
+                        denom: "uluna".to_string(),
+                    },
+                ],
+            ),
         ),
     ]);
 
@@ -562,9 +596,10 @@ fn assert_minimum_receive_native_token() {
     let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
     assert_eq!(
         res,
-        ContractError::Std(StdError::GenericErr {
-            msg: "assertion failed; minimum receive amount: 1000001, swap amount: 1000000".into()
-        })
+        ContractError::AssertionMinimumReceive {
+            receive: Uint128::from(1000001u128),
+            amount: Uint128::from(1000000u128),
+        }
     );
 }
 
@@ -602,8 +637,9 @@ fn assert_minimum_receive_token() {
     let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
     assert_eq!(
         res,
-        ContractError::Std(StdError::GenericErr {
-            msg: "assertion failed; minimum receive amount: 1000001, swap amount: 1000000".into()
-        })
+        ContractError::AssertionMinimumReceive {
+            receive: Uint128::from(1000001u128),
+            amount: Uint128::from(1000000u128),
+        }
     )
 }
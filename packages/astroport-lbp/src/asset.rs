@@ -22,6 +22,7 @@ pub struct WeightedAsset {
     pub amount: Uint128,
     pub start_weight: Uint128,
     pub end_weight: Uint128,
+    pub curve: Option<WeightCurve>,
 }
 
 impl fmt::Display for Asset {
@@ -35,6 +36,8 @@ pub struct WeightedAssetInfo {
     pub info: AssetInfo,
     pub start_weight: Uint128,
     pub end_weight: Uint128,
+    /// The way the weight moves from start_weight to end_weight, linear by default
+    pub curve: Option<WeightCurve>,
 }
 
 impl fmt::Display for WeightedAssetInfo {
@@ -43,6 +46,20 @@ impl fmt::Display for WeightedAssetInfo {
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WeightCurve {
+    /// The weight changes at a constant rate
+    Linear,
+    /// The distance to end_weight halves every `half_life` seconds,
+    /// normalized to reach end_weight at the end of the sale
+    Exponential { half_life: u64 },
+    /// The weight changes in `steps` equal jumps
+    Stepwise { steps: u64 },
+    /// The weight is linearly interpolated between the given `(timestamp, weight)` points
+    Piecewise { points: Vec<(u64, Uint128)> },
+}
+
 static DECIMAL_FRACTION: Uint128 = Uint128::new(1_000_000_000_000_000_000u128);
 
 impl Asset {
@@ -188,32 +205,75 @@ impl AssetInfo {
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LiquidityPolicy {
+    /// Anyone can provide and withdraw liquidity at any time
+    Open,
+    /// Only the sale owner can provide liquidity and only before the sale starts,
+    /// liquidity can not be withdrawn while the sale is running
+    SaleOwner,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapDirection {
+    /// Any asset can be offered
+    Both,
+    /// Only the first asset can be offered for the second one, two asset pairs only
+    Asset0ToAsset1,
+    /// Only the second asset can be offered for the first one, two asset pairs only
+    Asset1ToAsset0,
+}
+
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct PairInfo {
-    pub asset_infos: [WeightedAssetInfo; 2],
+    pub asset_infos: Vec<WeightedAssetInfo>,
     pub contract_addr: Addr,
     pub liquidity_token: Addr,
+    pub owner: Addr,
     pub start_time: u64,
     pub end_time: u64,
     pub description: Option<String>,
+    pub liquidity_policy: LiquidityPolicy,
+    pub swap_direction: SwapDirection,
+    pub paused: bool,
+    pub swap_fee: Decimal,
+    pub max_in_ratio: Decimal,
+    pub max_out_ratio: Decimal,
+    /// Time the sale was closed at by reaching its hard cap
+    pub closed_at: Option<u64>,
+    /// Whether the sale was cancelled before it started
+    pub cancelled: bool,
 }
 
 impl PairInfo {
-    pub fn query_pools(&self, deps: Deps, contract_addr: &Addr) -> StdResult<[WeightedAsset; 2]> {
-        Ok([
-            WeightedAsset {
-                amount: self.asset_infos[0].info.query_pool(deps, contract_addr)?,
-                info: self.asset_infos[0].info.clone(),
-                start_weight: self.asset_infos[0].start_weight,
-                end_weight: self.asset_infos[0].end_weight,
-            },
-            WeightedAsset {
-                amount: self.asset_infos[1].info.query_pool(deps, contract_addr)?,
-                info: self.asset_infos[1].info.clone(),
-                start_weight: self.asset_infos[1].start_weight,
-                end_weight: self.asset_infos[1].end_weight,
-            },
-        ])
+    /// Returns the time the sale finishes at, which is earlier than end_time if it was closed
+    pub fn sale_end_time(&self) -> u64 {
+        self.closed_at.unwrap_or(self.end_time)
+    }
+
+    pub fn query_pools(&self, deps: Deps, contract_addr: &Addr) -> StdResult<Vec<WeightedAsset>> {
+        self.asset_infos
+            .iter()
+            .map(|asset_info| {
+                Ok(WeightedAsset {
+                    amount: asset_info.info.query_pool(deps, contract_addr)?,
+                    info: asset_info.info.clone(),
+                    start_weight: asset_info.start_weight,
+                    end_weight: asset_info.end_weight,
+                    curve: asset_info.curve.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the asset infos without their weights
+    pub fn infos(&self) -> Vec<AssetInfo> {
+        self.asset_infos
+            .iter()
+            .map(|asset_info| asset_info.info.clone())
+            .collect()
     }
 }
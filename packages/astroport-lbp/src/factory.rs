@@ -1,8 +1,8 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::asset::{AssetInfo, PairInfo, WeightedAssetInfo};
-use cosmwasm_std::Addr;
+use crate::asset::{AssetInfo, LiquidityPolicy, PairInfo, SwapDirection, WeightedAssetInfo};
+use cosmwasm_std::{Addr, Decimal};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -10,6 +10,16 @@ pub struct InstantiateMsg {
     pub pair_code_id: u64,
     pub token_code_id: u64,
     pub owner: String,
+    /// Lowest swap fee a pair can be created with
+    pub min_swap_fee: Decimal,
+    /// Highest swap fee a pair can be created with
+    pub max_swap_fee: Decimal,
+    /// Protocol fee collector
+    pub fee_address: Option<String>,
+    /// Share of the swap commission sent to the fee collector
+    pub protocol_fee_share: Decimal,
+    /// Constant product pair contract code ID, which finished sales graduate to
+    pub xyk_pair_code_id: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -20,20 +30,40 @@ pub enum ExecuteMsg {
         owner: Option<Addr>,
         token_code_id: Option<u64>,
         pair_code_id: Option<u64>,
+        min_swap_fee: Option<Decimal>,
+        max_swap_fee: Option<Decimal>,
+        fee_address: Option<String>,
+        protocol_fee_share: Option<Decimal>,
+        xyk_pair_code_id: Option<u64>,
     },
     /// CreatePair instantiates pair contract
     CreatePair {
-        /// Asset infos
-        asset_infos: [WeightedAssetInfo; 2],
+        /// Asset infos, from 2 to 8 assets
+        asset_infos: Vec<WeightedAssetInfo>,
         /// LBP start time
         start_time: u64,
         /// LBP end time
         end_time: u64,
         /// Pair description
         description: Option<String>,
+        /// Who can provide and withdraw liquidity and when
+        liquidity_policy: Option<LiquidityPolicy>,
+        /// Which asset can be offered in swaps
+        swap_direction: Option<SwapDirection>,
+        /// Swap fee within the factory bounds
+        swap_fee: Decimal,
+        /// Max share of the offer pool a swap can offer
+        max_in_ratio: Option<Decimal>,
+        /// Max share of the ask pool a swap can return
+        max_out_ratio: Option<Decimal>,
     },
-    Unregister {
-        asset_infos: [AssetInfo; 2],
+    /// Removes the pair, only its creator or the pair itself can execute it
+    Unregister { asset_infos: Vec<AssetInfo> },
+    /// Records the constant product pair a finished sale graduated to;
+    /// only the registered pair can execute it
+    RegisterGraduatedPair {
+        asset_infos: Vec<AssetInfo>,
+        graduated_pair: String,
     },
 }
 
@@ -42,12 +72,15 @@ pub enum ExecuteMsg {
 pub enum QueryMsg {
     Config {},
     Pair {
-        asset_infos: [AssetInfo; 2],
+        asset_infos: Vec<AssetInfo>,
     },
     Pairs {
-        start_after: Option<[AssetInfo; 2]>,
+        start_after: Option<Vec<AssetInfo>>,
         limit: Option<u32>,
     },
+    GraduatedPair {
+        asset_infos: Vec<AssetInfo>,
+    },
 }
 
 // We define a custom struct for each query response
@@ -56,6 +89,11 @@ pub struct ConfigResponse {
     pub owner: Addr,
     pub pair_code_id: u64,
     pub token_code_id: u64,
+    pub min_swap_fee: Decimal,
+    pub max_swap_fee: Decimal,
+    pub fee_address: Option<Addr>,
+    pub protocol_fee_share: Decimal,
+    pub xyk_pair_code_id: Option<u64>,
 }
 
 /// We currently take no arguments for migrations
@@ -74,3 +112,10 @@ pub struct FactoryPairInfo {
     pub owner: Addr,
     pub contract_addr: Addr,
 }
+
+/// GraduatedPairResponse returns the constant product pair a sale graduated to
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GraduatedPairResponse {
+    pub lbp_pair: Addr,
+    pub graduated_pair: Addr,
+}
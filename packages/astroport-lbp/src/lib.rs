@@ -17,6 +17,9 @@ mod uints {
     construct_uint! {
         pub struct U256(4);
     }
+    construct_uint! {
+        pub struct U1024(16);
+    }
 }
 
-pub use uints::U256;
+pub use uints::{U1024, U256};
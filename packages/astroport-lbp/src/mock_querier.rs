@@ -238,7 +238,7 @@ impl DefaultQueryHandler {
                 msg,
             }) => match from_binary(&msg).unwrap() {
                 FactoryQueryMsg::Pair { asset_infos } => {
-                    let key = asset_infos[0].to_string() + asset_infos[1].to_string().as_str();
+                    let key: String = asset_infos.iter().map(|info| info.to_string()).collect();
                     match self.astroport_lbp_factory_querier.pairs.get(&key) {
                         Some(v) => SystemResult::Ok(to_binary(&v).into()),
                         None => SystemResult::Err(SystemError::InvalidRequest {
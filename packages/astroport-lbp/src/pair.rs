@@ -1,15 +1,17 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::asset::{Asset, WeightedAsset, WeightedAssetInfo};
+use crate::asset::{
+    Asset, AssetInfo, LiquidityPolicy, SwapDirection, WeightedAsset, WeightedAssetInfo,
+};
 
 use cosmwasm_std::{Addr, Decimal, Uint128};
 use cw20::Cw20ReceiveMsg;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
-    /// Asset infos
-    pub asset_infos: [WeightedAssetInfo; 2],
+    /// Asset infos, from 2 to 8 assets
+    pub asset_infos: Vec<WeightedAssetInfo>,
     /// Token contract code id for initialization
     pub token_code_id: u64,
     /// LBP start time
@@ -18,6 +20,18 @@ pub struct InstantiateMsg {
     pub end_time: u64,
     /// Pair description
     pub description: Option<String>,
+    /// Sale owner
+    pub owner: String,
+    /// Who can provide and withdraw liquidity and when, defaults to `SaleOwner`
+    pub liquidity_policy: Option<LiquidityPolicy>,
+    /// Which asset can be offered in swaps, defaults to `Both`
+    pub swap_direction: Option<SwapDirection>,
+    /// Swap fee, e.g. 0.0015 for 0.15%
+    pub swap_fee: Decimal,
+    /// Max share of the offer pool a swap can offer, defaults to 0.3
+    pub max_in_ratio: Option<Decimal>,
+    /// Max share of the ask pool a swap can return, defaults to 0.3
+    pub max_out_ratio: Option<Decimal>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -26,16 +40,117 @@ pub enum ExecuteMsg {
     Receive(Cw20ReceiveMsg),
     /// ProvideLiquidity a user provides pool liquidity
     ProvideLiquidity {
-        assets: [Asset; 2],
+        assets: Vec<Asset>,
         slippage_tolerance: Option<Decimal>,
     },
+    /// Provides liquidity in a single asset, the swap fee is charged on the part of the
+    /// deposit which is not proportional to the pool; not available while the sale is running
+    ProvideSingleLiquidity {
+        asset: Asset,
+        /// Minimum share to receive
+        min_share: Option<Uint128>,
+    },
     /// Swap an offer asset to the other
     Swap {
         offer_asset: Asset,
+        /// Asset to receive, required if the pair has more than two assets
+        ask_asset_info: Option<AssetInfo>,
         belief_price: Option<Decimal>,
         max_spread: Option<Decimal>,
         to: Option<Addr>,
+        /// Proof of the sender in the allowlist, required if the pair has a merkle root
+        merkle_proof: Option<Vec<String>>,
+    },
+    /// Swaps the required amount of a native offer asset for exactly the ask amount, the excess
+    /// of `max_offer_amount` is refunded; tokens are offered with the cw20 hook
+    SwapExactOut {
+        ask_asset: Asset,
+        /// Asset to offer, required if the pair has more than two assets
+        offer_asset_info: Option<AssetInfo>,
+        max_offer_amount: Uint128,
+        to: Option<Addr>,
+        /// Proof of the sender in the allowlist, required if the pair has a merkle root
+        merkle_proof: Option<Vec<String>>,
+    },
+    /// Creates a request to change the sale owner; only the current owner can execute it
+    ProposeNewOwner {
+        owner: String,
+        expires_in: u64,
+    },
+    /// Removes the pending request to change the sale owner
+    DropOwnershipProposal {},
+    /// The proposed owner accepts the sale ownership
+    ClaimOwnership {},
+    /// Re-anchors the weight schedule at the current weights and moves them linearly
+    /// to the new targets by end_time; only the sale owner can execute it
+    UpdateWeightSchedule {
+        end_weights: Vec<Uint128>,
+        end_time: u64,
+    },
+    /// Stops swaps and liquidity management until the pair is unpaused, withdrawals
+    /// remain available after the sale; the sale owner or the factory owner can execute it
+    Pause {},
+    /// Resumes swaps and liquidity management
+    Unpause {},
+    /// Changes the swap fee before the sale starts; only the sale owner can execute it
+    UpdateSwapFee {
+        swap_fee: Decimal,
     },
+    /// Sets the hex encoded merkle root of the allowed swap senders before the sale starts,
+    /// `None` opens swaps to everyone; only the sale owner can execute it
+    UpdateMerkleRoot {
+        merkle_root: Option<String>,
+    },
+    /// Sets the per-address purchase cap before the sale starts, `None` removes it;
+    /// only the sale owner can execute it
+    UpdatePurchaseCap {
+        purchase_cap: Option<PurchaseCap>,
+    },
+    /// Moves the sale owner liquidity to a new constant product pair seeded at the final
+    /// sale price after the sale ends; the owner must allow the pair to burn its LP tokens
+    GraduateToPool {},
+    /// Sets the vesting of purchased amounts before the sale starts, `None` delivers them
+    /// right away; only the sale owner can execute it
+    UpdateVesting {
+        vesting: Option<VestingConfig>,
+    },
+    /// Sends the vested part of the sender purchases
+    Claim {},
+    /// Sets the minimum amount of an asset the sale must raise before it starts, `None`
+    /// removes it; only the sale owner can execute it
+    UpdateSoftCap {
+        soft_cap: Option<Asset>,
+    },
+    /// Returns the sent tokens bought in a sale which did not reach its soft cap
+    /// and refunds the paid share of the raise asset
+    Refund {},
+    /// Sets the amount of an asset offered in swaps which closes the sale before it starts,
+    /// `None` removes it; only the sale owner can execute it
+    UpdateHardCap {
+        hard_cap: Option<Asset>,
+    },
+    /// Cancels the sale before it starts, returns the pooled assets to the sale owner and
+    /// unregisters the pair in the factory; the owner must allow the pair to burn its LP tokens
+    CancelSale {},
+}
+
+/// VestingConfig holds back the purchased amounts of an asset, they vest linearly over
+/// `duration` seconds after the sale ends and nothing can be claimed until `cliff` seconds pass
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingConfig {
+    pub info: AssetInfo,
+    pub cliff: u64,
+    pub duration: u64,
+}
+
+/// PurchaseCap limits the cumulative amount each address can buy during the sale
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PurchaseCap {
+    /// Caps the amount of the given asset an address receives from swaps
+    Received { info: AssetInfo, amount: Uint128 },
+    /// Caps the amount of the given asset an address offers in swaps
+    Spent { info: AssetInfo, amount: Uint128 },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -43,11 +158,32 @@ pub enum ExecuteMsg {
 pub enum Cw20HookMsg {
     /// Sell a given amount of asset
     Swap {
+        /// Asset to receive, required if the pair has more than two assets
+        ask_asset_info: Option<AssetInfo>,
         belief_price: Option<Decimal>,
         max_spread: Option<Decimal>,
         to: Option<Addr>,
+        /// Proof of the sender in the allowlist, required if the pair has a merkle root
+        merkle_proof: Option<Vec<String>>,
+    },
+    /// Swaps the required amount of the sent tokens for exactly the ask amount,
+    /// the sent amount is the maximum offer amount and its excess is refunded
+    SwapExactOut {
+        ask_asset: Asset,
+        to: Option<Addr>,
+        /// Proof of the sender in the allowlist, required if the pair has a merkle root
+        merkle_proof: Option<Vec<String>>,
     },
     WithdrawLiquidity {},
+    /// Withdraws liquidity in a single asset, the swap fee is charged on the part of the
+    /// return which is not proportional to the pool; not available while the sale is running
+    WithdrawSingleLiquidity {
+        asset_info: AssetInfo,
+        /// Minimum amount of the asset to receive
+        min_return: Option<Uint128>,
+    },
+    /// Returns the tokens bought in a sale which did not reach its soft cap
+    Refund {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -55,14 +191,72 @@ pub enum Cw20HookMsg {
 pub enum QueryMsg {
     Pair {},
     Pool {},
-    Simulation { offer_asset: Asset, block_time: u64 },
-    ReverseSimulation { ask_asset: Asset, block_time: u64 },
+    Simulation {
+        offer_asset: Asset,
+        /// Required if the pair has more than two assets
+        ask_asset_info: Option<AssetInfo>,
+        block_time: u64,
+    },
+    ReverseSimulation {
+        ask_asset: Asset,
+        /// Required if the pair has more than two assets
+        offer_asset_info: Option<AssetInfo>,
+        block_time: u64,
+    },
+    ProvideSingleSimulation {
+        asset: Asset,
+        block_time: u64,
+    },
+    WithdrawSingleSimulation {
+        share: Uint128,
+        asset_info: AssetInfo,
+        block_time: u64,
+    },
+    WeightSchedules {},
+    Stats {},
+    Eligibility {
+        address: String,
+        merkle_proof: Option<Vec<String>>,
+    },
+    Allowance {
+        address: String,
+    },
+    CumulativePrices {},
+    Twap {
+        start: u64,
+        end: u64,
+    },
+    /// Price of the base asset in the quote asset with the weights at `at_time`,
+    /// the current block time by default; the quote asset is required if the pair
+    /// has more than two assets
+    SpotPrice {
+        base_asset: AssetInfo,
+        quote_asset: Option<AssetInfo>,
+        at_time: Option<u64>,
+    },
+    /// Samples the weights and the spot price of the base asset with the current pools
+    /// at `points` evenly spaced timestamps from start_time to end_time of the current
+    /// weight schedule, earlier schedules are returned by WeightSchedules
+    PriceCurve {
+        base_asset: AssetInfo,
+        quote_asset: Option<AssetInfo>,
+        points: u32,
+    },
+    Graduation {},
+    VestingInfo {
+        address: String,
+    },
+    SoftCap {},
+    HardCap {},
+    Contribution {
+        address: String,
+    },
 }
 
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct PoolResponse {
-    pub assets: [WeightedAsset; 2],
+    pub assets: Vec<WeightedAsset>,
     pub total_share: Uint128,
 }
 
@@ -74,6 +268,9 @@ pub struct SimulationResponse {
     pub commission_amount: Uint128,
     pub ask_weight: String,
     pub offer_weight: String,
+    pub swap_fee: Decimal,
+    /// Part of the commission sent to the protocol fee collector
+    pub protocol_fee_amount: Uint128,
 }
 
 /// ReverseSimulationResponse returns reverse swap simulation response
@@ -84,6 +281,189 @@ pub struct ReverseSimulationResponse {
     pub commission_amount: Uint128,
     pub ask_weight: String,
     pub offer_weight: String,
+    pub swap_fee: Decimal,
+    /// Part of the commission sent to the protocol fee collector
+    pub protocol_fee_amount: Uint128,
+}
+
+/// ProvideSingleSimulationResponse returns the share minted for a single asset deposit
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProvideSingleSimulationResponse {
+    pub share: Uint128,
+    /// Part of the deposit charged as the swap fee
+    pub fee_amount: Uint128,
+}
+
+/// WithdrawSingleSimulationResponse returns the amount of the asset returned for a share
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WithdrawSingleSimulationResponse {
+    pub return_amount: Uint128,
+    /// Part of the return charged as the swap fee
+    pub fee_amount: Uint128,
+}
+
+/// WeightSchedule describes how the asset weights move over a period of time
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WeightSchedule {
+    pub asset_infos: Vec<WeightedAssetInfo>,
+    pub start_time: u64,
+    pub end_time: u64,
+}
+
+/// WeightSchedulesResponse returns the initial weight schedule followed by all its updates
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WeightSchedulesResponse {
+    pub schedules: Vec<WeightSchedule>,
+}
+
+/// AssetStats holds the cumulative swap totals of an asset
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AssetStats {
+    pub info: AssetInfo,
+    /// Amount of the asset offered to the pair
+    pub volume_in: Uint128,
+    /// Amount of the asset returned by the pair
+    pub volume_out: Uint128,
+    /// Commission charged in the asset
+    pub commission_amount: Uint128,
+    /// Part of the commission sent to the protocol fee collector
+    pub protocol_fee_amount: Uint128,
+}
+
+/// StatsResponse returns the cumulative swap totals for every asset
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StatsResponse {
+    pub assets: Vec<AssetStats>,
+}
+
+/// EligibilityResponse returns whether the address can swap with the given proof
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EligibilityResponse {
+    pub merkle_root: Option<String>,
+    pub eligible: bool,
+}
+
+/// AllowanceResponse returns how much the address has bought and can still buy
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllowanceResponse {
+    pub purchase_cap: Option<PurchaseCap>,
+    pub purchased: Uint128,
+    /// `None` if the pair has no purchase cap
+    pub remaining: Option<Uint128>,
+}
+
+/// CumulativePricesResponse returns the price accumulators at the current block, a price
+/// accumulator is the sum of the spot price with 18 decimals multiplied by its duration in seconds;
+/// the prices are only tracked in two asset pairs
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CumulativePricesResponse {
+    pub assets: Vec<WeightedAsset>,
+    pub total_share: Uint128,
+    /// Accumulator of the first asset price in the second asset
+    pub price0_cumulative_last: Uint128,
+    /// Accumulator of the second asset price in the first asset
+    pub price1_cumulative_last: Uint128,
+}
+
+/// TwapResponse returns the time-weighted average prices between start and end
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TwapResponse {
+    pub start: u64,
+    pub end: u64,
+    /// Average price of the first asset in the second asset
+    pub price0_average: Decimal,
+    /// Average price of the second asset in the first asset
+    pub price1_average: Decimal,
+}
+
+/// SpotPriceResponse returns the weighted spot price of the base asset in the quote asset
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SpotPriceResponse {
+    pub base_asset: AssetInfo,
+    pub quote_asset: AssetInfo,
+    pub at_time: u64,
+    /// `(quote_pool / quote_weight) / (base_pool / base_weight)`
+    pub spot_price: Decimal,
+    /// Spot price of buying the base asset, `spot_price / (1 - swap_fee)`
+    pub spot_price_with_fee: Decimal,
+}
+
+/// PricePoint is the projected spot price at a timestamp
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PricePoint {
+    pub time: u64,
+    pub base_weight: String,
+    pub quote_weight: String,
+    pub spot_price: Decimal,
+}
+
+/// PriceCurveResponse returns the projected spot prices of the base asset if nobody swaps
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceCurveResponse {
+    pub base_asset: AssetInfo,
+    pub quote_asset: AssetInfo,
+    pub points: Vec<PricePoint>,
+}
+
+/// GraduationResponse returns the constant product pair the sale graduated to
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GraduationResponse {
+    pub graduated_pair: Option<Addr>,
+}
+
+/// VestingInfoResponse returns the vested purchases of the address
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingInfoResponse {
+    pub vesting: Option<VestingConfig>,
+    pub purchased: Uint128,
+    pub claimed: Uint128,
+    pub vested: Uint128,
+    pub claimable: Uint128,
+}
+
+/// SoftCapResponse returns the soft cap, the raised amount and the amount which can be refunded
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SoftCapResponse {
+    pub soft_cap: Option<Asset>,
+    /// Amount of the soft cap asset offered in swaps minus the amount paid out of the pool
+    pub raised: Uint128,
+    /// Amount paid by the buyers which is not sold back or refunded
+    pub refundable: Uint128,
+    /// Whether the sale has ended below its soft cap
+    pub refunding: bool,
+}
+
+/// HardCapResponse returns the hard cap and the amount of its asset offered in swaps
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HardCapResponse {
+    pub hard_cap: Option<Asset>,
+    pub inflow: Uint128,
+    pub closed_at: Option<u64>,
+}
+
+/// ContributionResponse returns how much the address has paid and received in the sale
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContributionResponse {
+    pub paid: Uint128,
+    pub received: Uint128,
+}
+
+/// XykPairInstantiateMsg instantiates the constant product pair a sale graduates to
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct XykPairInstantiateMsg {
+    pub asset_infos: [AssetInfo; 2],
+    pub token_code_id: u64,
+}
+
+/// XykPairExecuteMsg is the part of the constant product pair interface used on graduation
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum XykPairExecuteMsg {
+    ProvideLiquidity {
+        assets: [Asset; 2],
+        slippage_tolerance: Option<Decimal>,
+        receiver: Option<String>,
+    },
 }
 
 /// We currently take no arguments for migrations
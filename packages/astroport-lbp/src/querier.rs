@@ -1,10 +1,13 @@
 use crate::asset::{Asset, AssetInfo};
-use crate::factory::{FactoryPairInfo, QueryMsg as FactoryQueryMsg};
+use crate::factory::{
+    ConfigResponse as FactoryConfigResponse, FactoryPairInfo, QueryMsg as FactoryQueryMsg,
+};
 use crate::pair::{QueryMsg as PairQueryMsg, ReverseSimulationResponse, SimulationResponse};
 
 use cosmwasm_std::{
-    to_binary, Addr, AllBalanceResponse, Api, BalanceResponse, BankQuery, Coin, Deps, Querier,
-    QueryRequest, StdResult, Storage, Uint128, WasmQuery,
+    from_binary, to_binary, Addr, AllBalanceResponse, Api, BalanceResponse, BankQuery, Coin,
+    ContractResult, Deps, Empty, Querier, QueryRequest, StdError, StdResult, Storage, SystemError,
+    SystemResult, Uint128, WasmQuery,
 };
 use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
 
@@ -58,26 +61,63 @@ pub fn query_supply(deps: Deps, contract_addr: &Addr) -> StdResult<Uint128> {
 pub fn query_factory_pair_info(
     deps: Deps,
     factory_contract: &Addr,
-    asset_infos: &[AssetInfo; 2],
+    asset_infos: &[AssetInfo],
 ) -> StdResult<FactoryPairInfo> {
     deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: factory_contract.to_string(),
         msg: to_binary(&FactoryQueryMsg::Pair {
-            asset_infos: asset_infos.clone(),
+            asset_infos: asset_infos.to_vec(),
         })?,
     }))
 }
 
+pub fn query_factory_config(
+    deps: Deps,
+    factory_contract: &Addr,
+) -> StdResult<FactoryConfigResponse> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: factory_contract.to_string(),
+        msg: to_binary(&FactoryQueryMsg::Config {})?,
+    }))
+}
+
+/// Returns `None` if the factory address is not a contract, e.g. a pair instantiated without
+/// the factory, any other failure of the query is returned as an error
+pub fn query_factory_config_if_exists(
+    deps: Deps,
+    factory_contract: &Addr,
+) -> StdResult<Option<FactoryConfigResponse>> {
+    let request = to_binary(&QueryRequest::<Empty>::Wasm(WasmQuery::Smart {
+        contract_addr: factory_contract.to_string(),
+        msg: to_binary(&FactoryQueryMsg::Config {})?,
+    }))?;
+
+    match deps.querier.raw_query(&request) {
+        SystemResult::Err(SystemError::NoSuchContract { .. }) => Ok(None),
+        SystemResult::Err(system_err) => Err(StdError::generic_err(format!(
+            "Querier system error: {}",
+            system_err
+        ))),
+        SystemResult::Ok(ContractResult::Err(contract_err)) => Err(StdError::generic_err(format!(
+            "Querier contract error: {}",
+            contract_err
+        ))),
+        SystemResult::Ok(ContractResult::Ok(value)) => from_binary(&value).map(Some),
+    }
+}
+
 pub fn simulate(
     deps: Deps,
     pair_contract: &Addr,
     offer_asset: &Asset,
+    ask_asset_info: Option<AssetInfo>,
     block_time: u64,
 ) -> StdResult<SimulationResponse> {
     deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: pair_contract.to_string(),
         msg: to_binary(&PairQueryMsg::Simulation {
             offer_asset: offer_asset.clone(),
+            ask_asset_info,
             block_time,
         })?,
     }))
@@ -87,12 +127,14 @@ pub fn reverse_simulate<S: Storage, A: Api, Q: Querier>(
     deps: Deps,
     pair_contract: &Addr,
     ask_asset: &Asset,
+    offer_asset_info: Option<AssetInfo>,
     block_time: u64,
 ) -> StdResult<ReverseSimulationResponse> {
     deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: pair_contract.to_string(),
         msg: to_binary(&PairQueryMsg::ReverseSimulation {
             ask_asset: ask_asset.clone(),
+            offer_asset_info,
             block_time,
         })?,
     }))
@@ -4,6 +4,9 @@ export const configDefault: Config = {
             owner: '',
             pair_code_id: 0,
             token_code_id: 0,
+            min_swap_fee: '0.001',
+            max_swap_fee: '0.01',
+            protocol_fee_share: '0',
         }
     },
     tokenConfig: {
@@ -52,7 +55,9 @@ export const configDefault: Config = {
             ],
             end_time: 0,
             start_time: 0,
-            token_code_id: 0
+            token_code_id: 0,
+            owner: '',
+            swap_fee: '0.0015'
         }
     },
 }
@@ -96,6 +96,7 @@ async function setupAstroportPair(cl: Client, cfg: Config) {
         cfg.astroportPairConfig.configInitMsg.end_time = Math.round(currTime) + 1000;
         cfg.astroportPairConfig.configInitMsg.start_time = Math.round(currTime);
         cfg.astroportPairConfig.configInitMsg.token_code_id = networkConfig.astroport_lbp_token.ID;
+        cfg.astroportPairConfig.configInitMsg.owner = cl.wallet.key.accAddress;
 
         networkConfig.astroport_lbp_pair.Addr = await instantiateContract(
             cl.terra,
@@ -117,6 +118,7 @@ async function setupAstroportFactory(cl: Client, cfg: Config) {
         cfg.astroportFactoryConfig.configInitMsg.owner = process.env.FACTORY_OWNER! || cl.wallet.key.accAddress;
         cfg.astroportFactoryConfig.configInitMsg.token_code_id = networkConfig.astroport_lbp_token.ID;
         cfg.astroportFactoryConfig.configInitMsg.pair_code_id = networkConfig.astroport_lbp_pair.ID;
+        cfg.astroportFactoryConfig.configInitMsg.fee_address = process.env.FACTORY_FEE_ADDRESS || undefined;
 
         networkConfig.astroport_lbp_factory.Addr = await instantiateContract(
             cl.terra,
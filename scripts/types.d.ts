@@ -3,6 +3,10 @@ interface AstroportFactoryConfig {
         owner: string,
         pair_code_id: number,
         token_code_id: number,
+        min_swap_fee: string,
+        max_swap_fee: string,
+        fee_address?: string,
+        protocol_fee_share: string,
     }
 }
 
@@ -37,7 +41,8 @@ interface AstroportPairConfig {
         end_time: number,
         start_time: number,
         token_code_id: number,
-
+        owner: string,
+        swap_fee: string,
     }
 }
 